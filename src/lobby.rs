@@ -1,6 +1,8 @@
 pub trait Game {
     type Setup: GameSetup;
     type Api: GameApi;
+    // Choices made by each seat before starting, like an ancestry
+    type SeatOption;
 }
 
 pub trait GameSetup {
@@ -30,6 +32,7 @@ pub struct GameToken(String);
 
 pub trait Lobby<G: Game> {
     fn set_setup(&mut self, setup: G::Setup);
+    fn set_seat_option(&mut self, option: G::SeatOption);
     fn toggle_ready(&mut self);
     fn start(&mut self);
     fn state(&self) -> LobbyState<G>;
@@ -38,6 +41,7 @@ pub trait Lobby<G: Game> {
 pub struct LobbyState<G: Game> {
     pub players: Vec<PlayerInfo>,
     pub ready: Vec<bool>,
+    pub seat_options: Vec<G::SeatOption>,
     pub admin: usize,
     pub started: bool,
     pub setup: G::Setup,
@@ -3,11 +3,13 @@ extern crate serde;
 
 mod model;
 mod smallrng;
+#[cfg(test)]
+mod smallrng_test;
 mod tui;
 mod lobby;
 
 mod player_api_tui {
-    use crate::model::api::{Api, Board};
+    use crate::model::api::{Api, PlayerAction};
     use crate::tui::*;
 
     pub struct PlayerViewTui<A: Api> {
@@ -28,6 +30,14 @@ mod player_api_tui {
             }
         }
 
+        pub fn api(&self) -> &A {
+            &self.api
+        }
+
+        pub fn api_mut(&mut self) -> &mut A {
+            &mut self.api
+        }
+
         fn column_len(&self, column: usize) -> usize {
             1
         }
@@ -59,6 +69,38 @@ mod player_api_tui {
                             self.current_column -= 1;
                         }
                     }
+                    '1'..='9' => {
+                        // while drafting, digits pick from the pool
+                        self.api.poll_deltas();
+                        if self.api.get_board().is_drafting() {
+                            let pick = c.to_digit(10).unwrap() as usize - 1;
+                            let _ = self.api.do_action(PlayerAction::DraftPick(pick));
+                        }
+                    }
+                    'p' => {
+                        let _ = self.api.do_action(PlayerAction::PlayAll);
+                    }
+                    'a' => {
+                        let _ = self
+                            .api
+                            .do_action(PlayerAction::ActivateAllAutomaticAbilities);
+                    }
+                    'x' => {
+                        // attack the next opponent still in the game
+                        self.api.poll_deltas();
+                        let board = self.api.get_board();
+                        let target = (1..board.players)
+                            .map(|i| (board.you + i) % board.players)
+                            .find(|p| board.mats[*p].lives > 0);
+                        if let Some(target) = target {
+                            let _ = self
+                                .api
+                                .do_action(PlayerAction::AttackWithEverything(target));
+                        }
+                    }
+                    'Q' => {
+                        let _ = self.api.do_action(PlayerAction::Concede);
+                    }
                     _ => println!("Unknown key {}", c),
                 },
                 _ => (),
@@ -75,21 +117,325 @@ mod player_api_tui {
     }
 }
 
+mod puzzle_tui {
+    use crate::model::puzzle::PuzzleApi;
+    use crate::player_api_tui::PlayerViewTui;
+    use crate::tui::*;
+
+    // Same controls as a game, plus '?' for a hint and 'r' to start over
+    pub struct PuzzleTui {
+        view: PlayerViewTui<PuzzleApi>,
+    }
+
+    impl PuzzleTui {
+        pub fn new(api: PuzzleApi) -> Self {
+            PuzzleTui {
+                view: PlayerViewTui::new(api),
+            }
+        }
+    }
+
+    impl Tui for PuzzleTui {
+        fn on_event(&mut self, event: Event) {
+            match event {
+                Event::Character('?') => {
+                    let api = self.view.api();
+                    match api.hint() {
+                        _ if api.is_solved() => println!("Already solved"),
+                        Ok(Some(action)) => println!("Try {:?}", action),
+                        Ok(None) => println!("No way to win from here, press r to start over"),
+                        Err(e) => println!("{}", e),
+                    }
+                }
+                Event::Character('r') => self.view.api_mut().restart(),
+                event => {
+                    self.view.on_event(event);
+                    if self.view.api().is_solved() {
+                        println!("Solved!");
+                    }
+                }
+            }
+        }
+        fn draw(&mut self, row: usize, col: usize) -> Result<Vec<Draw>, ()> {
+            self.view.draw(row, col)
+        }
+    }
+}
+
+mod replay_tui {
+    use crate::model::api::Board;
+    use crate::model::master::MasterBoard;
+    use crate::model::replay::{Perspective, Replay};
+    use crate::tui::*;
+
+    // j/k step by delta, J/K by action, n/p by turn, a number then g jumps
+    // to that turn and v switches between the players and everybody
+    pub struct ReplayTui {
+        replay: Replay,
+        number: usize,
+    }
+
+    impl ReplayTui {
+        pub fn new(replay: Replay) -> Self {
+            ReplayTui { replay, number: 0 }
+        }
+    }
+
+    impl Tui for ReplayTui {
+        fn on_event(&mut self, event: Event) {
+            let replay = &mut self.replay;
+            let result = match event {
+                Event::Character('j') => replay.step_delta(true),
+                Event::Character('k') => replay.step_delta(false),
+                Event::Character('J') => replay.step_action(true),
+                Event::Character('K') => replay.step_action(false),
+                Event::Character('n') => replay.step_turn(true),
+                Event::Character('p') => replay.step_turn(false),
+                Event::Character(c @ '0'..='9') => {
+                    self.number = self.number * 10 + c.to_digit(10).unwrap() as usize;
+                    return;
+                }
+                Event::Character('g') => replay.jump_to_turn(self.number),
+                Event::Character('v') => {
+                    let players = replay.master().players;
+                    replay.perspective = match replay.perspective {
+                        Perspective::Omniscient => Perspective::Player(0),
+                        Perspective::Player(p) if p + 1 < players => Perspective::Player(p + 1),
+                        Perspective::Player(_) => Perspective::Omniscient,
+                    };
+                    Ok(())
+                }
+                _ => Ok(()),
+            };
+            self.number = 0;
+            if let Err(e) = result {
+                println!("{}", e);
+            }
+        }
+
+        fn draw(&mut self, _row: usize, _col: usize) -> Result<Vec<Draw>, ()> {
+            let replay = &self.replay;
+            let mut lines = vec![format!(
+                "Turn {}/{}  step {}/{}  viewing {:?}",
+                replay.turn(),
+                replay.turns(),
+                replay.position(),
+                replay.len(),
+                replay.perspective
+            )];
+            if let Some(step) = replay.current() {
+                lines.push(format!(
+                    "Player {} did {:?}",
+                    step.player + 1,
+                    step.declared
+                ));
+                lines.push(format!("  {:?}", step.delta));
+            }
+            lines.push(format!("Shop: {:?}", replay.master().shop));
+            lines.push("".into());
+            match replay.perspective {
+                Perspective::Player(p) => lines.append(&mut describe_mats(replay.board(p))),
+                Perspective::Omniscient => lines.append(&mut describe_master(replay.master())),
+            }
+            Ok(std::iter::once(Draw::Clear)
+                .chain(
+                    lines
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, line)| Draw::Print(i, 0, line)),
+                )
+                .collect())
+        }
+    }
+
+    // The hands of everybody but the board owner are only counted
+    fn describe_mats(board: &Board) -> Vec<String> {
+        let mut lines = vec![format!("Seen by player {}", board.you + 1)];
+        for (p, mat) in board.mats.iter().enumerate() {
+            let hand = if p == board.you {
+                format!("{:?}", board.your_hand)
+            } else {
+                format!("{} cards", mat.hand)
+            };
+            lines.push(format!(
+                "  {} lives {} combat {} gold {} deck {} hand {}",
+                mat.name, mat.lives, mat.combat, mat.gold, mat.deck, hand
+            ));
+            let field: Vec<_> = mat.field.iter().map(|cif| &cif.card).collect();
+            lines.push(format!("    field {:?} discard {:?}", field, mat.discard));
+        }
+        lines
+    }
+
+    // Nothing is hidden, decks are listed from the bottom
+    fn describe_master(board: &MasterBoard) -> Vec<String> {
+        let mut lines = vec![format!("Shop deck {:?}", board.shop_deck)];
+        for mat in board.mats.iter() {
+            lines.push(format!(
+                "  {} lives {} combat {} gold {} hand {:?}",
+                mat.name, mat.lives, mat.combat, mat.gold, mat.hand
+            ));
+            let field: Vec<_> = mat.field.iter().map(|cif| &cif.card).collect();
+            lines.push(format!("    field {:?} discard {:?}", field, mat.discard));
+            lines.push(format!("    deck {:?}", mat.deck));
+        }
+        lines
+    }
+}
+
+use model::analysis::Analysis;
 use model::api::{Api, PlayerAction};
-use model::local::LocalServer;
+use model::branches::GameTree;
+use model::journal::{Journal, SNAPSHOT_INTERVAL};
+use model::local::{LocalClient, LocalServer};
+use model::puzzle::{Puzzle, PuzzleApi};
+use model::replay::Replay;
+use model::report::Report;
+use model::version;
 use player_api_tui::PlayerViewTui;
+use puzzle_tui::PuzzleTui;
+use replay_tui::ReplayTui;
 use std::thread;
 use std::time::Duration;
 
+const USAGE: &str = "usage: heros [puzzle <file> | replay <journal> | compact <journal> \
+                     | notation <journal> | import <notation> \
+                     | report <journal> | analyze <dir> | branch <journal> <action>]";
+
 fn main() {
-    let (mut server, mut clients) = LocalServer::new(239, 2);
-    let mut player = clients.remove(0);
+    // Without arguments a game against the bot is played
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        [] => play(),
+        ["puzzle", path] => puzzle(path),
+        ["replay", path] => replay(path),
+        ["compact", path] => compact(path),
+        ["notation", path] => notation(path),
+        ["import", path] => import(path),
+        ["report", path] => report(path),
+        ["analyze", dir] => analyze(dir),
+        ["branch", path, at] => branch(path, at),
+        _ => Err(USAGE),
+    };
+    if let Err(e) = result {
+        eprintln!("heros: {}", e);
+        std::process::exit(1);
+    }
+}
+
+// heros puzzle <file> plays a puzzle instead of a game against the bot
+fn puzzle(path: &str) -> Result<(), &'static str> {
+    let text = std::fs::read_to_string(path).map_err(|_| "Could not read puzzle file")?;
+    let puzzle = Puzzle::from_text(&text)?;
+    let unique = puzzle.is_unique()?;
+    if !unique && puzzle.solve()?.is_empty() {
+        return Err("The puzzle has no solution");
+    }
+    tui::main(PuzzleTui::new(PuzzleApi::new(puzzle)));
+    if !unique {
+        println!("The puzzle has more than one solution");
+    }
+    Ok(())
+}
+
+// heros replay <journal> steps through a recorded game
+fn replay(path: &str) -> Result<(), &'static str> {
+    let replay = Replay::new(&Journal::load(path)?)?;
+    if replay.is_empty() {
+        return Err("The journal has no actions to replay");
+    }
+    tui::main(ReplayTui::new(replay));
+    Ok(())
+}
+
+// heros compact <journal> rewrites the snapshots of a recorded game
+fn compact(path: &str) -> Result<(), &'static str> {
+    let compacted = Journal::load(path)?.compact(SNAPSHOT_INTERVAL)?;
+    compacted.save(path)?;
+    println!(
+        "{} actions, {} snapshots",
+        compacted.actions.len(),
+        compacted.snapshots.len()
+    );
+    Ok(())
+}
+
+// heros notation <journal> prints a recorded game as notation
+fn notation(path: &str) -> Result<(), &'static str> {
+    print!("{}", Journal::load(path)?.to_notation()?);
+    Ok(())
+}
+
+// heros import <notation> checks a game written as notation and prints it
+// as a journal
+fn import(path: &str) -> Result<(), &'static str> {
+    let text = std::fs::read_to_string(path).map_err(|_| "Could not read notation file")?;
+    let journal = Journal::from_notation(&text)?;
+    println!("{}", version::to_string(&journal)?);
+    Ok(())
+}
+
+// heros report <journal> prints the turn by turn summary of a recorded game
+fn report(path: &str) -> Result<(), &'static str> {
+    print!("{}", Report::new(&Journal::load(path)?)?.to_text());
+    Ok(())
+}
+
+// heros analyze <dir> prints card statistics over every journal in a directory
+fn analyze(dir: &str) -> Result<(), &'static str> {
+    print!("{}", Analysis::from_dir(dir)?.to_text());
+    Ok(())
+}
+
+// heros branch <journal> <action> plays against the bot from right before an
+// action of a recorded game, the branches are kept next to the journal
+fn branch(path: &str, at: &str) -> Result<(), &'static str> {
+    let at = at.parse().map_err(|_| "The action must be a number")?;
+    let tree_path = format!("{}.branches", path);
+    let mut tree = match std::path::Path::new(&tree_path).exists() {
+        true => GameTree::load(&tree_path)?,
+        false => GameTree::new(Journal::load(path)?),
+    };
+    let name = format!("From action {}", at);
+    let (branch, _) = tree.fork(&name, None, at)?;
+    let (server, clients) = LocalServer::from_journal(tree.journal(Some(branch))?)?;
+    let server = play_against_bot(server, clients)?;
+    tree.record(branch, server.journal())?;
+    tree.save(&tree_path)?;
+    for (branch, outcome) in tree.outcomes()? {
+        let name = match branch {
+            Some(b) => &tree.branches[b].name,
+            None => "Recorded game",
+        };
+        println!(
+            "{}: {} actions, winner {:?}, lives {:?}",
+            name, outcome.actions, outcome.winner, outcome.lives
+        );
+    }
+    Ok(())
+}
+
+fn play() -> Result<(), &'static str> {
+    let (mut server, clients) = LocalServer::new(239, 2)?;
+    server.record_to("last_game.json");
+    play_against_bot(server, clients)?;
+    println!("{}", tui::CARD_EXAMPLE);
+    Ok(())
+}
+
+// The first client is played from the terminal and the second one by the
+// bot, the server is handed back once the game is over
+fn play_against_bot(
+    mut server: LocalServer,
+    mut clients: Vec<LocalClient>,
+) -> Result<LocalServer, &'static str> {
+    let player = clients.remove(0);
     let mut bot = clients.remove(0);
 
-    thread::spawn(move || loop {
-        if server.process_action().is_err() {
-            break;
-        }
+    let server = thread::spawn(move || {
+        while server.process_action().is_ok() {}
+        server
     });
 
     thread::spawn(move || {
@@ -100,6 +446,14 @@ fn main() {
             thread::sleep(Duration::from_millis(200));
             bot.poll_deltas();
             if bot.get_board().current_player == you {
+                if bot.get_board().is_drafting() {
+                    // take the most expensive card available
+                    let pick = (0..bot.get_board().draft_pool.len())
+                        .max_by_key(|i| bot.get_board().draft_pool[*i].cost())
+                        .unwrap();
+                    bot.do_action(PlayerAction::DraftPick(pick)).expect("Oh no");
+                    continue;
+                }
                 for _ in 0..bot.get_board().mats[you].must_discard {
                     bot.do_action(PlayerAction::Discard(0)).expect("Oh no");
                 }
@@ -111,5 +465,6 @@ fn main() {
     let pvtui = PlayerViewTui::new(player);
     tui::main(pvtui);
 
-    println!("{}", tui::CARD_EXAMPLE);
+    // Leaving the game concedes it, which ends it for the server too
+    server.join().map_err(|_| "The server has died")
 }
@@ -0,0 +1,312 @@
+use super::{
+    api::{BoardDelta, Player, PlayerAction},
+    cards::{Card, Faction},
+    journal::Journal,
+    master::MasterBoard,
+};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+// Other cards of the same faction a buyer must end the game with to count as allied
+const ALLIED: usize = 2;
+
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct CardStats {
+    pub card: Card,
+    pub bought: usize,
+    // Games with a winner where some player bought the card, and how many of those players won
+    pub buyers: usize,
+    pub buyer_wins: usize,
+    pub total_buy_turn: usize,
+    // Times the card could have been bought instead of the one that was
+    pub passed: usize,
+    // Sum over purchases of the best win rate among the affordable alternatives
+    pub total_alternative_win_rate: f64,
+    pub alternatives: usize,
+    // Buyers that ended with at least ALLIED other cards of the faction, and their wins
+    pub allied_buyers: usize,
+    pub allied_wins: usize,
+}
+
+impl CardStats {
+    fn new(card: Card) -> CardStats {
+        CardStats {
+            card,
+            bought: 0,
+            buyers: 0,
+            buyer_wins: 0,
+            total_buy_turn: 0,
+            passed: 0,
+            total_alternative_win_rate: 0.0,
+            alternatives: 0,
+            allied_buyers: 0,
+            allied_wins: 0,
+        }
+    }
+
+    pub fn win_rate(&self) -> Option<f64> {
+        rate(self.buyer_wins, self.buyers)
+    }
+
+    pub fn average_buy_turn(&self) -> Option<f64> {
+        match self.bought {
+            0 => None,
+            n => Some(self.total_buy_turn as f64 / n as f64),
+        }
+    }
+
+    // How much better the best alternative did on average, negative when the card was the better pick
+    pub fn opportunity_cost(&self) -> Option<f64> {
+        match (self.alternatives, self.win_rate()) {
+            (0, _) | (_, None) => None,
+            (n, Some(own)) => Some(self.total_alternative_win_rate / n as f64 - own),
+        }
+    }
+
+    pub fn allied_win_rate(&self) -> Option<f64> {
+        rate(self.allied_wins, self.allied_buyers)
+    }
+
+    pub fn lone_win_rate(&self) -> Option<f64> {
+        rate(
+            self.buyer_wins - self.allied_wins,
+            self.buyers - self.allied_buyers,
+        )
+    }
+}
+
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct Analysis {
+    pub games: usize,
+    // Games that ended with a winner, the only ones counted in win rates
+    pub decided: usize,
+    pub cards: Vec<CardStats>,
+    // Players by the share of their deck taken by their main faction, in tenths, and their wins
+    pub concentration: Vec<(usize, usize)>,
+}
+
+// One purchase, kept until the winner of the game is known
+struct Purchase {
+    player: Player,
+    card: Card,
+    turn: usize,
+    alternatives: Vec<Card>,
+}
+
+impl Analysis {
+    // Every `.json` file in the directory is read as a journal
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Analysis, &'static str> {
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .map_err(|_| "Could not read journal directory")?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("json"))
+            .collect();
+        paths.sort();
+        let journals = paths
+            .iter()
+            .map(Journal::load)
+            .collect::<Result<Vec<_>, _>>()?;
+        Analysis::new(&journals)
+    }
+
+    pub fn new(journals: &[Journal]) -> Result<Analysis, &'static str> {
+        let mut analysis = Analysis {
+            games: journals.len(),
+            decided: 0,
+            cards: vec![],
+            concentration: vec![(0, 0); 11],
+        };
+        let mut games = vec![];
+        for journal in journals {
+            let (end, purchases) = purchases(journal)?;
+            let winner = match end.game_over {
+                true => end.mats.iter().position(|m| m.is_alive() && !m.eliminated),
+                false => None,
+            };
+            if let Some(winner) = winner {
+                analysis.decided += 1;
+                analysis.count_game(&end, winner, &purchases);
+            }
+            for purchase in purchases.iter() {
+                let stats = analysis.stats(&purchase.card);
+                stats.bought += 1;
+                stats.total_buy_turn += purchase.turn;
+            }
+            games.push((purchases, winner));
+        }
+
+        // Alternatives are judged by the win rates over all the games
+        let rates: Vec<(Card, f64)> = analysis
+            .cards
+            .iter()
+            .filter_map(|s| s.win_rate().map(|r| (s.card.clone(), r)))
+            .collect();
+        let rate_of = |card: &Card| rates.iter().find(|(c, _)| c == card).map(|(_, r)| *r);
+        for (purchases, _) in games.iter() {
+            for purchase in purchases.iter() {
+                let best = purchase
+                    .alternatives
+                    .iter()
+                    .filter_map(&rate_of)
+                    .fold(None, |best: Option<f64>, r| {
+                        Some(best.map_or(r, |b| b.max(r)))
+                    });
+                for alternative in purchase.alternatives.iter() {
+                    analysis.stats(alternative).passed += 1;
+                }
+                if let Some(best) = best {
+                    let stats = analysis.stats(&purchase.card);
+                    stats.total_alternative_win_rate += best;
+                    stats.alternatives += 1;
+                }
+            }
+        }
+
+        analysis.cards.sort_by(|a, b| {
+            b.win_rate()
+                .partial_cmp(&a.win_rate())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        Ok(analysis)
+    }
+
+    fn stats(&mut self, card: &Card) -> &mut CardStats {
+        match self.cards.iter().position(|s| s.card == *card) {
+            Some(i) => &mut self.cards[i],
+            None => {
+                self.cards.push(CardStats::new(card.clone()));
+                self.cards.last_mut().unwrap()
+            }
+        }
+    }
+
+    fn count_game(&mut self, end: &MasterBoard, winner: Player, purchases: &[Purchase]) {
+        for (p, mat) in end.mats.iter().enumerate() {
+            let won = (p == winner) as usize;
+            let owned: Vec<Faction> = mat
+                .deck
+                .iter()
+                .chain(mat.hand.iter())
+                .chain(mat.discard.iter())
+                .chain(mat.field.iter().map(|cif| &cif.card))
+                .map(|c| c.faction())
+                .filter(|f| *f != Faction::NoFaction)
+                .collect();
+            let count = |faction: &Faction| owned.iter().filter(|f| *f == faction).count();
+
+            if let Some(main) = owned.iter().map(count).max() {
+                let share = main * 10
+                    / (mat.deck.len() + mat.hand.len() + mat.discard.len() + mat.field.len());
+                self.concentration[share].0 += 1;
+                self.concentration[share].1 += won;
+            }
+
+            let mut seen: Vec<&Card> = vec![];
+            for purchase in purchases.iter().filter(|b| b.player == p) {
+                if seen.contains(&&purchase.card) {
+                    continue;
+                }
+                seen.push(&purchase.card);
+                let faction = purchase.card.faction();
+                let allied = faction != Faction::NoFaction && count(&faction) > ALLIED;
+                let stats = self.stats(&purchase.card);
+                stats.buyers += 1;
+                stats.buyer_wins += won;
+                if allied {
+                    stats.allied_buyers += 1;
+                    stats.allied_wins += won;
+                }
+            }
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let percent = |r: Option<f64>| match r {
+            Some(r) => format!("{:.0}%", r * 100.0),
+            None => "-".into(),
+        };
+        let mut lines = vec![
+            format!("{} games, {} with a winner", self.games, self.decided),
+            "".into(),
+            format!(
+                "{:<24} {:>6} {:>6} {:>8} {:>6} {:>8} {:>7} {:>7}",
+                "Card", "Bought", "Wins", "Turn", "Passed", "Cost", "Allied", "Lone"
+            ),
+        ];
+        for stats in self.cards.iter() {
+            lines.push(format!(
+                "{:<24} {:>6} {:>6} {:>8} {:>6} {:>8} {:>7} {:>7}",
+                format!("{:?}", stats.card),
+                stats.bought,
+                percent(stats.win_rate()),
+                stats
+                    .average_buy_turn()
+                    .map_or("-".into(), |t| format!("{:.1}", t)),
+                stats.passed,
+                stats
+                    .opportunity_cost()
+                    .map_or("-".into(), |c| format!("{:+.2}", c)),
+                percent(stats.allied_win_rate()),
+                percent(stats.lone_win_rate()),
+            ));
+        }
+        lines.push("".into());
+        lines.push("Main faction share  Players  Wins".into());
+        for (tenth, (players, wins)) in self.concentration.iter().enumerate() {
+            if *players > 0 {
+                lines.push(format!(
+                    "{:>17}%  {:>7}  {}",
+                    tenth * 10,
+                    players,
+                    percent(rate(*wins, *players))
+                ));
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+
+    pub fn to_json(&self) -> Result<String, &'static str> {
+        serde_json::to_string_pretty(self).map_err(|_| "Could not serialize analysis")
+    }
+}
+
+fn rate(wins: usize, games: usize) -> Option<f64> {
+    match games {
+        0 => None,
+        n => Some(wins as f64 / n as f64),
+    }
+}
+
+// Replays the game, noting every purchase from the shop with what else was affordable
+fn purchases(journal: &Journal) -> Result<(MasterBoard, Vec<Purchase>), &'static str> {
+    let mut state = journal.start()?;
+    let mut purchases = vec![];
+    let mut turn = 1;
+    for (player, action) in journal.actions.iter() {
+        let drafting = state.is_drafting();
+        if let PlayerAction::PurchaseFromShop(i) = action {
+            let mat = &state.mats[*player];
+            if let Some(card) = state.shop.get(*i) {
+                purchases.push(Purchase {
+                    player: *player,
+                    card: card.clone(),
+                    turn,
+                    alternatives: state
+                        .shop
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, c)| j != i && c.effective_cost(&mat.field) <= mat.gold)
+                        .map(|(_, c)| c.clone())
+                        .collect(),
+                });
+            }
+        }
+        for delta in state.do_action_as(*player, action.clone())? {
+            if let (BoardDelta::ChangeCurrentPlayer(_), false) = (delta, drafting) {
+                turn += 1;
+            }
+        }
+    }
+    Ok((state, purchases))
+}
@@ -0,0 +1,63 @@
+use super::{analysis::*, cards::*, journal::*, recorded_test::*};
+use crate::smallrng::*;
+use std::fs;
+
+// Whole games where the first player is left to the rng
+fn bot_games() -> Result<Vec<Journal>, &'static str> {
+    [0, 14279, 777]
+        .iter()
+        .map(|seed| {
+            let mut setup = Setup::base();
+            setup.rules.starting_lives = vec![25, 25];
+            recorded_game(setup, SRng::new(*seed), Strategy::Priciest, None)
+        })
+        .collect()
+}
+
+#[test]
+fn test_analyze_dir() -> Result<(), &'static str> {
+    let dir = std::env::temp_dir().join(format!("heros-analysis-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for (i, journal) in bot_games()?.iter().enumerate() {
+        journal.save(dir.join(format!("{}.json", i)))?;
+    }
+    // Anything else in the directory is skipped
+    fs::write(dir.join("notes.txt"), "not a journal").unwrap();
+    let analysis = Analysis::from_dir(&dir);
+    fs::remove_dir_all(&dir).unwrap();
+    let analysis = analysis?;
+
+    assert_eq!(analysis.games, 3);
+    assert_eq!(analysis.decided, 3);
+    let players: usize = analysis.concentration.iter().map(|(p, _)| p).sum();
+    let wins: usize = analysis.concentration.iter().map(|(_, w)| w).sum();
+    assert_eq!((players, wins), (6, 3));
+    Ok(())
+}
+
+#[test]
+fn test_card_stats() -> Result<(), &'static str> {
+    let analysis = Analysis::new(&bot_games()?)?;
+    assert!(!analysis.cards.is_empty());
+    for stats in analysis.cards.iter() {
+        assert!(stats.buyers <= stats.bought || stats.bought == 0);
+        assert!(stats.buyer_wins <= stats.buyers);
+        assert!(stats.allied_buyers <= stats.buyers);
+        if let Some(turn) = stats.average_buy_turn() {
+            assert!(turn >= 1.0);
+        }
+    }
+    let rates: Vec<f64> = analysis.cards.iter().filter_map(|s| s.win_rate()).collect();
+    assert!(rates.windows(2).all(|w| w[0] >= w[1]));
+    assert!(analysis.cards.iter().any(|s| s.passed > 0));
+    Ok(())
+}
+
+#[test]
+fn test_analysis_formats() -> Result<(), &'static str> {
+    let analysis = Analysis::new(&bot_games()?)?;
+    assert!(analysis.to_text().starts_with("3 games, 3 with a winner"));
+    let json: serde_json::Value = serde_json::from_str(&analysis.to_json()?).unwrap();
+    assert_eq!(json["games"], 3);
+    Ok(())
+}
@@ -1,12 +1,44 @@
-use super::cards::*;
-use serde::{Deserialize, Serialize};
+use super::{cards::*, hash::StateHasher};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt::Debug;
 use std::ops::AddAssign;
 
-pub trait Api {
+pub trait Api<C: GameCard = Card> {
     type Error;
-    fn get_board<'a>(&'a self) -> &'a Board;
+    fn get_board<'a>(&'a self) -> &'a Board<C>;
     fn do_action(&mut self, action: PlayerAction) -> Result<(), Self::Error>;
-    fn poll_deltas(&mut self) -> Vec<BoardDelta>;
+    fn poll_deltas(&mut self) -> Vec<BoardDelta<C>>;
+}
+
+// What the board needs to know about the cards of a ruleset
+pub trait GameCard: Clone + PartialEq + Debug + Serialize + DeserializeOwned {
+    // Chosen for each seat before the game, shown on their mat
+    type SeatOption: Clone + PartialEq + Debug + Serialize + DeserializeOwned;
+    type Trigger: Clone + Debug + Serialize + DeserializeOwned;
+
+    // The card always available for purchase outside of the shop
+    fn supply_card() -> Self;
+
+    // Names that go into state hashes, unlike Debug output they never change
+    fn id(&self) -> &'static str;
+    fn seat_option_id(option: &Self::SeatOption) -> &'static str;
+}
+
+impl GameCard for Card {
+    type SeatOption = Ancestry;
+    type Trigger = Trigger;
+
+    fn supply_card() -> Card {
+        Card::FireGem
+    }
+
+    fn id(&self) -> &'static str {
+        Card::id(self)
+    }
+
+    fn seat_option_id(ancestry: &Ancestry) -> &'static str {
+        ancestry.id()
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -21,6 +53,33 @@ pub enum PlayerAction {
     Discard(usize),
     PurchaseFireGem,
     EndTurn,
+    DraftPick(usize),
+    // Expanded by the server into the single actions they stand for
+    PlayAll,
+    ActivateAllAutomaticAbilities,
+    AttackWithEverything(Player),
+    // Allowed at any time, even out of turn
+    Concede,
+    // Sent by the server when the current player runs out of time
+    TimedOut,
+}
+
+// What the server sends to each client
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(bound = "")]
+pub enum ServerMessage<C: GameCard = Card> {
+    Delta(BoardDelta<C>),
+    // Hash of the client view after all the deltas of an action
+    StateHash(u64),
+    // The whole client view, to recover from a desync
+    Snapshot(Board<C>),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum ClientMessage {
+    Action(PlayerAction),
+    // Answered with a Snapshot
+    RequestSnapshot,
 }
 
 pub type Player = usize;
@@ -44,12 +103,15 @@ pub enum Location {
     Sacrifice,
     Shop,
     ShopDeck,
+    // The supply pile, fire gems in Hero Realms
     FireGems,
+    DraftPool,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
-pub enum BoardDelta {
-    Move(Location, usize, Location, Option<Card>),
+#[serde(bound = "")]
+pub enum BoardDelta<C: GameCard = Card> {
+    Move(Location, usize, Location, Option<C>),
     DecreaseDiscardAmount(Player, usize),
     DecreaseHealth(Player, usize),
     DecreaseCombat(Player, usize),
@@ -64,33 +126,106 @@ pub enum BoardDelta {
     // These do not actually change the board but are here
     // so each player can know what the other one was doing.
     PlayerDeclaredAction(PlayerAction),
+    TriggeredAbility(Player, C, C::Trigger),
     ShuffleDeck(Player),
+    // Sent once the eliminated player's cards are cleaned up
+    PlayerEliminated(Player),
+    GameOver,
+}
+
+// Everything an accepted action did, each delta along with its cause
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ActionOutcome {
+    // Counts the actions accepted since the start of the game
+    pub action_id: usize,
+    pub player: Player,
+    pub action: PlayerAction,
+    pub entries: Vec<Entry>,
+}
+
+impl ActionOutcome {
+    pub fn deltas(self) -> Vec<BoardDelta> {
+        self.entries.into_iter().map(|e| e.delta).collect()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Entry {
+    pub cause: Cause,
+    pub delta: BoardDelta,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum Cause {
+    // The single action itself, like moving the played card or paying for a
+    // purchase. Batch actions list the ones they expanded to
+    Action(PlayerAction),
+    // A card ability, with the effect being resolved and how many effects of
+    // the ability were resolved before it, none for using up the ability
+    Ability {
+        card: Card,
+        kind: AbilityKind,
+        effect: Option<(usize, Effect)>,
+    },
+    Rule(Rule),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum AbilityKind {
+    Primary,
+    Expend,
+    Ally,
+    Sacrifice,
+    Triggered(Trigger),
+}
+
+// Things the game does on its own
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Rule {
+    // Dealing once the draft is over
+    Draft,
+    // Clearing the mat and passing the turn
+    EndOfTurn,
+    // Drawing the next hand, reshuffling the discard if needed
+    NewHand,
+    RefillShop,
+    Timeout,
+    Elimination(Player),
     GameOver,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
-pub struct Board {
-    pub shop: Vec<Card>,
+#[serde(bound = "")]
+pub struct Board<C: GameCard = Card> {
+    pub shop: Vec<C>,
     pub shop_deck: usize,
+    // Only holds cards while drafting, before the game starts
+    pub draft_pool: Vec<C>,
     pub gems: usize,
-    pub sacrificed: Vec<Card>,
+    pub sacrificed: Vec<C>,
 
     pub current_player: Player,
     pub players: usize,
-    pub mats: Vec<Mat>,
+    pub mats: Vec<Mat<C>>,
 
     pub you: Player,
-    pub your_hand: Vec<Card>,
+    pub your_hand: Vec<C>,
     pub game_over: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
-pub struct Mat {
-    pub field: Vec<CardInField>,
+#[serde(bound = "")]
+pub struct Mat<C: GameCard = Card> {
+    pub field: Vec<CardInField<C>>,
     pub hand: usize,
     pub name: String,
-    pub discard: Vec<Card>,
+    pub ancestry: C::SeatOption,
+    pub discard: Vec<C>,
     pub deck: usize,
+    // Cards everybody has seen going on top of the deck, the last one is on top
+    pub known_deck: Vec<C>,
+    // Cards everybody knows are in the hand, like the ones drawn from `known_deck`
+    pub known_hand: Vec<C>,
     pub lives: usize,
     pub combat: usize,
     pub gold: usize,
@@ -98,27 +233,76 @@ pub struct Mat {
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
-pub enum BoardDeltaError {
-    CardMismatch(Card, Option<Card>),
+#[serde(bound = "")]
+pub enum BoardDeltaError<C: GameCard = Card> {
+    CardMismatch(C, Option<C>),
     MissingCard(Location),
     WrongSourceLocation,
     StatUnderflow,
 }
 
-impl Board {
-    pub fn apply(&mut self, delta: BoardDelta) -> Result<(), BoardDeltaError> {
+impl<C: GameCard> Board<C> {
+    pub fn is_drafting(&self) -> bool {
+        !self.draft_pool.is_empty()
+    }
+
+    // Same on the server and on every client that applied the same deltas
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher
+            .cards(&self.shop)
+            .usize(self.shop_deck)
+            .cards(&self.draft_pool)
+            .usize(self.gems)
+            .cards(&self.sacrificed)
+            .usize(self.current_player)
+            .usize(self.players)
+            .usize(self.you)
+            .cards(&self.your_hand)
+            .bool(self.game_over);
+        for mat in self.mats.iter() {
+            hasher.usize(mat.field.len());
+            for cif in mat.field.iter() {
+                hasher
+                    .card(&cif.card)
+                    .bool(cif.expend_ability_used)
+                    .bool(cif.ally_ability_used);
+            }
+            hasher
+                .usize(mat.hand)
+                .str(&mat.name)
+                .str(C::seat_option_id(&mat.ancestry))
+                .cards(&mat.discard)
+                .usize(mat.deck)
+                .cards(&mat.known_deck)
+                .cards(&mat.known_hand)
+                .usize(mat.lives)
+                .usize(mat.combat)
+                .usize(mat.gold)
+                .usize(mat.must_discard);
+        }
+        hasher.finish()
+    }
+
+    pub fn apply(&mut self, delta: BoardDelta<C>) -> Result<(), BoardDeltaError<C>> {
         match delta {
             BoardDelta::Move(from, index, to, card) => {
-                let mut removed_card: Option<Card> = None;
+                let mut removed_card: Option<C> = None;
+                let mut known_card: Option<C> = None;
                 match from {
                     Location::Deck(player) => {
                         self.mats[player].deck -= 1;
+                        known_card = self.mats[player].known_deck.pop();
                     }
                     Location::Hand(player) => {
                         self.mats[player].hand -= 1;
                         if player == self.you {
                             removed_card = Some(self.your_hand.remove(index));
                         }
+                        let known_hand = &mut self.mats[player].known_hand;
+                        if let Some(i) = known_hand.iter().position(|c| Some(c) == card.as_ref()) {
+                            known_hand.remove(i);
+                        }
                     }
                     Location::Field(player) => {
                         removed_card = Some(self.mats[player].field.remove(index).card);
@@ -133,20 +317,51 @@ impl Board {
                     Location::ShopDeck => self.shop_deck -= 1,
                     Location::FireGems => {
                         self.gems -= 1;
-                        removed_card = Some(Card::FireGem);
+                        removed_card = Some(C::supply_card());
+                    }
+                    Location::DraftPool => {
+                        removed_card = Some(self.draft_pool.remove(index));
                     }
                 };
 
-                if removed_card.is_some() && removed_card != card {
-                    return Err(BoardDeltaError::CardMismatch(removed_card.unwrap(), card));
+                if let Some(removed) = removed_card {
+                    if Some(&removed) != card.as_ref() {
+                        return Err(BoardDeltaError::CardMismatch(removed, card));
+                    }
+                }
+                if let (Some(known), Some(moved)) = (&known_card, &card) {
+                    if known != moved {
+                        return Err(BoardDeltaError::CardMismatch(known.clone(), card));
+                    }
                 }
 
+                // Whether every player knows which card is moving
+                let public = match from {
+                    Location::Deck(_) => known_card.is_some(),
+                    Location::Hand(_) | Location::ShopDeck => false,
+                    _ => true,
+                };
+                let card = card.or(known_card);
+
                 match to {
                     Location::Deck(player) => {
                         self.mats[player].deck += 1;
+                        if public {
+                            self.mats[player]
+                                .known_deck
+                                .push(card.ok_or(BoardDeltaError::MissingCard(to))?);
+                        } else {
+                            self.mats[player].known_deck.clear();
+                        }
                     }
                     Location::Hand(player) => {
                         self.mats[player].hand += 1;
+                        if public {
+                            self.mats[player].known_hand.push(
+                                card.clone()
+                                    .ok_or(BoardDeltaError::MissingCard(to.clone()))?,
+                            );
+                        }
                         if player == self.you {
                             self.your_hand
                                 .push(card.ok_or(BoardDeltaError::MissingCard(to))?);
@@ -168,10 +383,13 @@ impl Board {
                     Location::ShopDeck => self.shop_deck += 1,
                     Location::FireGems => {
                         self.gems += 1;
-                        if card != Some(Card::FireGem) {
-                            return Err(BoardDeltaError::CardMismatch(Card::FireGem, card));
+                        if card != Some(C::supply_card()) {
+                            return Err(BoardDeltaError::CardMismatch(C::supply_card(), card));
                         }
                     }
+                    Location::DraftPool => self
+                        .draft_pool
+                        .push(card.ok_or(BoardDeltaError::MissingCard(to))?),
                 }
             }
             BoardDelta::IncreaseHealth(player, amount) => self.mats[player].lives += amount,
@@ -197,7 +415,9 @@ impl Board {
                 self.current_player = player;
             }
             BoardDelta::PlayerDeclaredAction(_action) => {}
-            BoardDelta::ShuffleDeck(_player) => {}
+            BoardDelta::TriggeredAbility(_player, _card, _trigger) => {}
+            BoardDelta::ShuffleDeck(player) => self.mats[player].known_deck.clear(),
+            BoardDelta::PlayerEliminated(_player) => {}
             BoardDelta::SetExpendAbilityUsed(player, index, value) => {
                 self.mats[player].field[index].expend_ability_used = value;
             }
@@ -213,14 +433,14 @@ impl Board {
     }
 }
 
-impl AddAssign<BoardDelta> for Board {
-    fn add_assign(&mut self, delta: BoardDelta) {
+impl<C: GameCard> AddAssign<BoardDelta<C>> for Board<C> {
+    fn add_assign(&mut self, delta: BoardDelta<C>) {
         self.apply(delta);
     }
 }
 
-impl AddAssign<Vec<BoardDelta>> for Board {
-    fn add_assign(&mut self, deltas: Vec<BoardDelta>) {
+impl<C: GameCard> AddAssign<Vec<BoardDelta<C>>> for Board<C> {
+    fn add_assign(&mut self, deltas: Vec<BoardDelta<C>>) {
         for delta in deltas {
             self.apply(delta);
         }
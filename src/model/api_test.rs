@@ -4,6 +4,7 @@ fn create_test_board() -> Board {
     Board {
         shop: vec![Card::Bribe, Card::DarkEnergy, Card::Spark],
         shop_deck: 5,
+        draft_pool: vec![],
         gems: 16,
         sacrificed: vec![],
         game_over: false,
@@ -13,10 +14,13 @@ fn create_test_board() -> Board {
         mats: vec![
             Mat {
                 name: "Ana".into(),
+                ancestry: Ancestry::Human,
                 field: vec![],
                 hand: 3,
                 discard: vec![],
                 deck: 7,
+                known_deck: vec![],
+                known_hand: vec![],
                 lives: 50,
                 combat: 0,
                 gold: 0,
@@ -24,10 +28,13 @@ fn create_test_board() -> Board {
             },
             Mat {
                 name: "Bob".into(),
+                ancestry: Ancestry::Elf,
                 field: vec![],
                 hand: 4,
                 discard: vec![],
                 deck: 6,
+                known_deck: vec![],
+                known_hand: vec![],
                 lives: 50,
                 combat: 0,
                 gold: 0,
@@ -0,0 +1,153 @@
+use super::{
+    api::{BoardDelta, Player, PlayerAction},
+    journal::Journal,
+    master::MasterBoard,
+    version,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// Alternative line played from some point of another one
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Branch {
+    pub name: String,
+    // None when forked from the recorded game itself
+    pub parent: Option<usize>,
+    // Amount of actions of the parent line kept before diverging
+    pub at: usize,
+    // Only what was played after the fork
+    pub actions: Vec<(Player, PlayerAction)>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Outcome {
+    pub actions: usize,
+    pub current_player: Player,
+    pub game_over: bool,
+    pub winner: Option<Player>,
+    pub lives: Vec<usize>,
+}
+
+// A recorded game along with every "what if" played from it
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct GameTree {
+    pub root: Journal,
+    pub branches: Vec<Branch>,
+}
+
+impl GameTree {
+    pub fn new(root: Journal) -> GameTree {
+        GameTree {
+            root,
+            branches: vec![],
+        }
+    }
+
+    // Every action of the line from the start of the game
+    pub fn journal(&self, branch: Option<usize>) -> Result<Journal, &'static str> {
+        match branch {
+            None => Ok(self.root.clone()),
+            Some(i) => {
+                let b = self.branches.get(i).ok_or("No such branch")?;
+                let mut journal = self.journal(b.parent)?.truncated(b.at)?;
+                journal.actions.extend(b.actions.iter().cloned());
+                Ok(journal)
+            }
+        }
+    }
+
+    // Starts a new line right before the action `at` of the parent, the board
+    // returned is live and carries the same rng the parent had at that point
+    pub fn fork(
+        &mut self,
+        name: &str,
+        parent: Option<usize>,
+        at: usize,
+    ) -> Result<(usize, MasterBoard), &'static str> {
+        let board = self.journal(parent)?.board_at(at)?;
+        self.branches.push(Branch {
+            name: name.into(),
+            parent,
+            at,
+            actions: vec![],
+        });
+        Ok((self.branches.len() - 1, board))
+    }
+
+    // Plays on a board returned by `fork` and records the action in the branch
+    pub fn do_action_as(
+        &mut self,
+        branch: usize,
+        board: &mut MasterBoard,
+        player: Player,
+        action: PlayerAction,
+    ) -> Result<Vec<BoardDelta>, &'static str> {
+        let b = self.branches.get_mut(branch).ok_or("No such branch")?;
+        let deltas = board.do_action_as(player, action.clone())?;
+        b.actions.push((player, action));
+        Ok(deltas)
+    }
+
+    pub fn do_action(
+        &mut self,
+        branch: usize,
+        board: &mut MasterBoard,
+        action: PlayerAction,
+    ) -> Result<Vec<BoardDelta>, &'static str> {
+        let player = board.current_player;
+        self.do_action_as(branch, board, player, action)
+    }
+
+    // Keeps what a game resumed from the branch, for instance by a server,
+    // played after the fork
+    pub fn record(&mut self, branch: usize, journal: &Journal) -> Result<(), &'static str> {
+        let line = self.journal(Some(branch))?;
+        let at = line.actions.len();
+        if journal.actions.len() < at
+            || journal.truncated(at)?.replay()?.state_hash() != line.replay()?.state_hash()
+        {
+            return Err("The game wasn't played from this branch");
+        }
+        let b = &mut self.branches[branch];
+        b.actions.extend(journal.actions[at..].iter().cloned());
+        Ok(())
+    }
+
+    // Where the line stands after its last action
+    pub fn outcome(&self, branch: Option<usize>) -> Result<Outcome, &'static str> {
+        let journal = self.journal(branch)?;
+        let board = journal.replay()?;
+        Ok(Outcome {
+            actions: journal.actions.len(),
+            current_player: board.current_player,
+            game_over: board.game_over,
+            winner: match board.game_over {
+                true => board
+                    .mats
+                    .iter()
+                    .position(|m| m.is_alive() && !m.eliminated),
+                false => None,
+            },
+            lives: board.mats.iter().map(|m| m.lives).collect(),
+        })
+    }
+
+    // The recorded game first, then every branch in the order they were made
+    pub fn outcomes(&self) -> Result<Vec<(Option<usize>, Outcome)>, &'static str> {
+        std::iter::once(None)
+            .chain((0..self.branches.len()).map(Some))
+            .map(|branch| Ok((branch, self.outcome(branch)?)))
+            .collect()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), &'static str> {
+        let data = version::to_string(self)?;
+        fs::write(path, data).map_err(|_| "Could not write game tree file")
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<GameTree, &'static str> {
+        let data = fs::read_to_string(path).map_err(|_| "Could not read game tree file")?;
+        version::from_str(&data)
+    }
+}
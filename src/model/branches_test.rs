@@ -0,0 +1,88 @@
+use super::{api::*, branches::*, journal::*, local::*, recorded_test::*};
+use crate::smallrng::*;
+
+fn recorded() -> Result<Journal, &'static str> {
+    recorded_game(setup(), SRng::new(777), Strategy::PlayAll, Some(4))
+}
+
+#[test]
+fn test_fork_and_play() -> Result<(), &'static str> {
+    // Attack instead of ending the second turn
+    let journal = recorded()?;
+    let mut tree = GameTree::new(journal.clone());
+    let (attack, mut board) = tree.fork("attack", None, 3)?;
+    assert_eq!(board.state_hash(), journal.board_at(3)?.state_hash());
+    assert_eq!(board.rng.seed(), journal.board_at(3)?.rng.seed());
+    let combat = board.mats[1].combat;
+    assert!(combat > 0);
+    tree.do_action(attack, &mut board, PlayerAction::AttackWithEverything(0))?;
+    tree.do_action(attack, &mut board, PlayerAction::EndTurn)?;
+    assert!(tree
+        .do_action(attack, &mut board, PlayerAction::AttackPlayer(0, 100))
+        .is_err());
+    assert_eq!(tree.branches[attack].actions.len(), 2);
+
+    let attacked = tree.outcome(Some(attack))?;
+    assert_eq!(attacked.lives, vec![50 - combat, 50]);
+    assert_eq!(attacked.current_player, 0);
+    assert!(!attacked.game_over);
+
+    assert!(tree.fork("too far", None, 9).is_err());
+    assert!(tree.journal(Some(7)).is_err());
+    Ok(())
+}
+
+// The recorded game, an attack at its fourth action and a concession right after
+fn tree() -> Result<GameTree, &'static str> {
+    let mut tree = GameTree::new(recorded()?);
+    let (attack, mut board) = tree.fork("attack", None, 3)?;
+    tree.do_action(attack, &mut board, PlayerAction::AttackWithEverything(0))?;
+    let (concede, mut board) = tree.fork("concede", Some(attack), 4)?;
+    tree.do_action_as(concede, &mut board, 1, PlayerAction::Concede)?;
+    Ok(tree)
+}
+
+#[test]
+fn test_branch_of_branch() -> Result<(), &'static str> {
+    let tree = tree()?;
+    let outcomes = tree.outcomes()?;
+    assert_eq!(outcomes.len(), 3);
+    let (_, ref recorded) = outcomes[0];
+    let (_, ref conceded) = outcomes[2];
+    assert_eq!(recorded.actions, 8);
+    assert_eq!(recorded.lives, vec![50, 50]);
+    assert!(conceded.game_over);
+    assert_eq!(conceded.winner, Some(0));
+    assert_eq!(tree.journal(Some(1))?.actions.len(), 5);
+    Ok(())
+}
+
+#[test]
+fn test_save_and_load_tree() -> Result<(), &'static str> {
+    let tree = tree()?;
+    let path = std::env::temp_dir().join(format!("heros-tree-{}.json", std::process::id()));
+    tree.save(&path)?;
+    let loaded = GameTree::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded?.outcomes()?, tree.outcomes()?);
+    Ok(())
+}
+
+#[test]
+fn test_play_branch_on_server() -> Result<(), &'static str> {
+    let mut tree = GameTree::new(recorded()?);
+    let (branch, board) = tree.fork("concede", None, 3)?;
+    let (mut server, mut clients) = LocalServer::from_journal(tree.journal(Some(branch))?)?;
+    assert_eq!(clients[0].get_board(), &board.scoped_to(0));
+
+    let current = board.current_player;
+    clients[current].do_action(PlayerAction::Concede)?;
+    server.process_action()?;
+    tree.record(branch, server.journal())?;
+    assert_eq!(tree.branches[branch].actions.len(), 1);
+    assert!(tree.outcome(Some(branch))?.game_over);
+
+    // Games that went another way can't be kept in the branch
+    assert!(tree.record(branch, &recorded()?).is_err());
+    Ok(())
+}
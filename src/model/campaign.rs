@@ -0,0 +1,266 @@
+use super::{
+    api::{BoardDelta, Player, PlayerAction},
+    cards::{Ancestry, Card, Loadout, Setup},
+    master::MasterBoard,
+    version,
+};
+use crate::smallrng::SRng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// The campaign player always sits on the first seat
+pub const HERO: Player = 0;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Character {
+    pub name: String,
+    pub ancestry: Ancestry,
+    pub deck: Vec<Card>,
+    pub lives: usize,
+    pub upgrades: Vec<Upgrade>,
+}
+
+impl Character {
+    pub fn new(name: String, ancestry: Ancestry, setup: &Setup) -> Character {
+        let loadout = Loadout::new(ancestry, &setup.player_deck);
+        Character {
+            name,
+            ancestry: loadout.ancestry,
+            deck: loadout.deck,
+            lives: loadout.lives,
+            upgrades: vec![],
+        }
+    }
+
+    pub fn loadout(&self) -> Loadout {
+        Loadout {
+            ancestry: self.ancestry.clone(),
+            deck: self.deck.clone(),
+            lives: self.lives,
+        }
+    }
+
+    fn gain(&mut self, reward: &Reward) {
+        match reward {
+            Reward::Card(card) => self.deck.push(card.clone()),
+            Reward::Upgrade(upgrade) => {
+                match upgrade {
+                    Upgrade::MaxLives(x) => self.lives += x,
+                    Upgrade::RemoveCard(card) => {
+                        if let Some(i) = self.deck.iter().position(|c| c == card) {
+                            self.deck.remove(i);
+                        }
+                    }
+                }
+                self.upgrades.push(upgrade.clone());
+            }
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Upgrade {
+    MaxLives(usize),
+    RemoveCard(Card),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Reward {
+    Card(Card),
+    Upgrade(Upgrade),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum VictoryCondition {
+    DefeatAll,
+    // The hero wins by being alive after that many of their turns
+    SurviveTurns(usize),
+}
+
+impl VictoryCondition {
+    // Some(true) when the hero won, Some(false) when lost, None while undecided
+    pub fn check(&self, board: &MasterBoard, hero_turns: usize) -> Option<bool> {
+        if !board.mats[HERO].is_alive() {
+            return Some(false);
+        }
+        match self {
+            VictoryCondition::DefeatAll => {
+                if board.game_over {
+                    Some(true)
+                } else {
+                    None
+                }
+            }
+            VictoryCondition::SurviveTurns(n) => {
+                if hero_turns >= *n || board.game_over {
+                    Some(true)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Encounter {
+    pub name: String,
+    pub setup: Setup,
+    pub opponents: Vec<Loadout>,
+    pub victory: VictoryCondition,
+    pub rewards: Vec<Reward>,
+    // Index of the following encounter, the campaign ends after the last one
+    pub next: Option<usize>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum CampaignState {
+    Ready(usize),
+    Playing(usize),
+    Finished,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Campaign {
+    pub encounters: Vec<Encounter>,
+    pub character: Character,
+    pub state: CampaignState,
+    pub defeats: usize,
+    pub rng: SRng,
+}
+
+impl Campaign {
+    pub fn new(encounters: Vec<Encounter>, character: Character, rng: SRng) -> Campaign {
+        Campaign {
+            encounters,
+            character,
+            state: CampaignState::Ready(0),
+            defeats: 0,
+            rng,
+        }
+    }
+
+    pub fn tutorial(character_name: String, ancestry: Ancestry, seed: u64) -> Campaign {
+        let setup = Setup::base();
+        let bandit = Loadout {
+            ancestry: Ancestry::Human,
+            deck: setup.player_deck.clone(),
+            lives: 30,
+        };
+        let mut warlord = Loadout::new(Ancestry::Orc, &setup.player_deck);
+        warlord.deck.push(Card::OrcGrunt);
+        warlord.deck.push(Card::ElvenCurse);
+        warlord.lives = 60;
+
+        let encounters = vec![
+            Encounter {
+                name: "Bandits on the road".into(),
+                setup: setup.clone(),
+                opponents: vec![bandit.clone()],
+                victory: VictoryCondition::DefeatAll,
+                rewards: vec![Reward::Card(Card::Spark)],
+                next: Some(1),
+            },
+            Encounter {
+                name: "Ambush in the woods".into(),
+                setup: setup.clone(),
+                opponents: vec![bandit.clone(), bandit],
+                victory: VictoryCondition::SurviveTurns(8),
+                rewards: vec![Reward::Upgrade(Upgrade::RemoveCard(Card::Dagger))],
+                next: Some(2),
+            },
+            Encounter {
+                name: "The warlord".into(),
+                setup,
+                opponents: vec![warlord],
+                victory: VictoryCondition::DefeatAll,
+                rewards: vec![Reward::Upgrade(Upgrade::MaxLives(5))],
+                next: None,
+            },
+        ];
+
+        let character = Character::new(character_name, ancestry, &Setup::base());
+        Campaign::new(encounters, character, SRng::new(seed))
+    }
+
+    pub fn start_encounter(&mut self) -> Result<EncounterRun, &'static str> {
+        let index = match self.state {
+            CampaignState::Ready(i) => i,
+            CampaignState::Playing(_) => return Err("An encounter is already being played"),
+            CampaignState::Finished => return Err("The campaign is over"),
+        };
+        let encounter = self.encounters.get(index).ok_or("No such encounter")?;
+
+        let mut loadouts = vec![self.character.loadout()];
+        loadouts.extend(encounter.opponents.iter().cloned());
+        let mut board = MasterBoard::with_loadouts(&encounter.setup, loadouts, self.rng.fork())?;
+        board.mats[HERO].name = self.character.name.clone();
+        for (i, mat) in board.mats.iter_mut().enumerate().skip(1) {
+            mat.name = format!("{} {}", encounter.name, i);
+        }
+
+        self.state = CampaignState::Playing(index);
+        Ok(EncounterRun {
+            encounter: index,
+            board,
+            hero_turns: 0,
+        })
+    }
+
+    // Applies the outcome of a played encounter and picks the next one
+    pub fn finish_encounter(&mut self, run: &EncounterRun) -> Result<bool, &'static str> {
+        if self.state != CampaignState::Playing(run.encounter) {
+            return Err("That encounter is not being played");
+        }
+        let encounter = &self.encounters[run.encounter];
+        let won = encounter
+            .victory
+            .check(&run.board, run.hero_turns)
+            .ok_or("The encounter is not decided yet")?;
+
+        if won {
+            for reward in encounter.rewards.iter() {
+                self.character.gain(reward);
+            }
+            self.state = match encounter.next {
+                Some(next) => CampaignState::Ready(next),
+                None => CampaignState::Finished,
+            };
+        } else {
+            self.defeats += 1;
+            self.state = CampaignState::Ready(run.encounter);
+        }
+        Ok(won)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), &'static str> {
+        let data = version::to_string(self)?;
+        fs::write(path, data).map_err(|_| "Could not write save file")
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Campaign, &'static str> {
+        let data = fs::read_to_string(path).map_err(|_| "Could not read save file")?;
+        version::from_str(&data)
+    }
+}
+
+// A single encounter being played, the board is driven like any other game
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct EncounterRun {
+    pub encounter: usize,
+    pub board: MasterBoard,
+    pub hero_turns: usize,
+}
+
+impl EncounterRun {
+    pub fn do_action(&mut self, action: PlayerAction) -> Result<Vec<BoardDelta>, &'static str> {
+        let ends_hero_turn =
+            self.board.current_player == HERO && matches!(action, PlayerAction::EndTurn);
+        let deltas = self.board.do_action(action)?;
+        if ends_hero_turn {
+            self.hero_turns += 1;
+        }
+        Ok(deltas)
+    }
+}
@@ -0,0 +1,99 @@
+use super::{api::*, campaign::*, cards::*};
+
+fn defeat_everyone(run: &mut EncounterRun) {
+    for mat in run.board.mats.iter_mut().skip(1) {
+        mat.lives = 1;
+    }
+    while !run.board.game_over {
+        let current = run.board.current_player;
+        if current == HERO {
+            let target = (1..run.board.players)
+                .find(|p| run.board.mats[*p].is_alive())
+                .unwrap();
+            run.board.mats[HERO].combat += 1;
+            run.do_action(PlayerAction::AttackPlayer(target, 1))
+                .expect("Could not attack");
+        } else {
+            run.do_action(PlayerAction::EndTurn)
+                .expect("Could not end turn");
+        }
+    }
+}
+
+#[test]
+fn test_campaign_progression() -> Result<(), &'static str> {
+    let mut campaign = Campaign::tutorial("Hero".into(), Ancestry::Dwarf, 42);
+    assert_eq!(campaign.state, CampaignState::Ready(0));
+
+    let mut run = campaign.start_encounter()?;
+    assert_eq!(run.board.players, 2);
+    assert_eq!(run.board.mats[HERO].name, "Hero");
+    assert_eq!(run.board.mats[HERO].ancestry, Ancestry::Dwarf);
+    assert_eq!(run.board.mats[1].lives, 30);
+    campaign
+        .start_encounter()
+        .expect_err("Can't start two encounters at once");
+    campaign.finish_encounter(&run).expect_err("Nobody won yet");
+
+    defeat_everyone(&mut run);
+    assert_eq!(campaign.finish_encounter(&run), Ok(true));
+    assert_eq!(campaign.state, CampaignState::Ready(1));
+    assert_eq!(campaign.character.deck.len(), 11);
+    assert!(campaign.character.deck.contains(&Card::Spark));
+
+    // Surviving the ambush is enough
+    let mut run = campaign.start_encounter()?;
+    assert_eq!(run.board.players, 3);
+    while campaign.finish_encounter(&run).is_err() {
+        run.do_action(PlayerAction::EndTurn)?;
+        for mat in run.board.mats.iter_mut() {
+            mat.must_discard = 0;
+        }
+    }
+    assert_eq!(run.hero_turns, 8);
+    assert_eq!(campaign.state, CampaignState::Ready(2));
+    assert!(!campaign.character.deck.contains(&Card::Dagger));
+
+    // Losing against the warlord sends the hero back to the same encounter
+    let mut run = campaign.start_encounter()?;
+    run.board.mats[HERO].lives = 0;
+    assert_eq!(campaign.finish_encounter(&run), Ok(false));
+    assert_eq!(campaign.state, CampaignState::Ready(2));
+    assert_eq!(campaign.defeats, 1);
+
+    let mut run = campaign.start_encounter()?;
+    defeat_everyone(&mut run);
+    assert_eq!(campaign.finish_encounter(&run), Ok(true));
+    assert_eq!(campaign.state, CampaignState::Finished);
+    assert_eq!(campaign.character.lives, 55);
+    campaign
+        .start_encounter()
+        .expect_err("The campaign is over");
+
+    Ok(())
+}
+
+#[test]
+fn test_campaign_save_file() -> Result<(), &'static str> {
+    let path = std::env::temp_dir().join("heros_campaign_test.json");
+    let mut campaign = Campaign::tutorial("Hero".into(), Ancestry::Elf, 7);
+    let mut run = campaign.start_encounter()?;
+    defeat_everyone(&mut run);
+    campaign.finish_encounter(&run)?;
+    campaign.save(&path)?;
+
+    let loaded = Campaign::load(&path)?;
+    std::fs::remove_file(&path).ok();
+    assert_eq!(loaded.character, campaign.character);
+    assert_eq!(loaded.state, CampaignState::Ready(1));
+
+    // Same saved RNG, same encounter
+    let a = campaign.start_encounter()?;
+    let b = loaded.clone().start_encounter()?;
+    assert_eq!(a.board.mats[HERO].hand, b.board.mats[HERO].hand);
+
+    Campaign::load(std::env::temp_dir().join("heros_no_such_save.json"))
+        .expect_err("Missing save file");
+
+    Ok(())
+}
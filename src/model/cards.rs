@@ -1,10 +1,86 @@
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Setup {
     pub shop_deck: Vec<Card>,
     pub gems: Vec<Card>,
     pub player_deck: Vec<Card>,
+    // Chosen per seat, seats without a choice play as humans
+    pub ancestries: Vec<Ancestry>,
+    pub draft: Option<DraftRules>,
+    pub rules: Rules,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Rules {
+    // Indexed by seat, seats left out start with their loadout lives
+    pub starting_lives: Vec<usize>,
+    pub market_size: usize,
+    // Overrides the amount of fire gems from the setup
+    pub fire_gems: Option<usize>,
+    // Indexed by turn order, so the first value is for the first player
+    pub starting_hands: Vec<usize>,
+    pub purchase_to_hand_applies_to_gems: bool,
+    pub first_player: FirstPlayer,
+    pub clock: Option<Clock>,
+}
+
+impl Rules {
+    pub fn starting_hand(&self, players: usize, turn_position: usize) -> usize {
+        match self.starting_hands.get(turn_position) {
+            Some(amount) => *amount,
+            None if turn_position == 0 => 3,
+            None if turn_position == 1 && players > 2 => 4,
+            None => 5,
+        }
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            starting_lives: vec![],
+            market_size: 6,
+            fire_gems: None,
+            starting_hands: vec![],
+            purchase_to_hand_applies_to_gems: false,
+            first_player: FirstPlayer::Random,
+            clock: None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum FirstPlayer {
+    Random,
+    Fixed(usize),
+    // Rotates with the number of games already played in a series
+    Alternating(usize),
+}
+
+// Time each player has, enforced by the server
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Clock {
+    pub per_turn: Option<Duration>,
+    pub total: Option<Duration>,
+    pub on_timeout: TimeoutPolicy,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum TimeoutPolicy {
+    AutoEndTurn,
+    // Ends the turn like AutoEndTurn, but the player concedes on the nth timeout
+    ForfeitAfter(usize),
+}
+
+// Before the game starts, `pool` cards from the shuffled shop deck are
+// revealed and players take turns picking `picks` of them each into their
+// decks. Whatever is left goes back to the shop deck.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct DraftRules {
+    pub pool: usize,
+    pub picks: usize,
 }
 
 impl Setup {
@@ -46,6 +122,9 @@ impl Setup {
                 Card::Dagger,
                 Card::Ruby,
             ],
+            ancestries: vec![],
+            draft: None,
+            rules: Rules::default(),
         }
     }
 
@@ -157,6 +236,99 @@ impl Setup {
                 Card::Dagger,
                 Card::Ruby,
             ],
+            ancestries: vec![],
+            draft: None,
+            rules: Rules::default(),
+        }
+    }
+
+    pub fn with_ancestry(mut self, seat: usize, ancestry: Ancestry) -> Setup {
+        if self.ancestries.len() <= seat {
+            self.ancestries.resize(seat + 1, Ancestry::Human);
+        }
+        self.ancestries[seat] = ancestry;
+        self
+    }
+
+    pub fn with_draft(mut self, pool: usize, picks: usize) -> Setup {
+        self.draft = Some(DraftRules { pool, picks });
+        self
+    }
+
+    pub fn loadout(&self, seat: usize) -> Loadout {
+        let ancestry = self
+            .ancestries
+            .get(seat)
+            .cloned()
+            .unwrap_or(Ancestry::Human);
+        Loadout::new(ancestry, &self.player_deck)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub enum Ancestry {
+    #[default]
+    Human,
+    Dwarf,
+    Elf,
+    Ogre,
+    Orc,
+    Smallfolk,
+}
+
+impl Ancestry {
+    pub fn id(&self) -> &'static str {
+        match self {
+            Ancestry::Human => "Human",
+            Ancestry::Dwarf => "Dwarf",
+            Ancestry::Elf => "Elf",
+            Ancestry::Ogre => "Ogre",
+            Ancestry::Orc => "Orc",
+            Ancestry::Smallfolk => "Smallfolk",
+        }
+    }
+
+    // Starting card swapped out for the ancestry card, if any
+    pub fn replacement(&self) -> Option<(Card, Card)> {
+        match self {
+            Ancestry::Human => None,
+            Ancestry::Dwarf => Some((Card::ShortSword, Card::DwarvenAxe)),
+            Ancestry::Elf => Some((Card::Dagger, Card::ElvenBow)),
+            Ancestry::Ogre => Some((Card::ShortSword, Card::OgreClub)),
+            Ancestry::Orc => Some((Card::Dagger, Card::OrcishCleaver)),
+            Ancestry::Smallfolk => Some((Card::Gold, Card::LuckyCharm)),
+        }
+    }
+
+    pub fn starting_lives(&self) -> usize {
+        match self {
+            Ancestry::Ogre => 55,
+            Ancestry::Smallfolk => 45,
+            _ => 50,
+        }
+    }
+}
+
+// Everything a seat starts the game with
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Loadout {
+    pub ancestry: Ancestry,
+    pub deck: Vec<Card>,
+    pub lives: usize,
+}
+
+impl Loadout {
+    pub fn new(ancestry: Ancestry, player_deck: &[Card]) -> Loadout {
+        let mut deck = player_deck.to_vec();
+        if let Some((old, new)) = ancestry.replacement() {
+            if let Some(i) = deck.iter().position(|c| *c == old) {
+                deck[i] = new;
+            }
+        }
+        Loadout {
+            lives: ancestry.starting_lives(),
+            ancestry,
+            deck,
         }
     }
 }
@@ -184,6 +356,12 @@ pub enum Card {
     Ruby,
     FireGem,
 
+    DwarvenAxe,
+    ElvenBow,
+    OgreClub,
+    OrcishCleaver,
+    LuckyCharm,
+
     ArkusImperialDragon,
     CloseRanks,
     Command,
@@ -241,9 +419,96 @@ pub enum Card {
     Spark,
     WolfForm,
     WolfShaman,
+
+    BloodZealot,
+    DawnSinger,
+    MerchantPrince,
+    Quartermaster,
+    ShieldBearer,
 }
 
 impl Card {
+    // Stays the same once a card is released, so it can be hashed and stored
+    pub fn id(&self) -> &'static str {
+        match self {
+            Card::Gold => "Gold",
+            Card::ShortSword => "ShortSword",
+            Card::Dagger => "Dagger",
+            Card::Ruby => "Ruby",
+            Card::FireGem => "FireGem",
+
+            Card::DwarvenAxe => "DwarvenAxe",
+            Card::ElvenBow => "ElvenBow",
+            Card::OgreClub => "OgreClub",
+            Card::OrcishCleaver => "OrcishCleaver",
+            Card::LuckyCharm => "LuckyCharm",
+
+            Card::ArkusImperialDragon => "ArkusImperialDragon",
+            Card::CloseRanks => "CloseRanks",
+            Card::Command => "Command",
+            Card::DarianWarMage => "DarianWarMage",
+            Card::Domination => "Domination",
+            Card::CristovTheJust => "CristovTheJust",
+            Card::KrakaHighPriest => "KrakaHighPriest",
+            Card::ManAtArms => "ManAtArms",
+            Card::MasterWeyan => "MasterWeyan",
+            Card::RallyTheTroops => "RallyTheTroops",
+            Card::Recruit => "Recruit",
+            Card::TithePriest => "TithePriest",
+            Card::Taxation => "Taxation",
+            Card::WordOfPower => "WordOfPower",
+
+            Card::BorgOgreMercenary => "BorgOgreMercenary",
+            Card::Bribe => "Bribe",
+            Card::DeathThreat => "DeathThreat",
+            Card::Deception => "Deception",
+            Card::FireBomb => "FireBomb",
+            Card::HitJob => "HitJob",
+            Card::Intimidation => "Intimidation",
+            Card::MyrosGuildMage => "MyrosGuildMage",
+            Card::ParovTheEnforcer => "ParovTheEnforcer",
+            Card::Profit => "Profit",
+            Card::RakeMasterAssassin => "RakeMasterAssassin",
+            Card::RasmusTheSmuggler => "RasmusTheSmuggler",
+            Card::SmashAndGrab => "SmashAndGrab",
+            Card::StreetThug => "StreetThug",
+
+            Card::CultPriest => "CultPriest",
+            Card::DarkEnergy => "DarkEnergy",
+            Card::DarkReward => "DarkReward",
+            Card::DeathCultist => "DeathCultist",
+            Card::DeathTouch => "DeathTouch",
+            Card::RaylaEndweaver => "RaylaEndweaver",
+            Card::Influence => "Influence",
+            Card::KrythosMasterVampire => "KrythosMasterVampire",
+            Card::LifeDrain => "LifeDrain",
+            Card::LysTheUnseen => "LysTheUnseen",
+            Card::TheRot => "TheRot",
+            Card::TyrannorTheDevourer => "TyrannorTheDevourer",
+            Card::VarrickTheNecromancer => "VarrickTheNecromancer",
+
+            Card::BroelynLoreweaver => "BroelynLoreweaver",
+            Card::CronTheBerserker => "CronTheBerserker",
+            Card::DireWolf => "DireWolf",
+            Card::ElvenCurse => "ElvenCurse",
+            Card::ElvenGift => "ElvenGift",
+            Card::GrakStormGiant => "GrakStormGiant",
+            Card::NaturesBounty => "NaturesBounty",
+            Card::OrcGrunt => "OrcGrunt",
+            Card::Rampage => "Rampage",
+            Card::TorgenRocksplitter => "TorgenRocksplitter",
+            Card::Spark => "Spark",
+            Card::WolfForm => "WolfForm",
+            Card::WolfShaman => "WolfShaman",
+
+            Card::BloodZealot => "BloodZealot",
+            Card::DawnSinger => "DawnSinger",
+            Card::MerchantPrince => "MerchantPrince",
+            Card::Quartermaster => "Quartermaster",
+            Card::ShieldBearer => "ShieldBearer",
+        }
+    }
+
     pub fn faction(&self) -> Faction {
         match self {
             Card::Gold => Faction::NoFaction,
@@ -252,6 +517,12 @@ impl Card {
             Card::Ruby => Faction::NoFaction,
             Card::FireGem => Faction::NoFaction,
 
+            Card::DwarvenAxe => Faction::NoFaction,
+            Card::ElvenBow => Faction::NoFaction,
+            Card::OgreClub => Faction::NoFaction,
+            Card::OrcishCleaver => Faction::NoFaction,
+            Card::LuckyCharm => Faction::NoFaction,
+
             Card::ArkusImperialDragon => Faction::Imperial,
             Card::CloseRanks => Faction::Imperial,
             Card::Command => Faction::Imperial,
@@ -309,6 +580,12 @@ impl Card {
             Card::Spark => Faction::Wild,
             Card::WolfForm => Faction::Wild,
             Card::WolfShaman => Faction::Wild,
+
+            Card::BloodZealot => Faction::Necros,
+            Card::DawnSinger => Faction::Wild,
+            Card::MerchantPrince => Faction::Guild,
+            Card::Quartermaster => Faction::Imperial,
+            Card::ShieldBearer => Faction::Imperial,
         }
     }
 
@@ -341,6 +618,11 @@ impl Card {
             Card::OrcGrunt => 3,
             Card::TorgenRocksplitter => 7,
             Card::WolfShaman => 4,
+            Card::BloodZealot => 4,
+            Card::DawnSinger => 4,
+            Card::MerchantPrince => 5,
+            Card::Quartermaster => 4,
+            Card::ShieldBearer => 5,
             _ => 0,
         }
     }
@@ -373,7 +655,12 @@ impl Card {
             | Card::GrakStormGiant
             | Card::OrcGrunt
             | Card::TorgenRocksplitter
-            | Card::WolfShaman => true,
+            | Card::WolfShaman
+            | Card::BloodZealot
+            | Card::DawnSinger
+            | Card::MerchantPrince
+            | Card::Quartermaster
+            | Card::ShieldBearer => true,
             _ => false,
         }
     }
@@ -393,7 +680,8 @@ impl Card {
             | Card::DireWolf
             | Card::GrakStormGiant
             | Card::OrcGrunt
-            | Card::TorgenRocksplitter => true,
+            | Card::TorgenRocksplitter
+            | Card::ShieldBearer => true,
             _ => false,
         }
     }
@@ -401,6 +689,11 @@ impl Card {
     pub fn is_object(&self) -> bool {
         match self {
             Card::Gold | Card::Ruby | Card::Dagger | Card::ShortSword | Card::FireGem => true,
+            Card::DwarvenAxe
+            | Card::ElvenBow
+            | Card::OgreClub
+            | Card::OrcishCleaver
+            | Card::LuckyCharm => true,
             _ => false,
         }
     }
@@ -471,6 +764,12 @@ impl Card {
             Card::WolfForm => 5,
             Card::WolfShaman => 2,
 
+            Card::BloodZealot => 3,
+            Card::DawnSinger => 4,
+            Card::MerchantPrince => 5,
+            Card::Quartermaster => 4,
+            Card::ShieldBearer => 5,
+
             _ => 0,
         }
     }
@@ -483,6 +782,12 @@ impl Card {
             Card::ShortSword => vec![Effect::Combat(2)],
             Card::FireGem => vec![Effect::Gold(2)],
 
+            Card::DwarvenAxe => vec![Effect::Combat(2), Effect::Heal(1)],
+            Card::ElvenBow => vec![Effect::Combat(1), Effect::Gold(1)],
+            Card::OgreClub => vec![Effect::Combat(3)],
+            Card::OrcishCleaver => vec![Effect::Combat(2)],
+            Card::LuckyCharm => vec![Effect::Gold(1), Effect::Heal(1)],
+
             Card::Spark => vec![Effect::Combat(3), Effect::OpponentDiscards(1)],
             Card::Influence => vec![Effect::Gold(3)],
             Card::DeathTouch => vec![
@@ -628,6 +933,11 @@ impl Card {
             Card::BroelynLoreweaver => vec![Effect::Gold(2)],
             Card::CronTheBerserker => vec![Effect::Combat(5)],
             Card::DireWolf => vec![Effect::Combat(3)],
+            Card::BloodZealot => vec![Effect::Combat(2)],
+            Card::DawnSinger => vec![Effect::Combat(2)],
+            Card::MerchantPrince => vec![Effect::Gold(2)],
+            Card::Quartermaster => vec![Effect::Gold(1)],
+            Card::ShieldBearer => vec![Effect::Combat(1)],
             _ => {
                 if self.is_champion() {
                     panic!(format!("Unimplemented expend ability for {:?}", self));
@@ -695,17 +1005,65 @@ impl Card {
         };
         Some(effects)
     }
+
+    pub fn triggered_abilities(&self) -> Vec<(Trigger, Vec<Effect>)> {
+        match self {
+            Card::BloodZealot => vec![(Trigger::Stunned, vec![Effect::Heal(3)])],
+            Card::DawnSinger => vec![(Trigger::StartOfTurn, vec![Effect::Heal(2)])],
+            Card::MerchantPrince => {
+                vec![(Trigger::Acquire(CardKind::Action), vec![Effect::Combat(1)])]
+            }
+            _ => vec![],
+        }
+    }
+
+    pub fn static_modifiers(&self) -> Vec<Modifier> {
+        match self {
+            Card::Quartermaster => vec![Modifier::CostReduction(CardKind::Action, 1)],
+            Card::ShieldBearer => vec![Modifier::DefenseBonus(1)],
+            _ => vec![],
+        }
+    }
+
+    /// Cost of this card for a player with the given field in play.
+    pub fn effective_cost(&self, field: &[CardInField]) -> usize {
+        let reduction: usize = field
+            .iter()
+            .flat_map(|cif| cif.card.static_modifiers())
+            .map(|m| match m {
+                Modifier::CostReduction(kind, x) if kind.matches(self) => x,
+                _ => 0,
+            })
+            .sum();
+        self.cost().saturating_sub(reduction)
+    }
+
+    /// Defense of this card while in the given field.
+    pub fn effective_defense(&self, field: &[CardInField]) -> usize {
+        if !self.is_champion() {
+            return self.defense();
+        }
+        let bonus: usize = field
+            .iter()
+            .flat_map(|cif| cif.card.static_modifiers())
+            .map(|m| match m {
+                Modifier::DefenseBonus(x) => x,
+                _ => 0,
+            })
+            .sum();
+        self.defense() + bonus
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
-pub struct CardInField {
-    pub card: Card,
+pub struct CardInField<C = Card> {
+    pub card: C,
     pub expend_ability_used: bool,
     pub ally_ability_used: bool,
 }
 
-impl CardInField {
-    pub fn new(card: Card) -> CardInField {
+impl<C> CardInField<C> {
+    pub fn new(card: C) -> CardInField<C> {
         CardInField {
             card,
             expend_ability_used: false,
@@ -738,6 +1096,22 @@ pub enum Effect {
     NextPurchaseToTopOfDeck,
 }
 
+impl Effect {
+    // Whether the effect takes an EffectArgument when resolved
+    pub fn needs_arguments(&self) -> bool {
+        match self {
+            Effect::Choice(_, _)
+            | Effect::OpponentDiscards(_)
+            | Effect::PrepareChampion
+            | Effect::StunChampion
+            | Effect::PutCardFromDiscardOverDeck
+            | Effect::PutChampionFromDiscardOverDeck => true,
+            Effect::Sacrifice(amount) => *amount > 0,
+            _ => false,
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum PerAmount {
     AdditionalFactionCard(Faction),
@@ -746,3 +1120,40 @@ pub enum PerAmount {
     AdditionalGuardian,
     // Filter(Filter),
 }
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Trigger {
+    // When this champion is stunned, resolved for its owner
+    Stunned,
+    // Whenever its owner acquires a card of the given kind
+    Acquire(CardKind),
+    // At the start of its owner's turn
+    StartOfTurn,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum CardKind {
+    Any,
+    Action,
+    Champion,
+    Item,
+}
+
+impl CardKind {
+    pub fn matches(&self, card: &Card) -> bool {
+        match self {
+            CardKind::Any => true,
+            CardKind::Action => card.is_action(),
+            CardKind::Champion => card.is_champion(),
+            CardKind::Item => card.is_object(),
+        }
+    }
+}
+
+// Static modifiers apply while the card is in its owner's field.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Modifier {
+    CostReduction(CardKind, usize),
+    // Applies to every champion in the same field
+    DefenseBonus(usize),
+}
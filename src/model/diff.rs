@@ -0,0 +1,544 @@
+use super::{
+    api::{Board, BoardDelta, GameCard, Location, Player},
+    cards::CardInField,
+    master::MasterBoard,
+};
+
+impl<C: GameCard> Board<C> {
+    // Deltas turning this view into another one of the same game and seat. What
+    // is known of hidden cards follows from the moves, so it may still differ.
+    // Deltas can't make every pair of views, so this fails when the seats or
+    // the viewer differ, when a finished game would have to go on, and when the
+    // cards in play aren't the same ones moved around, like a sacrificed card
+    // coming back or a card showing up out of nowhere
+    pub fn diff(&self, other: &Board<C>) -> Result<Vec<BoardDelta<C>>, &'static str> {
+        let seats = |b: &Board<C>| -> Vec<(String, C::SeatOption)> {
+            b.mats
+                .iter()
+                .map(|m| (m.name.clone(), m.ancestry.clone()))
+                .collect()
+        };
+        if self.players != other.players || self.you != other.you || seats(self) != seats(other) {
+            return Err("Boards are from different games");
+        }
+        if self.game_over && !other.game_over {
+            return Err("A finished game can't be resumed");
+        }
+
+        let hand = |b: &Board<C>, p: Player| match p == b.you {
+            true => known(&b.your_hand),
+            false => vec![None; b.mats[p].hand],
+        };
+        // The known cards are the top of the deck
+        let deck = |b: &Board<C>, p: Player| {
+            let mat = &b.mats[p];
+            let mut cards = vec![None; mat.deck.saturating_sub(mat.known_deck.len())];
+            cards.extend(known(&mat.known_deck));
+            cards
+        };
+        let gems = |b: &Board<C>| vec![Some(C::supply_card()); b.gems];
+        let mut zones = vec![
+            Zone::new(
+                Location::Shop,
+                Access::Any,
+                known(&self.shop),
+                known(&other.shop),
+            ),
+            Zone::new(
+                Location::ShopDeck,
+                Access::Top,
+                vec![None; self.shop_deck],
+                vec![None; other.shop_deck],
+            ),
+            Zone::new(Location::FireGems, Access::Top, gems(self), gems(other)),
+            Zone::new(
+                Location::DraftPool,
+                Access::Any,
+                known(&self.draft_pool),
+                known(&other.draft_pool),
+            ),
+            Zone::new(
+                Location::Sacrifice,
+                Access::Never,
+                known(&self.sacrificed),
+                known(&other.sacrificed),
+            ),
+        ];
+        for (p, (mat, target)) in self.mats.iter().zip(other.mats.iter()).enumerate() {
+            zones.push(Zone::new(
+                Location::Hand(p),
+                Access::Any,
+                hand(self, p),
+                hand(other, p),
+            ));
+            zones.push(Zone::new(
+                Location::Deck(p),
+                Access::Top,
+                deck(self, p),
+                deck(other, p),
+            ));
+            zones.push(Zone::new(
+                Location::Discard(p),
+                Access::Any,
+                known(&mat.discard),
+                known(&target.discard),
+            ));
+            zones.push(Zone::new(
+                Location::Field(p),
+                Access::Any,
+                champions(&mat.field),
+                champions(&target.field),
+            ));
+        }
+
+        let mut deltas = plan_moves(&mut zones)?;
+        let mut board = self.clone();
+        for delta in deltas.iter() {
+            board
+                .apply(delta.clone())
+                .map_err(|_| "The boards can't be reconciled with deltas")?;
+        }
+        for (p, (mat, target)) in board.mats.iter().zip(other.mats.iter()).enumerate() {
+            deltas.append(&mut mat_deltas(
+                p,
+                (
+                    &mat.field,
+                    [mat.lives, mat.combat, mat.gold, mat.must_discard],
+                ),
+                (
+                    &target.field,
+                    [
+                        target.lives,
+                        target.combat,
+                        target.gold,
+                        target.must_discard,
+                    ],
+                ),
+            ));
+        }
+        if board.current_player != other.current_player {
+            deltas.push(BoardDelta::ChangeCurrentPlayer(other.current_player));
+        }
+        if !board.game_over && other.game_over {
+            deltas.push(BoardDelta::GameOver);
+        }
+        Ok(deltas)
+    }
+}
+
+impl MasterBoard {
+    // Deltas turning this board into another one of the same game. Only what
+    // deltas can tell is compared: rules, the rng, the draft picks, timeouts and
+    // pending purchase effects are left as they are. Fails like the view diff,
+    // and also when only one board is drafting or an eliminated player is back
+    pub fn diff(&self, other: &MasterBoard) -> Result<Vec<BoardDelta>, &'static str> {
+        let seats = |b: &MasterBoard| -> Vec<(String, _)> {
+            b.mats
+                .iter()
+                .map(|m| (m.name.clone(), m.ancestry.clone()))
+                .collect()
+        };
+        if self.players != other.players || seats(self) != seats(other) {
+            return Err("Boards are from different games");
+        }
+        if self.is_drafting() != other.is_drafting() {
+            return Err("Only one of the boards is drafting");
+        }
+        let revived =
+            (0..self.players).any(|p| self.mats[p].eliminated && !other.mats[p].eliminated);
+        if (self.game_over && !other.game_over) || revived {
+            return Err("A finished game can't be resumed");
+        }
+
+        let pool = |b: &MasterBoard| b.draft.as_ref().map(|d| known(&d.pool)).unwrap_or_default();
+        let mut zones = vec![
+            Zone::new(
+                Location::Shop,
+                Access::Any,
+                known(&self.shop),
+                known(&other.shop),
+            ),
+            Zone::new(
+                Location::ShopDeck,
+                Access::Top,
+                known(&self.shop_deck),
+                known(&other.shop_deck),
+            ),
+            Zone::new(
+                Location::FireGems,
+                Access::Top,
+                known(&self.gems),
+                known(&other.gems),
+            ),
+            Zone::new(Location::DraftPool, Access::Any, pool(self), pool(other)),
+            Zone::new(
+                Location::Sacrifice,
+                Access::Never,
+                known(&self.sacrificed),
+                known(&other.sacrificed),
+            ),
+        ];
+        for (p, (mat, target)) in self.mats.iter().zip(other.mats.iter()).enumerate() {
+            zones.push(Zone::new(
+                Location::Hand(p),
+                Access::Any,
+                known(&mat.hand),
+                known(&target.hand),
+            ));
+            zones.push(Zone::new(
+                Location::Deck(p),
+                Access::Top,
+                known(&mat.deck),
+                known(&target.deck),
+            ));
+            zones.push(Zone::new(
+                Location::Discard(p),
+                Access::Any,
+                known(&mat.discard),
+                known(&target.discard),
+            ));
+            zones.push(Zone::new(
+                Location::Field(p),
+                Access::Any,
+                champions(&mat.field),
+                champions(&target.field),
+            ));
+        }
+
+        let mut deltas = plan_moves(&mut zones)?;
+        let mut board = self.clone();
+        for delta in deltas.iter() {
+            board.apply(delta.clone())?;
+        }
+        for (p, (mat, target)) in board.mats.iter().zip(other.mats.iter()).enumerate() {
+            deltas.append(&mut mat_deltas(
+                p,
+                (
+                    &mat.field,
+                    [mat.lives, mat.combat, mat.gold, mat.must_discard],
+                ),
+                (
+                    &target.field,
+                    [
+                        target.lives,
+                        target.combat,
+                        target.gold,
+                        target.must_discard,
+                    ],
+                ),
+            ));
+        }
+        if board.current_player != other.current_player {
+            deltas.push(BoardDelta::ChangeCurrentPlayer(other.current_player));
+        }
+        for p in 0..self.players {
+            if !board.mats[p].eliminated && other.mats[p].eliminated {
+                deltas.push(BoardDelta::PlayerEliminated(p));
+            }
+        }
+        if !board.game_over && other.game_over {
+            deltas.push(BoardDelta::GameOver);
+        }
+        Ok(deltas)
+    }
+}
+
+fn known<C: Clone>(cards: &[C]) -> Vec<Option<C>> {
+    cards.iter().cloned().map(Some).collect()
+}
+
+fn champions<C: Clone>(field: &[CardInField<C>]) -> Vec<Option<C>> {
+    field.iter().map(|cif| Some(cif.card.clone())).collect()
+}
+
+type Change<C> = fn(Player, usize) -> BoardDelta<C>;
+
+// Champion flags and stats, once every card is in place
+fn mat_deltas<C: GameCard>(
+    p: Player,
+    (field, stats): (&[CardInField<C>], [usize; 4]),
+    (target_field, target_stats): (&[CardInField<C>], [usize; 4]),
+) -> Vec<BoardDelta<C>> {
+    let mut deltas = vec![];
+    for (i, (cif, target)) in field.iter().zip(target_field.iter()).enumerate() {
+        if cif.expend_ability_used != target.expend_ability_used {
+            deltas.push(BoardDelta::SetExpendAbilityUsed(
+                p,
+                i,
+                target.expend_ability_used,
+            ));
+        }
+        if cif.ally_ability_used != target.ally_ability_used {
+            deltas.push(BoardDelta::SetAllyAbilityUsed(
+                p,
+                i,
+                target.ally_ability_used,
+            ));
+        }
+    }
+
+    let changes: [(Change<C>, Change<C>); 4] = [
+        (BoardDelta::IncreaseHealth, BoardDelta::DecreaseHealth),
+        (BoardDelta::IncreaseCombat, BoardDelta::DecreaseCombat),
+        (BoardDelta::IncreaseGold, BoardDelta::DecreaseGold),
+        (
+            BoardDelta::IncreaseDiscardAmount,
+            BoardDelta::DecreaseDiscardAmount,
+        ),
+    ];
+    for (i, (increase, decrease)) in changes.iter().enumerate() {
+        if target_stats[i] > stats[i] {
+            deltas.push(increase(p, target_stats[i] - stats[i]));
+        } else if target_stats[i] < stats[i] {
+            deltas.push(decrease(p, stats[i] - target_stats[i]));
+        }
+    }
+    deltas
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Access {
+    // Any card by its index
+    Any,
+    // Only the last card, the top of a pile
+    Top,
+    // Cards never leave, like sacrificed ones
+    Never,
+}
+
+// Cards of some location and the ones it should end up with, hidden cards are
+// None and stand for any card
+struct Zone<C> {
+    location: Location,
+    access: Access,
+    cards: Vec<Option<C>>,
+    target: Vec<Option<C>>,
+    // Hidden cards above this many have to be moved even if they look in place
+    keep: usize,
+}
+
+impl<C: GameCard> Zone<C> {
+    fn new(
+        location: Location,
+        access: Access,
+        cards: Vec<Option<C>>,
+        target: Vec<Option<C>>,
+    ) -> Zone<C> {
+        Zone {
+            location,
+            access,
+            cards,
+            target,
+            keep: usize::MAX,
+        }
+    }
+
+    // Cards before this index are already in place and stay there
+    fn settled(&self) -> usize {
+        self.cards
+            .iter()
+            .zip(self.target.iter())
+            .take_while(|(card, target)| card == target)
+            .count()
+            .min(self.keep)
+    }
+
+    // What the zone is waiting for, once every misplaced card is out
+    fn next_slot(&self) -> Option<&Option<C>> {
+        match self.cards.len() == self.settled() {
+            true => self.target.get(self.cards.len()),
+            false => None,
+        }
+    }
+
+    // Misplaced cards that can be taken out right now
+    fn takeable(&self) -> Vec<usize> {
+        let settled = self.settled();
+        match self.access {
+            Access::Any => (settled..self.cards.len()).collect(),
+            Access::Top if self.cards.len() > settled => vec![self.cards.len() - 1],
+            _ => vec![],
+        }
+    }
+
+    fn misplaced(&self) -> &[Option<C>] {
+        &self.cards[self.settled()..]
+    }
+
+    fn missing(&self) -> &[Option<C>] {
+        &self.target[self.settled()..]
+    }
+}
+
+// Misplaced known cards of this kind minus the places still waiting for one, a
+// known card only goes to a hidden place when there are too many of it
+fn surplus<C: GameCard>(zones: &[Zone<C>], card: &C) -> isize {
+    let count = |cards: &[Option<C>]| cards.iter().flatten().filter(|c| *c == card).count();
+    zones
+        .iter()
+        .map(|z| count(z.misplaced()) as isize - count(z.missing()) as isize)
+        .sum()
+}
+
+// How good a match the card is for the place, the same known card being the
+// best and two unknown ones the worst
+fn rank<C: GameCard>(zones: &[Zone<C>], card: &Option<C>, slot: &Option<C>) -> Option<usize> {
+    match (card, slot) {
+        (Some(card), Some(slot)) if card == slot => Some(0),
+        (Some(card), None) if surplus(zones, card) > 0 => Some(1),
+        (None, Some(slot)) if surplus(zones, slot) < 0 => Some(1),
+        (None, None) => Some(2),
+        _ => None,
+    }
+}
+
+// Known cards nobody waits for need hidden places, which may mean taking out
+// hidden cards that looked in place, preferably from hands over piles
+fn make_room<C: GameCard>(zones: &mut [Zone<C>]) {
+    let mut kinds: Vec<C> = vec![];
+    for card in zones.iter().flat_map(|z| z.misplaced().iter().flatten()) {
+        if !kinds.contains(card) {
+            kinds.push(card.clone());
+        }
+    }
+    let extra: isize = kinds.iter().map(|c| surplus(zones, c).max(0)).sum();
+    let room: isize = zones
+        .iter()
+        .map(|z| z.missing().iter().filter(|c| c.is_none()).count() as isize)
+        .sum();
+    let mut needed = extra - room;
+    for access in [Access::Any, Access::Top].iter() {
+        for zone in zones.iter_mut().filter(|z| z.access == *access) {
+            let mut keep = zone.settled();
+            while needed > 0 && keep > 0 && zone.target[keep - 1].is_none() {
+                keep -= 1;
+                needed -= 1;
+            }
+            if keep < zone.settled() {
+                zone.keep = keep;
+            }
+        }
+    }
+}
+
+// Misplaced cards go straight to a zone waiting for them whenever there is one,
+// and otherwise to the end of the first zone, which is put in order last
+fn plan_moves<C: GameCard>(zones: &mut [Zone<C>]) -> Result<Vec<BoardDelta<C>>, &'static str> {
+    let staging = 0;
+    let mut deltas = vec![];
+    make_room(zones);
+    loop {
+        if let Some((from, index, to)) = waiting_zone(zones) {
+            let slot = zones[to].target[zones[to].cards.len()].clone();
+            let named = zones[from].cards[index].clone().or_else(|| slot.clone());
+            move_card(zones, &mut deltas, (from, index, to), named, slot);
+            continue;
+        }
+        // Known cards first, unknown ones are named after what is missing
+        let takeable = |known: bool| {
+            (0..zones.len()).filter(|z| *z != staging).find_map(|z| {
+                zones[z]
+                    .takeable()
+                    .into_iter()
+                    .rev()
+                    .find(|i| zones[z].cards[*i].is_some() == known)
+                    .map(|i| (z, i))
+            })
+        };
+        match takeable(true).or_else(|| takeable(false)) {
+            Some((from, index)) => {
+                let named = zones[from].cards[index]
+                    .clone()
+                    .or_else(|| missing_card(zones));
+                move_card(
+                    zones,
+                    &mut deltas,
+                    (from, index, staging),
+                    named.clone(),
+                    named,
+                );
+            }
+            None => break,
+        }
+    }
+
+    // Sorting by moving each card in turn to the end
+    let settled = zones[staging].settled();
+    let unsorted = zones[staging].cards.len() - settled;
+    if unsorted == zones[staging].target.len() - settled {
+        for i in 0..unsorted {
+            let slot = zones[staging].target[settled + i].clone();
+            let region = settled..settled + unsorted - i;
+            let index = region
+                .clone()
+                .filter_map(|j| rank(zones, &zones[staging].cards[j], &slot).map(|r| (r, j)))
+                .min()
+                .map(|(_, j)| j);
+            if let Some(index) = index {
+                let named = zones[staging].cards[index].clone().or_else(|| slot.clone());
+                move_card(zones, &mut deltas, (staging, index, staging), named, slot);
+            }
+        }
+    }
+
+    if zones.iter().any(|z| z.cards != z.target) {
+        return Err("The boards don't hold the same cards");
+    }
+    Ok(deltas)
+}
+
+// A misplaced card some zone is waiting for, the best match first
+fn waiting_zone<C: GameCard>(zones: &[Zone<C>]) -> Option<(usize, usize, usize)> {
+    for best in 0..3 {
+        for (to, zone) in zones.iter().enumerate() {
+            let slot = match zone.next_slot() {
+                Some(slot) => slot,
+                None => continue,
+            };
+            for (from, source) in zones.iter().enumerate() {
+                let index = source
+                    .takeable()
+                    .into_iter()
+                    .find(|i| rank(zones, &source.cards[*i], slot) == Some(best));
+                if let Some(index) = index {
+                    return Some((from, index, to));
+                }
+            }
+        }
+    }
+    None
+}
+
+// Some card still expected somewhere that no misplaced card is known to be,
+// used to name an unknown card moved out of the way
+fn missing_card<C: GameCard>(zones: &[Zone<C>]) -> Option<C> {
+    zones
+        .iter()
+        .flat_map(|z| z.missing().iter().flatten())
+        .find(|card| surplus(zones, card) < 0)
+        .cloned()
+}
+
+fn move_card<C: GameCard>(
+    zones: &mut [Zone<C>],
+    deltas: &mut Vec<BoardDelta<C>>,
+    (from, index, to): (usize, usize, usize),
+    named: Option<C>,
+    placed: Option<C>,
+) {
+    zones[from].cards.remove(index);
+    if zones[from].cards.len() <= zones[from].keep {
+        zones[from].keep = usize::MAX;
+    }
+    let index = match zones[from].access {
+        Access::Top => 0,
+        _ => index,
+    };
+    deltas.push(BoardDelta::Move(
+        zones[from].location.clone(),
+        index,
+        zones[to].location.clone(),
+        named,
+    ));
+    zones[to].cards.push(placed);
+}
@@ -0,0 +1,161 @@
+use super::{api::*, cards::*, journal::*, master::*, recorded_test::*};
+use crate::smallrng::*;
+
+fn recorded() -> Result<Journal, &'static str> {
+    recorded_game(setup(), SRng::new(14279), Strategy::FireGems, Some(6))
+}
+
+// What deltas can't tell is taken from the target, so the hashes can be compared
+fn assert_reaches(from: &MasterBoard, to: &MasterBoard) -> Result<usize, &'static str> {
+    let deltas = from.diff(to)?;
+    let mut state = from.clone();
+    for delta in deltas.iter() {
+        state.apply(delta.clone())?;
+    }
+    state.rng = to.rng.clone();
+    for (mat, target) in state.mats.iter_mut().zip(to.mats.iter()) {
+        mat.known_deck = target.known_deck;
+        mat.known_hand = target.known_hand.clone();
+        mat.next_action_purchase_to_top_of_deck = target.next_action_purchase_to_top_of_deck;
+        mat.next_purchase_to_top_of_deck = target.next_purchase_to_top_of_deck;
+        mat.next_purchase_to_hand = target.next_purchase_to_hand;
+        mat.timeouts = target.timeouts;
+    }
+    assert_eq!(state.state_hash(), to.state_hash());
+    Ok(deltas.len())
+}
+
+fn forget(board: &mut Board) {
+    for mat in board.mats.iter_mut() {
+        mat.known_deck.clear();
+        mat.known_hand.clear();
+    }
+}
+
+#[test]
+fn test_diff_each_action() -> Result<(), &'static str> {
+    let journal = recorded()?;
+    for at in 0..journal.actions.len() {
+        let before = journal.board_at(at)?;
+        let after = journal.board_at(at + 1)?;
+        let played = before
+            .clone()
+            .do_action_as(journal.actions[at].0, journal.actions[at].1.clone())?;
+        let moves = assert_reaches(&before, &after)?;
+        // Nothing is gained by listing more than the game itself did, shuffles aside
+        if !played
+            .iter()
+            .any(|d| matches!(d, BoardDelta::ShuffleDeck(_)))
+        {
+            assert!(moves <= played.len());
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_diff_whole_game() -> Result<(), &'static str> {
+    let journal = recorded()?;
+    let start = journal.board_at(0)?;
+    let end = journal.replay()?;
+    assert!(start.diff(&start)?.is_empty());
+    assert_reaches(&start, &end)?;
+    assert_reaches(&end, &start)?;
+    Ok(())
+}
+
+#[test]
+fn test_diff_scoped_boards() -> Result<(), &'static str> {
+    let journal = recorded()?;
+    let start = journal.board_at(0)?;
+    let end = journal.replay()?;
+    for p in 0..2 {
+        let mut view = start.scoped_to(p);
+        let mut target = end.scoped_to(p);
+        for delta in view.diff(&target)? {
+            view.apply(delta).unwrap();
+        }
+        forget(&mut view);
+        forget(&mut target);
+        assert_eq!(view, target);
+    }
+    assert!(start.scoped_to(0).diff(&end.scoped_to(1)).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_diff_failures() -> Result<(), &'static str> {
+    let journal = recorded()?;
+    let start = journal.board_at(0)?;
+    let mut over = journal.replay()?;
+    over.do_action_as(1, PlayerAction::Concede)?;
+    assert_eq!(
+        start.scoped_to(0).diff(&over.scoped_to(1)).unwrap_err(),
+        "Boards are from different games"
+    );
+    assert_eq!(
+        over.scoped_to(0).diff(&start.scoped_to(0)).unwrap_err(),
+        "A finished game can't be resumed"
+    );
+    assert_eq!(
+        over.diff(&start).unwrap_err(),
+        "A finished game can't be resumed"
+    );
+
+    let mut drafting = setup();
+    drafting.draft = Some(DraftRules { pool: 6, picks: 4 });
+    let drafted = Journal::new(2, drafting, SRng::new(14279)).start()?;
+    assert_eq!(
+        drafted.diff(&start).unwrap_err(),
+        "Only one of the boards is drafting"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_diff_reorders_cards() -> Result<(), &'static str> {
+    let from = MasterBoard::from_scenario(
+        "
+        shop Profit StreetThug
+        shop_deck Influence Bribe
+        gems FireGem*4
+        player Human Alice
+        hand Gold Ruby
+        deck Dagger ShortSword Gold
+        discard Gold Ruby
+        field DeathCultist:expend WolfShaman
+        player Dwarf Bob
+        deck Gold
+        field ManAtArms
+        ",
+    )?;
+    let to = MasterBoard::from_scenario(
+        "
+        current 1
+        shop StreetThug Profit
+        shop_deck Bribe Influence
+        gems FireGem*3
+        sacrificed Ruby
+        player Human Alice
+        lives 40
+        gold 3
+        hand Gold
+        deck Gold ShortSword Dagger
+        discard Ruby Gold FireGem
+        field WolfShaman:ally
+        player Dwarf Bob
+        field ManAtArms DeathCultist Gold
+        ",
+    )?;
+    assert_reaches(&from, &to)?;
+    // Sacrificed cards never come back
+    assert_eq!(
+        to.diff(&from).unwrap_err(),
+        "The boards don't hold the same cards"
+    );
+
+    let mut other = to.clone();
+    other.mats[1].name = "Carol".into();
+    assert!(from.diff(&other).is_err());
+    Ok(())
+}
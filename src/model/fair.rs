@@ -0,0 +1,198 @@
+use super::{
+    api::Player,
+    sha256::{sha256, Digest},
+    version::Versioned,
+};
+use crate::smallrng::SRng;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+// Seeding a game among peers so that no single one of them picks the shuffles:
+//
+// 1. Every player draws a secret and sends the hash of their seat and secret
+// 2. Once every commitment is in, every player reveals their secret
+// 3. Reveals are checked against the commitments, and the seed is the hash of
+//    all the secrets in seat order
+//
+// Messages from different peers can arrive in any order, so a reveal may come
+// before some other commitment. It is kept aside and checked once every
+// commitment is in.
+//
+// Nobody knows the seed before the last reveal, and by then nobody can change
+// their secret. A player can still refuse to reveal once they have seen the
+// others, which is why those who don't are named as withholding.
+pub type Secret = [u8; 32];
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum FairMessage {
+    Commit(Digest),
+    Reveal(Secret),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cheat {
+    // Whoever sent it isn't at the table
+    NotSeated(Player),
+    // Sent a second commitment or reveal that isn't the same as the first
+    Changed(Player),
+    // Revealed before committing, so the commitment could be chosen knowing it
+    EarlyReveal(Player),
+    // The secret doesn't match the commitment
+    WrongReveal(Player),
+    // Hasn't revealed and the others have
+    Withheld(Player),
+}
+
+// Everything received so far, from every seat including ours
+#[derive(Clone, Debug)]
+pub struct SeedExchange {
+    commitments: Vec<Option<Digest>>,
+    secrets: Vec<Option<Secret>>,
+    // Reveals received while some commitment was still missing
+    pending: Vec<Option<Secret>>,
+}
+
+pub fn commitment(player: Player, secret: &Secret) -> Digest {
+    let mut data = (player as u64).to_le_bytes().to_vec();
+    data.extend_from_slice(secret);
+    sha256(&data)
+}
+
+impl SeedExchange {
+    pub fn new(players: usize) -> SeedExchange {
+        SeedExchange {
+            commitments: vec![None; players],
+            secrets: vec![None; players],
+            pending: vec![None; players],
+        }
+    }
+
+    // The sender is told by the connection, not by the message
+    pub fn receive(&mut self, from: Player, message: &FairMessage) -> Result<(), Cheat> {
+        if from >= self.commitments.len() {
+            return Err(Cheat::NotSeated(from));
+        }
+        match message {
+            FairMessage::Commit(digest) => match self.commitments[from] {
+                Some(committed) if committed != *digest => Err(Cheat::Changed(from)),
+                _ => {
+                    self.commitments[from] = Some(*digest);
+                    if !self.all_committed() {
+                        return Ok(());
+                    }
+                    // Every kept reveal is opened, the first cheat found is reported
+                    let mut result = Ok(());
+                    for p in 0..self.pending.len() {
+                        if let Some(secret) = self.pending[p].take() {
+                            result = result.and(self.open(p, &secret));
+                        }
+                    }
+                    result
+                }
+            },
+            FairMessage::Reveal(secret) => {
+                if self.commitments[from].is_none() {
+                    return Err(Cheat::EarlyReveal(from));
+                }
+                if self.all_committed() {
+                    return self.open(from, secret);
+                }
+                match self.pending[from] {
+                    Some(revealed) if revealed != *secret => Err(Cheat::Changed(from)),
+                    _ => {
+                        self.pending[from] = Some(*secret);
+                        Ok(())
+                    }
+                }
+            }
+        }
+    }
+
+    fn open(&mut self, from: Player, secret: &Secret) -> Result<(), Cheat> {
+        if matches!(self.secrets[from], Some(revealed) if revealed != *secret) {
+            return Err(Cheat::Changed(from));
+        }
+        if self.commitments[from] != Some(commitment(from, secret)) {
+            return Err(Cheat::WrongReveal(from));
+        }
+        self.secrets[from] = Some(*secret);
+        Ok(())
+    }
+
+    pub fn all_committed(&self) -> bool {
+        self.commitments.iter().all(Option::is_some)
+    }
+
+    pub fn all_revealed(&self) -> bool {
+        self.secrets.iter().all(Option::is_some)
+    }
+
+    // Who is holding the game back, to be called once the others gave up waiting
+    pub fn withheld(&self) -> Vec<Cheat> {
+        (0..self.secrets.len())
+            .filter(|p| self.secrets[*p].is_none())
+            .map(Cheat::Withheld)
+            .collect()
+    }
+
+    pub fn seed(&self) -> Result<u64, &'static str> {
+        let mut data = vec![];
+        for secret in self.secrets.iter() {
+            data.extend_from_slice(secret.as_ref().ok_or("Not every secret is revealed")?);
+        }
+        let digest = sha256(&data);
+        let mut seed = [0; 8];
+        seed.copy_from_slice(&digest[..8]);
+        Ok(u64::from_le_bytes(seed))
+    }
+
+    pub fn rng(&self) -> Result<SRng, &'static str> {
+        Ok(SRng::new(self.seed()?))
+    }
+}
+
+// One player of the exchange, keeping their secret until it's time to reveal
+pub struct FairPeer {
+    pub seat: Player,
+    secret: Secret,
+    pub exchange: SeedExchange,
+}
+
+impl FairPeer {
+    pub fn new(seat: Player, players: usize, secret: Secret) -> FairPeer {
+        let mut exchange = SeedExchange::new(players);
+        exchange.commitments[seat] = Some(commitment(seat, &secret));
+        FairPeer {
+            seat,
+            secret,
+            exchange,
+        }
+    }
+
+    // With a secret from the operating system
+    pub fn random(seat: Player, players: usize) -> FairPeer {
+        let mut secret = [0; 32];
+        OsRng.fill_bytes(&mut secret);
+        FairPeer::new(seat, players, secret)
+    }
+
+    pub fn commit(&self) -> FairMessage {
+        FairMessage::Commit(commitment(self.seat, &self.secret))
+    }
+
+    pub fn reveal(&mut self) -> Result<FairMessage, &'static str> {
+        if !self.exchange.all_committed() {
+            return Err("Waiting for every commitment");
+        }
+        self.exchange.secrets[self.seat] = Some(self.secret);
+        Ok(FairMessage::Reveal(self.secret))
+    }
+
+    pub fn receive(&mut self, from: Player, message: &FairMessage) -> Result<(), Cheat> {
+        self.exchange.receive(from, message)
+    }
+}
+
+impl Versioned for FairMessage {
+    const KIND: &'static str = "fair_message";
+}
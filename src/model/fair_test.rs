@@ -0,0 +1,181 @@
+use super::{api::*, cards::*, fair::*, master::*, sha256::*, version};
+
+fn hex(digest: &Digest) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn test_sha256_reference() {
+    // Examples from FIPS 180-4
+    assert_eq!(
+        hex(&sha256(b"")),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        hex(&sha256(b"abc")),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(
+        hex(&sha256(
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+        )),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+    assert_eq!(
+        hex(&sha256(&vec![b'a'; 1_000_000])),
+        "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+    );
+}
+
+// Every message goes to every other peer, through the same envelope as the
+// network would use
+fn broadcast(peers: &mut [FairPeer], from: Player, message: FairMessage) -> Result<(), Cheat> {
+    let sent = version::to_string(&message).unwrap();
+    for peer in peers.iter_mut().filter(|p| p.seat != from) {
+        peer.receive(from, &version::from_str(&sent).unwrap())?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_fair_seed_between_peers() -> Result<(), Cheat> {
+    let mut peers: Vec<FairPeer> = (0..3).map(|seat| FairPeer::random(seat, 3)).collect();
+    assert!(peers[0].reveal().is_err());
+    for seat in 0..3 {
+        let message = peers[seat].commit();
+        broadcast(&mut peers, seat, message)?;
+    }
+    for seat in 0..3 {
+        let message = peers[seat].reveal().unwrap();
+        broadcast(&mut peers, seat, message)?;
+    }
+
+    let seed = peers[0].exchange.seed().unwrap();
+    let boards: Vec<MasterBoard> = peers
+        .iter()
+        .map(|p| MasterBoard::new(3, &Setup::base(), p.exchange.rng().unwrap()).unwrap())
+        .collect();
+    for (peer, board) in peers.iter().zip(boards.iter()) {
+        assert!(peer.exchange.withheld().is_empty());
+        assert_eq!(peer.exchange.seed().unwrap(), seed);
+        assert_eq!(board.state_hash(), boards[0].state_hash());
+    }
+
+    // Any single secret changes the seed
+    let secrets = [[1; 32], [2; 32]];
+    let mut exchange = SeedExchange::new(2);
+    for (p, secret) in secrets.iter().enumerate() {
+        exchange.receive(p, &FairMessage::Commit(commitment(p, secret)))?;
+    }
+    for (p, secret) in secrets.iter().enumerate() {
+        exchange.receive(p, &FairMessage::Reveal(*secret))?;
+    }
+    let mut other = SeedExchange::new(2);
+    for (p, secret) in [[1; 32], [3; 32]].iter().enumerate() {
+        other.receive(p, &FairMessage::Commit(commitment(p, secret)))?;
+        other.receive(p, &FairMessage::Reveal(*secret)).ok();
+    }
+    other.receive(0, &FairMessage::Reveal([1; 32]))?;
+    assert_ne!(exchange.seed().unwrap(), other.seed().unwrap());
+    Ok(())
+}
+
+#[test]
+fn test_cheating_is_caught() {
+    let secrets = [[7; 32], [8; 32], [9; 32]];
+    let mut exchange = SeedExchange::new(3);
+    let commit = |p: Player| FairMessage::Commit(commitment(p, &secrets[p]));
+
+    assert_eq!(exchange.receive(3, &commit(0)), Err(Cheat::NotSeated(3)));
+    assert_eq!(exchange.receive(0, &commit(0)), Ok(()));
+    assert_eq!(exchange.receive(0, &commit(0)), Ok(()));
+    assert_eq!(exchange.receive(0, &commit(1)), Err(Cheat::Changed(0)));
+    assert_eq!(exchange.receive(1, &commit(1)), Ok(()));
+    assert_eq!(
+        exchange.receive(2, &FairMessage::Reveal(secrets[2])),
+        Err(Cheat::EarlyReveal(2))
+    );
+    // Kept until the last commitment, but can't change in the meantime
+    assert_eq!(
+        exchange.receive(0, &FairMessage::Reveal(secrets[0])),
+        Ok(())
+    );
+    assert_eq!(
+        exchange.receive(0, &FairMessage::Reveal(secrets[1])),
+        Err(Cheat::Changed(0))
+    );
+    assert_eq!(exchange.receive(2, &commit(2)), Ok(()));
+    assert_eq!(
+        exchange.receive(0, &FairMessage::Reveal(secrets[2])),
+        Err(Cheat::Changed(0))
+    );
+
+    // Somebody else's secret, or somebody else's commitment, won't open
+    assert_eq!(
+        exchange.receive(2, &FairMessage::Reveal(secrets[1])),
+        Err(Cheat::WrongReveal(2))
+    );
+    assert_eq!(
+        exchange.receive(2, &FairMessage::Reveal(secrets[2])),
+        Ok(())
+    );
+
+    assert!(!exchange.all_revealed());
+    assert!(exchange.seed().is_err());
+    assert_eq!(exchange.withheld(), vec![Cheat::Withheld(1)]);
+    assert_eq!(
+        exchange.receive(1, &FairMessage::Reveal(secrets[1])),
+        Ok(())
+    );
+    assert!(exchange.all_revealed());
+    assert!(exchange.seed().is_ok());
+}
+
+#[test]
+fn test_reveals_before_the_last_commitment() -> Result<(), Cheat> {
+    let mut peers: Vec<FairPeer> = (0..3).map(|seat| FairPeer::random(seat, 3)).collect();
+    let commits: Vec<FairMessage> = peers.iter().map(FairPeer::commit).collect();
+
+    // Peer 0 hears from everybody first and reveals straight away
+    peers[0].receive(1, &commits[1])?;
+    peers[0].receive(2, &commits[2])?;
+    let reveal_0 = peers[0].reveal().unwrap();
+
+    // Peer 1 gets that reveal before the commitment of peer 2
+    peers[1].receive(0, &commits[0])?;
+    peers[1].receive(0, &reveal_0)?;
+    peers[1].receive(2, &commits[2])?;
+    let reveal_1 = peers[1].reveal().unwrap();
+
+    // Peer 2 gets the reveal of peer 1 before the commitment of peer 0
+    peers[2].receive(1, &commits[1])?;
+    peers[2].receive(1, &reveal_1)?;
+    peers[2].receive(0, &commits[0])?;
+    peers[2].receive(0, &reveal_0)?;
+    let reveal_2 = peers[2].reveal().unwrap();
+
+    peers[0].receive(2, &reveal_2)?;
+    peers[0].receive(1, &reveal_1)?;
+    peers[1].receive(2, &reveal_2)?;
+    let seed = peers[0].exchange.seed().unwrap();
+    for peer in peers.iter() {
+        assert!(peer.exchange.all_revealed());
+        assert_eq!(peer.exchange.seed().unwrap(), seed);
+    }
+
+    // A reveal that was kept aside is still checked against its commitment
+    let secrets = [[4; 32], [5; 32]];
+    let mut exchange = SeedExchange::new(2);
+    exchange.receive(0, &FairMessage::Commit(commitment(0, &secrets[0])))?;
+    exchange.receive(0, &FairMessage::Reveal(secrets[1]))?;
+    assert_eq!(
+        exchange.receive(1, &FairMessage::Commit(commitment(1, &secrets[1]))),
+        Err(Cheat::WrongReveal(0))
+    );
+    assert!(exchange.all_committed());
+    assert_eq!(
+        exchange.withheld(),
+        vec![Cheat::Withheld(0), Cheat::Withheld(1)]
+    );
+    Ok(())
+}
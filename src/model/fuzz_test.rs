@@ -0,0 +1,198 @@
+use super::{api::*, cards::*, local::hide_card_info, master::*};
+use crate::smallrng::*;
+use rand::Rng;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+const GAMES: u64 = 60;
+const STEPS: usize = 400;
+
+#[test]
+fn fuzz_random_games() {
+    let mut seeds = SRng::new(0);
+    for _ in 0..GAMES {
+        let seed: u64 = seeds.gen();
+        if let Err(reason) = catch_unwind(AssertUnwindSafe(|| fuzz_game(seed))) {
+            let reason = reason
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| reason.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_default();
+            panic!("Game with seed {} failed: {}", seed, reason);
+        }
+    }
+}
+
+fn fuzz_game(seed: u64) {
+    let mut rng = SRng::new(seed);
+    let players = rng.gen_range(2, 5);
+    let mut setup = Setup::base();
+    if rng.gen_bool(0.3) {
+        setup = setup.with_draft(6, 2);
+    }
+    if rng.gen_bool(0.3) {
+        setup.rules.purchase_to_hand_applies_to_gems = true;
+    }
+    if rng.gen_bool(0.3) {
+        setup.rules.starting_lives = vec![rng.gen_range(1, 20); players];
+    }
+
+    let mut state = MasterBoard::new(players, &setup, rng.fork()).unwrap();
+    let mut boards: Vec<Board> = (0..players).map(|p| state.scoped_to(p)).collect();
+    let cards = count_cards(&state);
+
+    for step in 0..STEPS {
+        if state.game_over {
+            break;
+        }
+        let (player, action) = random_action(&state, &mut rng);
+        let before = state.clone();
+        let deltas = match state.do_action_as(player, action.clone()) {
+            Ok(deltas) => deltas,
+            Err(_) => {
+                // Rejected actions must leave the board untouched
+                assert_eq!(state.state_hash(), before.state_hash());
+                continue;
+            }
+        };
+
+        let context = format!("step {} after {:?}", step, action);
+        assert_eq!(count_cards(&state), cards, "cards lost at {}", context);
+        for (p, board) in boards.iter_mut().enumerate() {
+            for delta in deltas.iter() {
+                if let Err(e) = board.apply(hide_card_info(p, delta.clone())) {
+                    panic!("{:?} applying {:?} at {}", e, delta, context);
+                }
+            }
+            assert_eq!(*board, state.scoped_to(p), "view of {} at {}", p, context);
+        }
+        let alive = state
+            .mats
+            .iter()
+            .filter(|m| m.is_alive() && !m.eliminated)
+            .count();
+        assert_eq!(state.game_over, alive <= 1, "game over at {}", context);
+    }
+}
+
+fn count_cards(state: &MasterBoard) -> usize {
+    let draft = state.draft.as_ref().map(|d| d.pool.len()).unwrap_or(0);
+    let mats: usize = state
+        .mats
+        .iter()
+        .map(|m| m.field.len() + m.hand.len() + m.discard.len() + m.deck.len())
+        .sum();
+    state.shop.len()
+        + state.shop_deck.len()
+        + state.gems.len()
+        + state.sacrificed.len()
+        + draft
+        + mats
+}
+
+// Mostly sensible actions, with the occasional out of range argument
+fn random_action(state: &MasterBoard, rng: &mut SRng) -> (Player, PlayerAction) {
+    let me = state.current_player;
+    let mat = &state.mats[me];
+    let other = (me + rng.gen_range(1, state.players)) % state.players;
+    let index = |rng: &mut SRng, len: usize| rng.gen_range(0, len + 1);
+
+    if rng.gen_bool(0.002) {
+        return (rng.gen_range(0, state.players), PlayerAction::Concede);
+    }
+    if rng.gen_bool(0.01) {
+        return (me, PlayerAction::TimedOut);
+    }
+    if let Some(draft) = state.draft.as_ref() {
+        return (me, PlayerAction::DraftPick(index(rng, draft.pool.len())));
+    }
+    if mat.must_discard > 0 {
+        return (me, PlayerAction::Discard(index(rng, mat.hand.len())));
+    }
+
+    let action = match rng.gen_range(0, 12) {
+        0..=3 => {
+            let i = index(rng, mat.hand.len());
+            let effects = mat.hand.get(i).and_then(|c| c.primary_ability());
+            PlayerAction::Play(i, random_arguments(state, effects, rng))
+        }
+        4 => {
+            let i = index(rng, mat.field.len());
+            let effects = mat.field.get(i).and_then(|c| c.card.expend_ability());
+            PlayerAction::ActivateExpendAbility(i, random_arguments(state, effects, rng))
+        }
+        5 => {
+            let i = index(rng, mat.field.len());
+            let effects = mat.field.get(i).and_then(|c| c.card.ally_ability());
+            PlayerAction::ActivateAllyAbility(i, random_arguments(state, effects, rng))
+        }
+        6 => {
+            let i = index(rng, mat.field.len());
+            let effects = mat.field.get(i).and_then(|c| c.card.sacrifice_ability());
+            PlayerAction::ActivateSacrificeAbility(i, random_arguments(state, effects, rng))
+        }
+        7 => PlayerAction::AttackPlayer(other, rng.gen_range(0, mat.combat + 2)),
+        8 => PlayerAction::AttackPlayerChampion(other, index(rng, state.mats[other].field.len())),
+        9 => PlayerAction::PurchaseFromShop(index(rng, state.shop.len())),
+        10 => PlayerAction::PurchaseFireGem,
+        _ => match rng.gen_range(0, 4) {
+            0 => PlayerAction::PlayAll,
+            1 => PlayerAction::ActivateAllAutomaticAbilities,
+            2 => PlayerAction::AttackWithEverything(other),
+            _ => PlayerAction::EndTurn,
+        },
+    };
+    (me, action)
+}
+
+// Walks the effects the way apply_effects does, picking an argument for each one that needs it
+fn random_arguments(
+    state: &MasterBoard,
+    effects: Option<Vec<Effect>>,
+    rng: &mut SRng,
+) -> Vec<EffectArgument> {
+    let me = state.current_player;
+    let mat = &state.mats[me];
+    let mut args = vec![];
+    let mut effects = effects.unwrap_or_default();
+    effects.reverse();
+    while let Some(effect) = effects.pop() {
+        let other = (me + rng.gen_range(1, state.players)) % state.players;
+        match effect {
+            Effect::Choice(first, second) => {
+                let mut branch = if rng.gen_bool(0.5) {
+                    args.push(EffectArgument::ChooseFirst);
+                    first
+                } else {
+                    args.push(EffectArgument::ChooseSecond);
+                    second
+                };
+                branch.reverse();
+                effects.append(&mut branch);
+            }
+            Effect::OpponentDiscards(_) => args.push(EffectArgument::Opponent(other)),
+            Effect::Sacrifice(amount) => {
+                for _ in 0..amount {
+                    args.push(if rng.gen_bool(0.5) {
+                        EffectArgument::CardInHand(rng.gen_range(0, mat.hand.len() + 1))
+                    } else {
+                        EffectArgument::CardInDiscard(rng.gen_range(0, mat.discard.len() + 1))
+                    });
+                }
+            }
+            Effect::PrepareChampion => args.push(EffectArgument::Champion(
+                me,
+                rng.gen_range(0, mat.field.len() + 1),
+            )),
+            Effect::StunChampion => args.push(EffectArgument::Champion(
+                other,
+                rng.gen_range(0, state.mats[other].field.len() + 1),
+            )),
+            Effect::PutCardFromDiscardOverDeck | Effect::PutChampionFromDiscardOverDeck => args
+                .push(EffectArgument::CardInDiscard(
+                    rng.gen_range(0, mat.discard.len() + 1),
+                )),
+            _ => {}
+        }
+    }
+    args
+}
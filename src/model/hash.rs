@@ -0,0 +1,59 @@
+use super::api::GameCard;
+
+// FNV-1a, small and stable across platforms and versions of our dependencies
+const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME: u64 = 0x0100_0000_01b3;
+
+// Feeds a state field by field, in a fixed order, into a 64 bit hash
+pub struct StateHasher {
+    hash: u64,
+}
+
+impl StateHasher {
+    pub fn new() -> StateHasher {
+        StateHasher { hash: OFFSET_BASIS }
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        for byte in bytes {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(PRIME);
+        }
+        self
+    }
+
+    pub fn u64(&mut self, value: u64) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub fn usize(&mut self, value: usize) -> &mut Self {
+        self.u64(value as u64)
+    }
+
+    pub fn bool(&mut self, value: bool) -> &mut Self {
+        self.bytes(&[value as u8])
+    }
+
+    // Prefixed with its length so consecutive strings can't be confused
+    pub fn str(&mut self, value: &str) -> &mut Self {
+        self.usize(value.len()).bytes(value.as_bytes())
+    }
+
+    // Cards are hashed by id, which doesn't depend on the position of the
+    // variant nor on how serde or Debug write it
+    pub fn card<C: GameCard>(&mut self, card: &C) -> &mut Self {
+        self.str(card.id())
+    }
+
+    pub fn cards<C: GameCard>(&mut self, cards: &[C]) -> &mut Self {
+        self.usize(cards.len());
+        for card in cards {
+            self.card(card);
+        }
+        self
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.hash
+    }
+}
@@ -0,0 +1,185 @@
+use super::{
+    api::{Board, BoardDelta, BoardDeltaError, Location, Player},
+    cards::{Card, Setup},
+};
+
+// Cards along with a weight, a probability or an expected amount of copies
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution(pub Vec<(Card, f64)>);
+
+impl Distribution {
+    fn from_counts(counts: Vec<(Card, usize)>, scale: f64) -> Distribution {
+        Distribution(
+            counts
+                .into_iter()
+                .map(|(card, n)| (card, n as f64 * scale))
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, card: &Card) -> f64 {
+        self.0
+            .iter()
+            .find(|(c, _)| c == card)
+            .map(|(_, w)| *w)
+            .unwrap_or(0.0)
+    }
+
+    pub fn total(&self) -> f64 {
+        self.0.iter().map(|(_, w)| w).sum()
+    }
+}
+
+// Follows a game from the point of view of a player, counting every card that
+// was seen to guess what is in the hidden zones. Cards that are not known are
+// assumed to be uniformly shuffled between the hand and the deck of their owner.
+#[derive(Clone, Debug)]
+pub struct Inference {
+    pub board: Board,
+    // What is in the hand or deck of each player, whether it is known or not
+    pools: Vec<Vec<Card>>,
+    shop_deck: Vec<Card>,
+}
+
+impl Inference {
+    // The board must be the one of the start of the game
+    pub fn new(setup: &Setup, board: Board) -> Inference {
+        let mut pools: Vec<Vec<Card>> = (0..board.players).map(|i| setup.loadout(i).deck).collect();
+        // Drafted cards are added to the decks by the deltas
+        if board.is_drafting() {
+            for pool in pools.iter_mut() {
+                pool.clear();
+            }
+        }
+
+        let mut shop_deck = setup.shop_deck.clone();
+        for card in board.shop.iter().chain(board.draft_pool.iter()) {
+            remove(&mut shop_deck, card);
+        }
+        Inference {
+            board,
+            pools,
+            shop_deck,
+        }
+    }
+
+    pub fn observe(&mut self, delta: BoardDelta) -> Result<(), BoardDeltaError> {
+        if let BoardDelta::Move(from, _, to, Some(card)) = &delta {
+            match (from, to) {
+                (Location::Hand(a), Location::Deck(b)) | (Location::Deck(a), Location::Hand(b))
+                    if a == b => {}
+                _ => {
+                    if let Location::Hand(p) | Location::Deck(p) = from {
+                        remove(&mut self.pools[*p], card);
+                    }
+                    if let Location::Hand(p) | Location::Deck(p) = to {
+                        self.pools[*p].push(card.clone());
+                    }
+                    if let Location::ShopDeck = from {
+                        remove(&mut self.shop_deck, card);
+                    }
+                    if let Location::ShopDeck = to {
+                        self.shop_deck.push(card.clone());
+                    }
+                }
+            }
+        }
+        self.board.apply(delta)
+    }
+
+    // Cards in the hand or deck of the player that could be anywhere in them
+    pub fn unknown_cards(&self, player: Player) -> Vec<Card> {
+        let mat = &self.board.mats[player];
+        let mut unknown = self.pools[player].clone();
+        for card in mat.known_deck.iter().chain(mat.known_hand.iter()) {
+            remove(&mut unknown, card);
+        }
+        if player == self.board.you {
+            for card in self.board.your_hand.iter() {
+                remove(&mut unknown, card);
+            }
+        }
+        unknown
+    }
+
+    fn unknown_in_hand(&self, player: Player) -> usize {
+        if player == self.board.you {
+            0
+        } else {
+            let mat = &self.board.mats[player];
+            mat.hand.saturating_sub(mat.known_hand.len())
+        }
+    }
+
+    fn unknown_in_deck(&self, player: Player) -> usize {
+        let mat = &self.board.mats[player];
+        mat.deck.saturating_sub(mat.known_deck.len())
+    }
+
+    // Expected amount of copies of each card in the hand
+    pub fn hand(&self, player: Player) -> Distribution {
+        if player == self.board.you {
+            return Distribution::from_counts(count(&self.board.your_hand), 1.0);
+        }
+        let mut hand = Distribution::from_counts(count(&self.board.mats[player].known_hand), 1.0);
+        self.add_unknown(&mut hand, player, self.unknown_in_hand(player));
+        hand
+    }
+
+    // Expected amount of copies of each card in the deck
+    pub fn deck(&self, player: Player) -> Distribution {
+        let mut deck = Distribution::from_counts(count(&self.board.mats[player].known_deck), 1.0);
+        self.add_unknown(&mut deck, player, self.unknown_in_deck(player));
+        deck
+    }
+
+    fn add_unknown(&self, distribution: &mut Distribution, player: Player, slots: usize) {
+        let unknown = self.unknown_cards(player);
+        if unknown.is_empty() {
+            return;
+        }
+        let share = slots as f64 / unknown.len() as f64;
+        for (card, n) in count(&unknown) {
+            match distribution.0.iter_mut().find(|(c, _)| *c == card) {
+                Some((_, w)) => *w += n as f64 * share,
+                None => distribution.0.push((card, n as f64 * share)),
+            }
+        }
+    }
+
+    // Chance of each card being the next one drawn by the player
+    pub fn next_draw(&self, player: Player) -> Distribution {
+        let mat = &self.board.mats[player];
+        if let Some(card) = mat.known_deck.last() {
+            return Distribution(vec![(card.clone(), 1.0)]);
+        }
+        if mat.deck == 0 {
+            // The discard pile gets shuffled into the deck
+            return Distribution::from_counts(count(&mat.discard), 1.0 / mat.discard.len() as f64);
+        }
+        let unknown = self.unknown_cards(player);
+        Distribution::from_counts(count(&unknown), 1.0 / unknown.len() as f64)
+    }
+
+    // What is left in the shop deck, in no particular order
+    pub fn shop_deck(&self) -> &[Card] {
+        &self.shop_deck
+    }
+}
+
+fn remove(cards: &mut Vec<Card>, card: &Card) {
+    if let Some(i) = cards.iter().position(|c| c == card) {
+        cards.remove(i);
+    }
+}
+
+fn count(cards: &[Card]) -> Vec<(Card, usize)> {
+    let mut counts: Vec<(Card, usize)> = vec![];
+    for card in cards {
+        match counts.iter_mut().find(|(c, _)| c == card) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((card.clone(), 1)),
+        }
+    }
+    counts
+}
@@ -0,0 +1,127 @@
+use super::{api::*, cards::*, inference::*, local::hide_card_info, master::*};
+use crate::smallrng::*;
+
+fn observe_all(inference: &mut Inference, deltas: &[BoardDelta]) {
+    for delta in deltas {
+        let delta = hide_card_info(inference.board.you, delta.clone());
+        inference.observe(delta).unwrap();
+    }
+}
+
+fn assert_same_cards(a: &[Card], b: &[Card]) {
+    let mut b = b.to_vec();
+    for card in a {
+        let i = b
+            .iter()
+            .position(|c| c == card)
+            .unwrap_or_else(|| panic!("{:?} is missing", card));
+        b.remove(i);
+    }
+    assert!(b.is_empty(), "{:?} are left over", b);
+}
+
+fn copies(cards: &[Card], card: &Card) -> f64 {
+    cards.iter().filter(|c| *c == card).count() as f64
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn test_initial_inference() {
+    let mut setup = Setup::base();
+    setup.rules.first_player = FirstPlayer::Fixed(0);
+    let state = MasterBoard::new(2, &setup, SRng::new(0)).unwrap();
+    let inference = Inference::new(&setup, state.scoped_to(1));
+
+    // 7 of the 10 starting cards are gold, and 3 of them are in the hand
+    assert!(close(inference.hand(0).get(&Card::Gold), 2.1));
+    assert!(close(inference.deck(0).get(&Card::Gold), 4.9));
+    assert!(close(inference.hand(0).total(), 3.0));
+    assert!(close(inference.next_draw(0).get(&Card::Gold), 0.7));
+    assert!(close(inference.next_draw(0).total(), 1.0));
+
+    // Our own hand is known, so only the deck is guessed
+    let mine = inference.hand(1);
+    assert!(close(mine.total(), 5.0));
+    assert!(close(inference.deck(1).total(), 5.0));
+
+    assert_same_cards(inference.shop_deck(), &state.shop_deck);
+}
+
+#[test]
+fn test_inference_follows_the_game() -> Result<(), &'static str> {
+    let setup = Setup::base();
+    let mut state = MasterBoard::new(2, &setup, SRng::new(14279))?;
+    let mut inference = Inference::new(&setup, state.scoped_to(0));
+    let mut decks: Vec<usize> = state.mats.iter().map(|m| m.deck.len()).collect();
+    let mut reshuffled = false;
+
+    for _ in 0..6 {
+        let player = state.current_player;
+        while !state.mats[player].hand.is_empty() {
+            let deltas = state.do_action(PlayerAction::Play(0, vec![]))?;
+            observe_all(&mut inference, &deltas);
+        }
+        if state.mats[player].gold >= state.shop[0].cost() {
+            let deltas = state.do_action(PlayerAction::PurchaseFromShop(0))?;
+            observe_all(&mut inference, &deltas);
+        }
+        let deltas = state.do_action(PlayerAction::EndTurn)?;
+        observe_all(&mut inference, &deltas);
+
+        assert_eq!(inference.board, state.scoped_to(0));
+        assert_same_cards(inference.shop_deck(), &state.shop_deck);
+        for (p, deck) in decks.iter_mut().enumerate() {
+            let mat = &state.mats[p];
+            assert!(close(inference.hand(p).total(), mat.hand.len() as f64));
+            assert!(close(inference.deck(p).total(), mat.deck.len() as f64));
+            reshuffled |= mat.deck.len() > *deck;
+            *deck = mat.deck.len();
+        }
+
+        // Our own hand is known, so the rest of our cards are in the deck
+        for card in state.mats[0].hand.iter().chain(state.mats[0].deck.iter()) {
+            let mat = &state.mats[0];
+            assert!(close(inference.hand(0).get(card), copies(&mat.hand, card)));
+            assert!(close(inference.deck(0).get(card), copies(&mat.deck, card)));
+        }
+        // The opponent's cards are spread evenly between their hand and deck
+        let mat = &state.mats[1];
+        let in_deck = mat.deck.len() as f64 / (mat.hand.len() + mat.deck.len()) as f64;
+        for card in mat.hand.iter().chain(mat.deck.iter()) {
+            let n = copies(&mat.hand, card) + copies(&mat.deck, card);
+            assert!(close(inference.deck(1).get(card), n * in_deck));
+            assert!(close(inference.hand(1).get(card), n * (1.0 - in_deck)));
+        }
+    }
+    assert!(reshuffled);
+    Ok(())
+}
+
+#[test]
+fn test_known_top_of_deck() -> Result<(), &'static str> {
+    let mut setup = Setup::base();
+    setup.rules.first_player = FirstPlayer::Fixed(0);
+    let mut state = MasterBoard::new(2, &setup, SRng::new(777))?;
+    state.mats[0].hand[0] = Card::SmashAndGrab;
+    state.mats[0].discard = vec![Card::HitJob];
+    let mut inference = Inference::new(&setup, state.scoped_to(1));
+
+    let deltas = state.do_action(PlayerAction::Play(
+        0,
+        vec![
+            EffectArgument::ChooseSecond,
+            EffectArgument::CardInDiscard(0),
+        ],
+    ))?;
+    observe_all(&mut inference, &deltas);
+
+    assert_eq!(
+        inference.next_draw(0),
+        Distribution(vec![(Card::HitJob, 1.0)])
+    );
+    assert!(close(inference.deck(0).get(&Card::HitJob), 1.0));
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use super::{
+    api::{BoardDelta, Player, PlayerAction},
+    cards::Setup,
+    master::MasterBoard,
+    version,
+};
+use crate::smallrng::SRng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// Actions between snapshots taken while recording
+pub const SNAPSHOT_INTERVAL: usize = 100;
+
+// Everything needed to replay a game from scratch, the setup carries the house rules
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Journal {
+    pub players: usize,
+    pub setup: Setup,
+    pub rng: SRng,
+    // Along with the seat acting, as concessions can happen out of turn
+    pub actions: Vec<(Player, PlayerAction)>,
+    // Journals from before snapshots have none, replaying them starts from scratch
+    pub snapshots: Vec<Snapshot>,
+    // No snapshots are taken while recording when zero
+    pub snapshot_interval: usize,
+}
+
+// The game as it was before the action at the index
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Snapshot {
+    pub at: usize,
+    pub board: MasterBoard,
+}
+
+impl Journal {
+    pub fn new(players: usize, setup: Setup, rng: SRng) -> Journal {
+        Journal {
+            players,
+            setup,
+            rng,
+            actions: vec![],
+            snapshots: vec![],
+            snapshot_interval: SNAPSHOT_INTERVAL,
+        }
+    }
+
+    pub fn start(&self) -> Result<MasterBoard, &'static str> {
+        MasterBoard::new(self.players, &self.setup, self.rng.clone())
+    }
+
+    // Only actions that were accepted by the board should be recorded
+    pub fn record(&mut self, player: Player, action: PlayerAction) {
+        self.actions.push((player, action));
+    }
+
+    pub fn replay(&self) -> Result<MasterBoard, &'static str> {
+        self.board_at(self.actions.len())
+    }
+
+    // The game as it was before the action at the given index, replayed from
+    // the nearest snapshot
+    pub fn board_at(&self, at: usize) -> Result<MasterBoard, &'static str> {
+        if at > self.actions.len() {
+            return Err("No such action in journal");
+        }
+        let nearest = self
+            .snapshots
+            .iter()
+            .filter(|s| s.at <= at)
+            .max_by_key(|s| s.at);
+        let (mut board, from) = match nearest {
+            Some(snapshot) => (snapshot.board.clone(), snapshot.at),
+            None => (self.start()?, 0),
+        };
+        for (player, action) in self.actions[from..at].iter() {
+            board.do_action_as(*player, action.clone())?;
+        }
+        Ok(board)
+    }
+
+    // Takes a snapshot of the board as it is after the recorded actions
+    pub fn snapshot(&mut self, board: &MasterBoard) {
+        let at = self.actions.len();
+        self.snapshots.retain(|s| s.at != at);
+        self.snapshots.push(Snapshot {
+            at,
+            board: board.clone(),
+        });
+    }
+
+    // Replays from scratch checking every snapshot along the way
+    pub fn verify(&self) -> Result<MasterBoard, &'static str> {
+        let mut board = self.start()?;
+        for at in 0..=self.actions.len() {
+            for snapshot in self.snapshots.iter().filter(|s| s.at == at) {
+                if snapshot.board.state_hash() != board.state_hash() {
+                    return Err("Snapshot doesn't match the recorded actions");
+                }
+            }
+            if let Some((player, action)) = self.actions.get(at) {
+                board.do_action_as(*player, action.clone())?;
+            }
+        }
+        if self.snapshots.iter().any(|s| s.at > self.actions.len()) {
+            return Err("Snapshot past the recorded actions");
+        }
+        Ok(board)
+    }
+
+    // The same journal with a snapshot every `interval` actions, replacing the
+    // ones it had, and checked to end up in the same game
+    pub fn compact(&self, interval: usize) -> Result<Journal, &'static str> {
+        let end = self.verify()?;
+        let mut journal = Journal {
+            actions: vec![],
+            snapshots: vec![],
+            snapshot_interval: interval,
+            ..self.clone()
+        };
+        let mut board = journal.start()?;
+        for (player, action) in self.actions.iter() {
+            journal.do_action_as(&mut board, *player, action.clone())?;
+        }
+        if journal.replay()?.state_hash() != end.state_hash() {
+            return Err("Compacted journal doesn't replay the same game");
+        }
+        Ok(journal)
+    }
+
+    pub fn truncated(&self, at: usize) -> Result<Journal, &'static str> {
+        if at > self.actions.len() {
+            return Err("No such action in journal");
+        }
+        let mut journal = self.clone();
+        journal.actions.truncate(at);
+        journal.snapshots.retain(|s| s.at <= at);
+        Ok(journal)
+    }
+
+    // Plays and records the action if it succeeds
+    pub fn do_action(
+        &mut self,
+        board: &mut MasterBoard,
+        action: PlayerAction,
+    ) -> Result<Vec<BoardDelta>, &'static str> {
+        let player = board.current_player;
+        self.do_action_as(board, player, action)
+    }
+
+    pub fn do_action_as(
+        &mut self,
+        board: &mut MasterBoard,
+        player: Player,
+        action: PlayerAction,
+    ) -> Result<Vec<BoardDelta>, &'static str> {
+        let deltas = board.do_action_as(player, action.clone())?;
+        self.record(player, action);
+        if self.snapshot_interval > 0 && self.actions.len().is_multiple_of(self.snapshot_interval) {
+            self.snapshot(board);
+        }
+        Ok(deltas)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), &'static str> {
+        let data = version::to_string(self)?;
+        fs::write(path, data).map_err(|_| "Could not write journal file")
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Journal, &'static str> {
+        let data = fs::read_to_string(path).map_err(|_| "Could not read journal file")?;
+        version::from_str(&data)
+    }
+}
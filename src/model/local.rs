@@ -1,111 +1,249 @@
-use super::{api::*, cards::*, master::*};
+use super::{api::*, cards::*, journal::Journal, master::*};
+use crate::lobby::{Game, GameApi, GameSetup};
 use crate::smallrng::*;
-use std::sync::mpsc::{channel, Receiver, Sender};
-use std::time::Duration;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub struct HeroRealms;
+
+impl Game for HeroRealms {
+    type Setup = Setup;
+    type Api = LocalClient;
+    type SeatOption = Ancestry;
+}
+
+impl GameSetup for Setup {
+    fn min_players(&self) -> usize {
+        2
+    }
+    fn max_players(&self) -> usize {
+        4
+    }
+}
 
 pub struct LocalServer {
     board: MasterBoard,
+    journal: Journal,
+    // Where the journal is saved once the game is over, if anywhere
+    journal_path: Option<PathBuf>,
     remaining_deltas: Vec<Vec<BoardDelta>>,
     connections: Vec<LocalServerConnection>,
+    turn_started: Instant,
+    // What is left of each player total clock, if there is one
+    time_left: Vec<Option<Duration>>,
 }
 
 struct LocalServerConnection {
-    deltas: Sender<BoardDelta>,
-    actions: Receiver<PlayerAction>,
+    messages: Sender<ServerMessage>,
+    actions: Receiver<ClientMessage>,
 }
 
 impl LocalServer {
-    pub fn new(seed: u64, players: usize) -> (Self, Vec<LocalClient>) {
+    pub fn new(seed: u64, players: usize) -> Result<(Self, Vec<LocalClient>), &'static str> {
+        LocalServer::with_setup(SRng::new(seed), players, &Setup::base())
+    }
+
+    pub fn with_setup(
+        rng: SRng,
+        players: usize,
+        setup: &Setup,
+    ) -> Result<(Self, Vec<LocalClient>), &'static str> {
+        LocalServer::from_journal(Journal::new(players, setup.clone(), rng))
+    }
+
+    // Goes on with a recorded game, or with a branch of it, from its last action
+    pub fn from_journal(journal: Journal) -> Result<(Self, Vec<LocalClient>), &'static str> {
         let mut connections = vec![];
         let mut clients = vec![];
-        let board = MasterBoard::new(2, &Setup::base(), SRng::new(seed));
+        let board = journal.replay()?;
+        let players = board.players;
+        let total = board.rules.clock.as_ref().and_then(|c| c.total);
         for i in 0..players {
-            let (send_delta, receive_delta) = channel::<BoardDelta>();
-            let (send_action, receive_action) = channel::<PlayerAction>();
+            let (send_message, receive_message) = channel::<ServerMessage>();
+            let (send_action, receive_action) = channel::<ClientMessage>();
 
             connections.push(LocalServerConnection {
-                deltas: send_delta,
+                messages: send_message,
                 actions: receive_action,
             });
             clients.push(LocalClient {
                 board: board.scoped_to(i),
                 player: i,
+                desynced: false,
                 send_action,
-                receive_delta,
+                receive_message,
             });
         }
         let server = LocalServer {
             board,
+            journal,
+            journal_path: None,
             remaining_deltas: std::iter::repeat(vec![]).take(players).collect(),
             connections,
+            turn_started: Instant::now(),
+            time_left: vec![total; players],
         };
 
-        (server, clients)
+        Ok((server, clients))
+    }
+
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    pub fn record_to<P: Into<PathBuf>>(&mut self, path: P) {
+        self.journal_path = Some(path.into());
     }
 
+    // Waits for a message from any player, or for the current one to run out of time
     pub fn process_action(&mut self) -> Result<(), &'static str> {
-        let action = self.connections[self.board.current_player]
-            .actions
-            .recv_timeout(Duration::from_secs(5))
-            .map_err(|_| "Timeout when waiting for player action")?;
+        loop {
+            if self.board.game_over {
+                return Err("The game is already over");
+            }
+            for player in 0..self.board.players {
+                if self.board.mats[player].eliminated {
+                    continue;
+                }
+                match self.connections[player].actions.try_recv() {
+                    Ok(ClientMessage::RequestSnapshot) => {
+                        let snapshot = ServerMessage::Snapshot(self.board.scoped_to(player));
+                        return self.send(player, snapshot);
+                    }
+                    Ok(ClientMessage::Action(PlayerAction::TimedOut)) => {
+                        return Err("Only the server can time out")
+                    }
+                    Ok(ClientMessage::Action(action)) => return self.play(player, action),
+                    Err(TryRecvError::Empty) => {}
+                    Err(TryRecvError::Disconnected) => {
+                        return self.play(player, PlayerAction::Concede)
+                    }
+                }
+            }
+            if let Some(deadline) = self.deadline() {
+                if Instant::now() >= deadline {
+                    return self.play(self.board.current_player, PlayerAction::TimedOut);
+                }
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
 
-        for player in 0..self.board.players {
-            self.connections[player]
-                .deltas
-                .send(BoardDelta::PlayerDeclaredAction(action.clone()))
-                .map_err(|_| "A client has died")?;
+    fn deadline(&self) -> Option<Instant> {
+        let clock = self.board.rules.clock.as_ref()?;
+        let limit = match (clock.per_turn, self.time_left[self.board.current_player]) {
+            (Some(turn), Some(total)) => Some(turn.min(total)),
+            (turn, total) => turn.or(total),
+        }?;
+        Some(self.turn_started + limit)
+    }
+
+    fn play(&mut self, player: Player, action: PlayerAction) -> Result<(), &'static str> {
+        let previous = self.board.current_player;
+        let result = self
+            .journal
+            .do_action_as(&mut self.board, player, action.clone());
+        self.broadcast(BoardDelta::PlayerDeclaredAction(action))?;
+        let deltas = result?;
+        // What was recorded has to replay into the authoritative game, or
+        // neither replays nor resyncs can be trusted
+        if self.journal.replay()?.state_hash() != self.board.state_hash() {
+            return Err("The journal doesn't replay into the game");
+        }
+        if self.board.game_over {
+            if let Some(path) = self.journal_path.as_ref() {
+                self.journal.save(path)?;
+            }
         }
 
-        for delta in self.board.do_action(action.clone())? {
-            for player in 0..self.board.players {
-                self.connections[player]
-                    .deltas
-                    .send(self.hide_card_info(player, delta.clone()))
-                    .map_err(|_| "A client has died")?;
+        // The clock only runs for the current player
+        if self.board.current_player != previous {
+            let now = Instant::now();
+            let elapsed = now - self.turn_started;
+            if let Some(left) = self.time_left[previous].as_mut() {
+                *left = left.checked_sub(elapsed).unwrap_or_default();
             }
+            self.turn_started = now;
         }
 
+        for delta in deltas {
+            self.broadcast(delta)?;
+        }
+        for player in 0..self.board.players {
+            let hash = self.board.scoped_to(player).state_hash();
+            self.send(player, ServerMessage::StateHash(hash))?;
+        }
         Ok(())
     }
 
-    fn hide_card_info(&self, player: Player, delta: BoardDelta) -> BoardDelta {
-        match delta {
-            BoardDelta::Move(from, index, to, card) => BoardDelta::Move(
-                from.clone(),
-                index,
-                to.clone(),
-                if self.can_see(player, from) || self.can_see(player, to) {
-                    card
-                } else {
-                    None
-                },
-            ),
-            d => d,
+    fn broadcast(&self, delta: BoardDelta) -> Result<(), &'static str> {
+        for player in 0..self.board.players {
+            let delta = hide_card_info(player, delta.clone());
+            self.send(player, ServerMessage::Delta(delta))?;
         }
+        Ok(())
     }
 
-    fn can_see(&self, player: Player, loc: Location) -> bool {
-        match loc {
-            Location::Deck(_) => false,
-            Location::Discard(_) => true,
-            Location::Field(_) => true,
-            Location::FireGems => true,
-            Location::Hand(p) => player == p,
-            Location::Sacrifice => true,
-            Location::Shop => true,
-            Location::ShopDeck => false,
+    fn send(&self, player: Player, message: ServerMessage) -> Result<(), &'static str> {
+        let sent = self.connections[player].messages.send(message);
+        // Eliminated players may have left already
+        if sent.is_err() && !self.board.mats[player].eliminated {
+            return Err("A client has died");
         }
+        Ok(())
+    }
+}
+
+// What a player gets to see of a delta, cards moving between hidden zones are removed
+pub fn hide_card_info(player: Player, delta: BoardDelta) -> BoardDelta {
+    match delta {
+        BoardDelta::Move(from, index, to, card) => BoardDelta::Move(
+            from.clone(),
+            index,
+            to.clone(),
+            if can_see(player, from) || can_see(player, to) {
+                card
+            } else {
+                None
+            },
+        ),
+        d => d,
+    }
+}
+
+fn can_see(player: Player, loc: Location) -> bool {
+    match loc {
+        Location::Deck(_) => false,
+        Location::Discard(_) => true,
+        Location::Field(_) => true,
+        Location::FireGems => true,
+        Location::Hand(p) => player == p,
+        Location::Sacrifice => true,
+        Location::Shop => true,
+        Location::ShopDeck => false,
+        Location::DraftPool => true,
     }
 }
 
 pub struct LocalClient {
     board: Board,
     player: usize,
-    send_action: Sender<PlayerAction>,
-    receive_delta: Receiver<BoardDelta>,
+    // Waiting for a snapshot after the board stopped matching the server
+    desynced: bool,
+    send_action: Sender<ClientMessage>,
+    receive_message: Receiver<ServerMessage>,
+}
+
+impl LocalClient {
+    fn desync(&mut self) {
+        self.desynced = true;
+        let _ = self.send_action.send(ClientMessage::RequestSnapshot);
+    }
 }
 
-impl LocalClient {}
+impl GameApi for LocalClient {}
 
 impl Api for LocalClient {
     type Error = &'static str;
@@ -113,20 +251,43 @@ impl Api for LocalClient {
         &self.board
     }
     fn do_action(&mut self, action: PlayerAction) -> Result<(), Self::Error> {
-        if self.board.current_player != self.player {
+        if self.desynced {
+            Err("Desynchronized from the server")
+        } else if self.board.current_player != self.player
+            && !matches!(action, PlayerAction::Concede)
+        {
             Err("Not the current player")
         } else {
             self.send_action
-                .send(action)
+                .send(ClientMessage::Action(action))
                 .map_err(|_| "Failed to send action")?;
             Ok(())
         }
     }
 
+    // Deltas received while desynced are skipped, the snapshot already has them
     fn poll_deltas(&mut self) -> Vec<BoardDelta> {
-        let deltas: Vec<_> = self.receive_delta.try_iter().collect();
-        for d in deltas.iter() {
-            self.board.apply(d.clone());
+        let messages: Vec<_> = self.receive_message.try_iter().collect();
+        let mut deltas = vec![];
+        for message in messages {
+            match message {
+                ServerMessage::Snapshot(board) => {
+                    self.board = board;
+                    self.desynced = false;
+                }
+                _ if self.desynced => {}
+                ServerMessage::Delta(delta) => {
+                    if self.board.apply(delta.clone()).is_err() {
+                        self.desync();
+                    }
+                    deltas.push(delta);
+                }
+                ServerMessage::StateHash(hash) => {
+                    if hash != self.board.state_hash() {
+                        self.desync();
+                    }
+                }
+            }
         }
         deltas
     }
@@ -139,7 +300,7 @@ mod test {
 
     #[test]
     fn test_local1v1() -> Result<(), &'static str> {
-        let (s, mut c) = LocalServer::new(777, 2);
+        let (s, mut c) = LocalServer::with_setup(SRng::legacy(777), 2, &Setup::base())?;
         let s = Mutex::new(s);
         let wait = || s.lock().unwrap().process_action();
         let p1 = TestApi {
@@ -153,6 +314,94 @@ mod test {
         test_game_777(p1, p2)
     }
 
+    #[test]
+    fn test_clock_and_concession() -> Result<(), &'static str> {
+        let mut setup = Setup::base();
+        setup.rules.clock = Some(Clock {
+            per_turn: Some(Duration::from_millis(10)),
+            total: Some(Duration::from_secs(60)),
+            on_timeout: TimeoutPolicy::AutoEndTurn,
+        });
+        let (mut server, mut clients) = LocalServer::with_setup(SRng::new(777), 2, &setup)?;
+        let path = std::env::temp_dir().join(format!("heros-server-{}.json", std::process::id()));
+        server.record_to(&path);
+        let first = clients[0].get_board().current_player;
+        let second = (first + 1) % 2;
+
+        server.process_action()?;
+        for client in clients.iter_mut() {
+            client.poll_deltas();
+            assert_eq!(client.get_board().current_player, second);
+        }
+
+        clients[first].do_action(PlayerAction::Concede)?;
+        server.process_action()?;
+        for client in clients.iter_mut() {
+            client.poll_deltas();
+            assert!(client.get_board().game_over);
+            assert_eq!(client.get_board().mats[first].lives, 0);
+        }
+        server
+            .process_action()
+            .expect_err("The game is already over");
+
+        // The journal was written when the game ended
+        let journal = Journal::load(&path)?;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(journal.actions.len(), 2);
+        assert_eq!(journal.replay()?.state_hash(), server.board.state_hash());
+        Ok(())
+    }
+
+    #[test]
+    fn test_desync_and_resync() -> Result<(), &'static str> {
+        let (mut server, mut clients) = LocalServer::new(14279, 2)?;
+        let first = clients[0].get_board().current_player;
+        let second = (first + 1) % 2;
+        clients[second].board.mats[first].lives += 1;
+
+        clients[first].do_action(PlayerAction::Play(0, vec![]))?;
+        server.process_action()?;
+        clients[first].poll_deltas();
+        clients[second].poll_deltas();
+        assert!(!clients[first].desynced);
+        assert!(clients[second].desynced);
+        assert_eq!(
+            clients[second].do_action(PlayerAction::Concede),
+            Err("Desynchronized from the server")
+        );
+
+        server.process_action()?;
+        clients[second].poll_deltas();
+        assert!(!clients[second].desynced);
+        assert_eq!(clients[second].board, server.board.scoped_to(second));
+        assert_eq!(
+            clients[second].board.state_hash(),
+            server.board.scoped_to(second).state_hash()
+        );
+        assert_ne!(
+            clients[first].board.state_hash(),
+            clients[second].board.state_hash()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_journal_desync() -> Result<(), &'static str> {
+        let (mut server, mut clients) = LocalServer::new(14279, 2)?;
+        let first = clients[0].get_board().current_player;
+        clients[first].do_action(PlayerAction::Play(0, vec![]))?;
+        server.process_action()?;
+
+        server.journal.rng = SRng::new(14280);
+        clients[first].do_action(PlayerAction::Play(0, vec![]))?;
+        assert_eq!(
+            server.process_action(),
+            Err("The journal doesn't replay into the game")
+        );
+        Ok(())
+    }
+
     struct TestApi<A: Api, W>
     where
         W: FnMut() -> Result<(), A::Error>,
@@ -1,9 +1,17 @@
 use super::{
-    api::{Board, BoardDelta, EffectArgument, Location, Mat, PlayerAction},
-    cards::{Card, CardInField, Effect, PerAmount, Setup},
+    api::{
+        AbilityKind, ActionOutcome, Board, BoardDelta, Cause, EffectArgument, Entry, Location, Mat,
+        Player, PlayerAction, Rule,
+    },
+    cards::{
+        Ancestry, Card, CardInField, Effect, FirstPlayer, Loadout, PerAmount, Rules, Setup,
+        TimeoutPolicy, Trigger,
+    },
+    hash::StateHasher,
 };
-use crate::smallrng::{Rng, SRng};
+use crate::smallrng::{Algorithm, SRng};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct MasterBoard {
@@ -16,54 +24,250 @@ pub struct MasterBoard {
     pub players: usize,
     pub game_over: bool,
     pub mats: Vec<MasterMat>,
+    pub draft: Option<DraftState>,
+    pub rules: Rules,
     pub rng: SRng,
+    // Actions accepted so far, not part of the game state
+    pub action_count: usize,
 }
 
 impl MasterBoard {
-    pub fn new(players: usize, setup: &Setup, mut rng: SRng) -> MasterBoard {
+    pub fn new(players: usize, setup: &Setup, rng: SRng) -> Result<MasterBoard, &'static str> {
+        let loadouts = (0..players).map(|i| setup.loadout(i)).collect();
+        MasterBoard::with_loadouts(setup, loadouts, rng)
+    }
+
+    // Same as `new` but with explicit starting decks, one per seat. Fails on
+    // house rules that can't be dealt, like a first player with no seat or a
+    // starting hand larger than the deck
+    pub fn with_loadouts(
+        setup: &Setup,
+        loadouts: Vec<Loadout>,
+        mut rng: SRng,
+    ) -> Result<MasterBoard, &'static str> {
+        let players = loadouts.len();
+        if players == 0 {
+            return Err("No players");
+        }
         let mut shop_deck = setup.shop_deck.clone();
         rng.shuffle(&mut shop_deck);
-        let shop = draw(6, &mut shop_deck);
-        let current_player = rng.gen::<usize>() % players;
-        let mats: Vec<_> = (0..players)
-            .map(|i| {
-                let starting_cards = if i == current_player {
-                    3
-                } else if players > 2 && i == current_player + 1 {
-                    4
-                } else {
-                    5
-                };
-                MasterMat::new(
-                    format!("Player {}", i + 1),
-                    starting_cards,
-                    &setup,
-                    rng.fork(),
-                )
-            })
-            .collect();
-
-        MasterBoard {
+        // When drafting, the market and the starting hands are dealt
+        // once every pick has been made
+        let drafting = setup
+            .draft
+            .as_ref()
+            .filter(|rules| rules.pool > 0 && rules.picks > 0 && !shop_deck.is_empty());
+        let draft_pool = match drafting {
+            Some(rules) => draw(rules.pool.min(shop_deck.len()), &mut shop_deck),
+            None => vec![],
+        };
+        let rules = setup.rules.clone();
+        let shop = match drafting {
+            Some(_) => vec![],
+            None => draw(rules.market_size.min(shop_deck.len()), &mut shop_deck),
+        };
+        let current_player = match rules.first_player {
+            FirstPlayer::Random => (rng.next_u64() % players as u64) as usize,
+            FirstPlayer::Fixed(p) if p < players => p,
+            FirstPlayer::Fixed(_) => return Err("No such first player"),
+            FirstPlayer::Alternating(games) => games % players,
+        };
+        let mut mats = vec![];
+        for (i, loadout) in loadouts.iter().enumerate() {
+            let starting_cards = match drafting {
+                Some(_) => 0,
+                None => rules.starting_hand(players, (i + players - current_player) % players),
+            };
+            if starting_cards > loadout.deck.len() {
+                return Err("Starting hand larger than the deck");
+            }
+            let mut mat = MasterMat::new(
+                format!("Player {}", i + 1),
+                starting_cards,
+                loadout,
+                rng.fork(),
+            );
+            if let Some(lives) = rules.starting_lives.get(i) {
+                mat.lives = *lives;
+            }
+            mats.push(mat);
+        }
+        let gems = match rules.fire_gems {
+            Some(amount) => vec![Card::FireGem; amount],
+            None => setup.gems.clone(),
+        };
+        let draft = drafting.map(|rules| DraftState {
+            picks_left: (rules.picks * players).min(draft_pool.len()),
+            pool: draft_pool,
+            first_player: current_player,
+        });
+
+        Ok(MasterBoard {
             shop,
             players,
-            shop_deck: shop_deck,
-            gems: setup.gems.clone(),
+            shop_deck,
+            gems,
             sacrificed: vec![],
             current_player,
             game_over: false,
             mats,
+            draft,
+            rules,
             rng,
+            action_count: 0,
+        })
+    }
+
+    pub fn is_drafting(&self) -> bool {
+        self.draft.is_some()
+    }
+
+    fn draft_pick(&mut self, position: usize) -> Result<Trace, &'static str> {
+        let player = self.current_player;
+        let draft = self.draft.as_mut().ok_or("Not drafting")?;
+        if position >= draft.pool.len() {
+            return Err("No such card in draft pool");
+        }
+
+        let mut deltas = Trace::new(Cause::Action(PlayerAction::DraftPick(position)));
+        let card = draft.pool.remove(position);
+        draft.picks_left -= 1;
+        self.mats[player].deck.push(card.clone());
+        deltas.push(BoardDelta::Move(
+            Location::DraftPool,
+            position,
+            Location::Deck(player),
+            Some(card),
+        ));
+
+        if draft.picks_left > 0 {
+            self.current_player = self.next_player(player);
+            deltas.push(BoardDelta::ChangeCurrentPlayer(self.current_player));
+        } else {
+            deltas.append(&mut self.finish_draft()?);
         }
+        Ok(deltas)
+    }
+
+    // Returns the leftovers to the shop deck and deals like a regular game
+    fn finish_draft(&mut self) -> Result<Trace, &'static str> {
+        let draft = self.draft.take().ok_or("Not drafting")?;
+        let mut deltas = Trace::new(Cause::Rule(Rule::Draft));
+
+        for card in draft.pool {
+            deltas.push(BoardDelta::Move(
+                Location::DraftPool,
+                0,
+                Location::ShopDeck,
+                Some(card.clone()),
+            ));
+            self.shop_deck.push(card);
+        }
+        self.rng.shuffle(&mut self.shop_deck);
+
+        while self.shop.len() < self.rules.market_size {
+            match self.shop_deck.pop() {
+                Some(card) => {
+                    deltas.push(BoardDelta::Move(
+                        Location::ShopDeck,
+                        0,
+                        Location::Shop,
+                        Some(card.clone()),
+                    ));
+                    self.shop.push(card);
+                }
+                None => break,
+            }
+        }
+
+        for i in 0..self.players {
+            self.rng.shuffle(&mut self.mats[i].deck);
+            deltas.push(BoardDelta::ShuffleDeck(i));
+            let turn_position = (i + self.players - draft.first_player) % self.players;
+            let amount = self.rules.starting_hand(self.players, turn_position);
+            deltas.append(&mut self.apply_effects_for(
+                i,
+                vec![Effect::Draw(amount)],
+                vec![],
+                Cause::Rule(Rule::Draft),
+            )?);
+        }
+
+        self.current_player = draft.first_player;
+        deltas.push(BoardDelta::ChangeCurrentPlayer(self.current_player));
+        Ok(deltas)
+    }
+}
+
+// Deltas being made along with their causes, pushed ones take the current cause
+pub struct Trace {
+    pub entries: Vec<Entry>,
+    pub cause: Cause,
+}
+
+impl Trace {
+    pub fn new(cause: Cause) -> Trace {
+        Trace {
+            entries: vec![],
+            cause,
+        }
+    }
+
+    pub fn push(&mut self, delta: BoardDelta) {
+        self.entries.push(Entry {
+            cause: self.cause.clone(),
+            delta,
+        });
+    }
+
+    // Keeps the causes the other trace had
+    pub fn append(&mut self, other: &mut Trace) {
+        self.entries.append(&mut other.entries);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// Using up an ability, before any of its effects
+fn ability(card: &Card, kind: AbilityKind) -> Cause {
+    Cause::Ability {
+        card: card.clone(),
+        kind,
+        effect: None,
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct DraftState {
+    pub pool: Vec<Card>,
+    pub picks_left: usize,
+    pub first_player: Player,
+}
+
+fn take<C>(cards: &mut Vec<C>, index: usize) -> Option<C> {
+    match index < cards.len() {
+        true => Some(cards.remove(index)),
+        false => None,
+    }
+}
+
+fn take_from_pile(pile: &mut Vec<Card>, card: &Option<Card>) -> Option<Card> {
+    match card {
+        Some(card) => take(pile, pile.iter().rposition(|c| c == card)?),
+        None => pile.pop(),
     }
 }
 
-pub fn draw(amount: usize, source: &mut Vec<Card>) -> Vec<Card> {
+pub fn draw<C>(amount: usize, source: &mut Vec<C>) -> Vec<C> {
     source.split_off(source.len() - amount)
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct MasterMat {
     pub name: String,
+    pub ancestry: Ancestry,
     pub field: Vec<CardInField>,
     pub hand: Vec<Card>,
     pub discard: Vec<Card>,
@@ -75,26 +279,36 @@ pub struct MasterMat {
     pub next_action_purchase_to_top_of_deck: usize,
     pub next_purchase_to_top_of_deck: usize,
     pub next_purchase_to_hand: usize,
+    pub timeouts: usize,
+    pub eliminated: bool,
+    // How many cards on top of the deck every player has seen
+    pub known_deck: usize,
+    pub known_hand: Vec<Card>,
 }
 
 impl MasterMat {
-    pub fn new(name: String, starting_cards: usize, setup: &Setup, mut rng: SRng) -> MasterMat {
-        let mut deck = setup.player_deck.clone();
+    pub fn new(name: String, starting_cards: usize, loadout: &Loadout, mut rng: SRng) -> MasterMat {
+        let mut deck = loadout.deck.clone();
         rng.shuffle(&mut deck);
         let hand = draw(starting_cards, &mut deck);
         MasterMat {
             name,
+            ancestry: loadout.ancestry.clone(),
             field: vec![],
             hand,
             discard: vec![],
             deck,
-            lives: 50,
+            lives: loadout.lives,
             combat: 0,
             gold: 0,
             must_discard: 0,
             next_action_purchase_to_top_of_deck: 0,
             next_purchase_to_top_of_deck: 0,
             next_purchase_to_hand: 0,
+            timeouts: 0,
+            eliminated: false,
+            known_deck: 0,
+            known_hand: vec![],
         }
     }
 
@@ -103,55 +317,135 @@ impl MasterMat {
     }
 }
 
+// Things happening on the board that triggered abilities can react to.
+#[derive(Clone, Debug)]
+pub enum GameEvent {
+    Stunned(Player, Card),
+    Acquired(Player, Card),
+    TurnStarted(Player),
+}
+
 impl MasterBoard {
+    /// Resolves every triggered ability listening to `event`. Only the cards
+    /// of the affected player fire: the stunned card itself for
+    /// `Stunned`, or the cards in the player's field, in field order, for
+    /// the other events. Triggered abilities can't take arguments, and
+    /// `cause` is whatever raised the event.
+    pub fn resolve_triggers(
+        &mut self,
+        event: GameEvent,
+        cause: Cause,
+    ) -> Result<Trace, &'static str> {
+        let (player, sources) = match &event {
+            GameEvent::Stunned(p, card) => (*p, vec![card.clone()]),
+            GameEvent::Acquired(p, _) | GameEvent::TurnStarted(p) => (
+                *p,
+                self.mats[*p]
+                    .field
+                    .iter()
+                    .map(|cif| cif.card.clone())
+                    .collect(),
+            ),
+        };
+
+        // Every push below comes with the cause of the ability firing
+        let mut deltas = Trace::new(cause);
+        for card in sources {
+            for (trigger, effects) in card.triggered_abilities() {
+                let fires = match (&trigger, &event) {
+                    (Trigger::Stunned, GameEvent::Stunned(_, _)) => true,
+                    (Trigger::Acquire(kind), GameEvent::Acquired(_, acquired)) => {
+                        kind.matches(acquired)
+                    }
+                    (Trigger::StartOfTurn, GameEvent::TurnStarted(_)) => true,
+                    _ => false,
+                };
+                if fires {
+                    let cause = Cause::Ability {
+                        card: card.clone(),
+                        kind: AbilityKind::Triggered(trigger.clone()),
+                        effect: None,
+                    };
+                    deltas.cause = cause.clone();
+                    deltas.push(BoardDelta::TriggeredAbility(player, card.clone(), trigger));
+                    deltas.append(&mut self.apply_effects_for(player, effects, vec![], cause)?);
+                }
+            }
+        }
+        Ok(deltas)
+    }
+
     pub fn apply_effects(
         &mut self,
+        effects: Vec<Effect>,
+        effect_args: Vec<EffectArgument>,
+        cause: Cause,
+    ) -> Result<Trace, &'static str> {
+        self.apply_effects_for(self.current_player, effects, effect_args, cause)
+    }
+
+    /// Applies effects on behalf of `player`, who is not necessarily the
+    /// current one when resolving triggered abilities. The deltas of an
+    /// ability are traced to the effect that made them.
+    pub fn apply_effects_for(
+        &mut self,
+        player: Player,
         mut effects: Vec<Effect>,
         mut effect_args: Vec<EffectArgument>,
-    ) -> Result<Vec<BoardDelta>, &'static str> {
-        let mut deltas = vec![];
+        cause: Cause,
+    ) -> Result<Trace, &'static str> {
+        let mut deltas = Trace::new(cause.clone());
         effects.reverse();
         effect_args.reverse();
-        while !effects.is_empty() {
-            match effects.pop().unwrap() {
+        let mut resolved = 0;
+        while let Some(effect) = effects.pop() {
+            if let Cause::Ability { card, kind, .. } = &cause {
+                deltas.cause = Cause::Ability {
+                    card: card.clone(),
+                    kind: kind.clone(),
+                    effect: Some((resolved, effect.clone())),
+                };
+            }
+            resolved += 1;
+            match effect {
                 Effect::Gold(x) => {
-                    self.mats[self.current_player].gold += x;
-                    deltas.push(BoardDelta::IncreaseGold(self.current_player, x));
+                    self.mats[player].gold += x;
+                    deltas.push(BoardDelta::IncreaseGold(player, x));
                 }
                 Effect::Combat(x) => {
-                    self.mats[self.current_player].combat += x;
-                    deltas.push(BoardDelta::IncreaseCombat(self.current_player, x));
+                    self.mats[player].combat += x;
+                    deltas.push(BoardDelta::IncreaseCombat(player, x));
                 }
                 Effect::Heal(x) => {
-                    self.mats[self.current_player].lives += x;
-                    deltas.push(BoardDelta::IncreaseHealth(self.current_player, x));
+                    self.mats[player].lives += x;
+                    deltas.push(BoardDelta::IncreaseHealth(player, x));
                 }
                 Effect::Nothing => {}
                 Effect::Draw(x) => {
-                    let ref mut mat = self.mats[self.current_player];
+                    let mat = &mut self.mats[player];
                     for _ in 0..x {
                         if mat.deck.is_empty() && !mat.discard.is_empty() {
                             while !mat.discard.is_empty() {
                                 let card = mat.discard.remove(0);
                                 deltas.push(BoardDelta::Move(
-                                    Location::Discard(self.current_player),
+                                    Location::Discard(player),
                                     0,
-                                    Location::Deck(self.current_player),
+                                    Location::Deck(player),
                                     Some(card.clone()),
                                 ));
                                 mat.deck.push(card);
                             }
 
                             self.rng.shuffle(&mut mat.deck);
-                            deltas.push(BoardDelta::ShuffleDeck(self.current_player));
+                            deltas.push(BoardDelta::ShuffleDeck(player));
                         }
 
                         if let Some(card) = mat.deck.pop() {
                             mat.hand.push(card.clone());
                             deltas.push(BoardDelta::Move(
-                                Location::Deck(self.current_player),
+                                Location::Deck(player),
                                 0,
-                                Location::Hand(self.current_player),
+                                Location::Hand(player),
                                 Some(card),
                             ));
                         }
@@ -159,6 +453,12 @@ impl MasterBoard {
                 }
                 Effect::OpponentDiscards(x) => {
                     if let Some(EffectArgument::Opponent(o)) = effect_args.pop() {
+                        if o >= self.players || o == player {
+                            return Err("No such opponent");
+                        }
+                        if self.mats[o].eliminated || !self.mats[o].is_alive() {
+                            return Err("Opponent already eliminated");
+                        }
                         self.mats[o].must_discard += x;
                         deltas.push(BoardDelta::IncreaseDiscardAmount(o, x))
                     } else {
@@ -166,8 +466,8 @@ impl MasterBoard {
                     }
                 }
                 Effect::PlayerDiscards(x) => {
-                    self.mats[self.current_player].must_discard += x;
-                    deltas.push(BoardDelta::IncreaseDiscardAmount(self.current_player, x))
+                    self.mats[player].must_discard += x;
+                    deltas.push(BoardDelta::IncreaseDiscardAmount(player, x))
                 }
                 Effect::Choice(first, second) => {
                     let mut branch = match effect_args.pop() {
@@ -182,20 +482,26 @@ impl MasterBoard {
                     for _ in 0..amount {
                         match effect_args.pop() {
                             Some(EffectArgument::CardInHand(i)) => {
-                                let card = self.mats[self.current_player].hand.remove(i);
+                                if i >= self.mats[player].hand.len() {
+                                    return Err("No such card in hand");
+                                }
+                                let card = self.mats[player].hand.remove(i);
                                 self.sacrificed.push(card.clone());
                                 deltas.push(BoardDelta::Move(
-                                    Location::Hand(self.current_player),
+                                    Location::Hand(player),
                                     i,
                                     Location::Sacrifice,
                                     Some(card),
                                 ));
                             }
                             Some(EffectArgument::CardInDiscard(i)) => {
-                                let card = self.mats[self.current_player].discard.remove(i);
+                                if i >= self.mats[player].discard.len() {
+                                    return Err("No such card in discard");
+                                }
+                                let card = self.mats[player].discard.remove(i);
                                 self.sacrificed.push(card.clone());
                                 deltas.push(BoardDelta::Move(
-                                    Location::Discard(self.current_player),
+                                    Location::Discard(player),
                                     i,
                                     Location::Sacrifice,
                                     Some(card),
@@ -206,28 +512,28 @@ impl MasterBoard {
                     }
                 }
                 Effect::HealPer(x, times) => {
-                    let times = self.calculate_times(times);
+                    let times = self.calculate_times(player, times);
                     effects.push(Effect::Heal(x * times));
                 }
                 Effect::CombatPer(x, times) => {
-                    let times = self.calculate_times(times);
+                    let times = self.calculate_times(player, times);
                     effects.push(Effect::Combat(x * times));
                 }
                 Effect::NextActionPurchaseToTopOfDeck => {
-                    self.mats[self.current_player].next_action_purchase_to_top_of_deck += 1;
+                    self.mats[player].next_action_purchase_to_top_of_deck += 1;
                 }
                 Effect::NextPurchaseToTopOfDeck => {
-                    self.mats[self.current_player].next_purchase_to_top_of_deck += 1;
+                    self.mats[player].next_purchase_to_top_of_deck += 1;
                 }
                 Effect::NextPurchaseToHand => {
-                    self.mats[self.current_player].next_purchase_to_hand += 1;
+                    self.mats[player].next_purchase_to_hand += 1;
                 }
                 Effect::PrepareChampion => match effect_args.pop() {
-                    Some(EffectArgument::Champion(p, id)) if p == self.current_player => {
+                    Some(EffectArgument::Champion(p, id)) if p == player => {
                         if id >= self.mats[p].field.len() {
                             return Err("No such card in field");
                         }
-                        let ref mut cif = self.mats[p].field[id];
+                        let cif = &mut self.mats[p].field[id];
                         if !cif.card.is_champion() {
                             return Err("Target card is not a champion");
                         }
@@ -237,7 +543,7 @@ impl MasterBoard {
                     _ => return Err("Wrong arguments, expected player champion"),
                 },
                 Effect::StunChampion => match effect_args.pop() {
-                    Some(EffectArgument::Champion(p, id)) if p != self.current_player => {
+                    Some(EffectArgument::Champion(p, id)) if p != player => {
                         if p >= self.players {
                             return Err("No such player");
                         }
@@ -254,22 +560,26 @@ impl MasterBoard {
                             Location::Field(p),
                             id,
                             Location::Discard(p),
-                            Some(card),
+                            Some(card.clone()),
                         ));
+                        let cause = deltas.cause.clone();
+                        deltas.append(
+                            &mut self.resolve_triggers(GameEvent::Stunned(p, card), cause)?,
+                        );
                     }
                     _ => return Err("Wrong arguments, exprected opponent champion"),
                 },
                 Effect::PutCardFromDiscardOverDeck => match effect_args.pop() {
                     Some(EffectArgument::CardInDiscard(id)) => {
-                        if id >= self.mats[self.current_player].discard.len() {
+                        if id >= self.mats[player].discard.len() {
                             return Err("No such card");
                         }
-                        let card = self.mats[self.current_player].discard.remove(id);
-                        self.mats[self.current_player].deck.push(card.clone());
+                        let card = self.mats[player].discard.remove(id);
+                        self.mats[player].deck.push(card.clone());
                         deltas.push(BoardDelta::Move(
-                            Location::Discard(self.current_player),
+                            Location::Discard(player),
                             id,
-                            Location::Deck(self.current_player),
+                            Location::Deck(player),
                             Some(card),
                         ));
                     }
@@ -278,18 +588,18 @@ impl MasterBoard {
 
                 Effect::PutChampionFromDiscardOverDeck => match effect_args.pop() {
                     Some(EffectArgument::CardInDiscard(id)) => {
-                        if id >= self.mats[self.current_player].discard.len() {
+                        if id >= self.mats[player].discard.len() {
                             return Err("No such card");
                         }
-                        if !self.mats[self.current_player].discard[id].is_champion() {
+                        if !self.mats[player].discard[id].is_champion() {
                             return Err("Target card is not a champion");
                         }
-                        let card = self.mats[self.current_player].discard.remove(id);
-                        self.mats[self.current_player].deck.push(card.clone());
+                        let card = self.mats[player].discard.remove(id);
+                        self.mats[player].deck.push(card.clone());
                         deltas.push(BoardDelta::Move(
-                            Location::Discard(self.current_player),
+                            Location::Discard(player),
                             id,
-                            Location::Deck(self.current_player),
+                            Location::Deck(player),
                             Some(card),
                         ));
                     }
@@ -301,7 +611,7 @@ impl MasterBoard {
         Ok(deltas)
     }
 
-    fn calculate_times(&self, times: PerAmount) -> usize {
+    fn calculate_times(&self, player: Player, times: PerAmount) -> usize {
         let sub = match times {
             PerAmount::Champion => 0,
             _ => 1,
@@ -312,7 +622,7 @@ impl MasterBoard {
             PerAmount::AdditionalFactionCard(f) => card.faction() == f,
             PerAmount::AdditionalGuardian => card.is_guard(),
         };
-        self.mats[self.current_player]
+        self.mats[player]
             .field
             .iter()
             .filter(|cif| filter(&cif.card))
@@ -320,17 +630,309 @@ impl MasterBoard {
             - sub
     }
 
+    // The next player still in the game, the same one if nobody else is left
+    pub fn next_player(&self, player: Player) -> Player {
+        (1..=self.players)
+            .map(|i| (player + i) % self.players)
+            .find(|p| !self.mats[*p].eliminated)
+            .unwrap_or(player)
+    }
+
+    // Removes the player cards from play and passes the turn if it was theirs
+    fn eliminate(&mut self, player: Player) -> Result<Trace, &'static str> {
+        let mut deltas = Trace::new(Cause::Rule(Rule::Elimination(player)));
+        let mat = &mut self.mats[player];
+        mat.eliminated = true;
+
+        if mat.lives > 0 {
+            deltas.push(BoardDelta::DecreaseHealth(player, mat.lives));
+            mat.lives = 0;
+        }
+        if mat.gold > 0 {
+            deltas.push(BoardDelta::DecreaseGold(player, mat.gold));
+            mat.gold = 0;
+        }
+        if mat.combat > 0 {
+            deltas.push(BoardDelta::DecreaseCombat(player, mat.combat));
+            mat.combat = 0;
+        }
+        if mat.must_discard > 0 {
+            deltas.push(BoardDelta::DecreaseDiscardAmount(player, mat.must_discard));
+            mat.must_discard = 0;
+        }
+        while !mat.field.is_empty() {
+            let card = mat.field.remove(0).card;
+            deltas.push(BoardDelta::Move(
+                Location::Field(player),
+                0,
+                Location::Discard(player),
+                Some(card.clone()),
+            ));
+            mat.discard.push(card);
+        }
+        while !mat.hand.is_empty() {
+            let card = mat.hand.remove(0);
+            deltas.push(BoardDelta::Move(
+                Location::Hand(player),
+                0,
+                Location::Discard(player),
+                Some(card.clone()),
+            ));
+            mat.discard.push(card);
+        }
+        deltas.push(BoardDelta::PlayerEliminated(player));
+
+        if self.current_player == player && self.mats.iter().filter(|m| m.is_alive()).count() > 1 {
+            self.current_player = self.next_player(player);
+            deltas.push(BoardDelta::ChangeCurrentPlayer(self.current_player));
+            if !self.is_drafting() {
+                let cause = deltas.cause.clone();
+                deltas.append(
+                    &mut self
+                        .resolve_triggers(GameEvent::TurnStarted(self.current_player), cause)?,
+                );
+            }
+        }
+        Ok(deltas)
+    }
+
+    fn check_game_over(&mut self) -> Trace {
+        let mut deltas = Trace::new(Cause::Rule(Rule::GameOver));
+        if !self.game_over && self.mats.iter().filter(|m| m.is_alive()).count() <= 1 {
+            self.game_over = true;
+            deltas.push(BoardDelta::GameOver);
+        }
+        deltas
+    }
+
+    fn concede(&mut self, player: Player) -> Result<Trace, &'static str> {
+        if player >= self.players {
+            return Err("No such player");
+        }
+        if self.mats[player].eliminated {
+            return Err("Player already eliminated");
+        }
+        let mut state = self.clone();
+        let mut deltas = state.eliminate(player)?;
+        deltas.append(&mut state.check_game_over());
+        *self = state;
+        Ok(deltas)
+    }
+
+    // Applies the clock policy to the current player
+    fn time_out(&mut self) -> Result<Trace, &'static str> {
+        let player = self.current_player;
+        let mut state = self.clone();
+        let mut deltas = Trace::new(Cause::Rule(Rule::Timeout));
+        state.mats[player].timeouts += 1;
+
+        let forfeit = match state.rules.clock.as_ref().map(|c| &c.on_timeout) {
+            Some(TimeoutPolicy::ForfeitAfter(n)) => state.mats[player].timeouts >= *n,
+            _ => false,
+        };
+        if forfeit {
+            deltas.append(&mut state.eliminate(player)?);
+            deltas.append(&mut state.check_game_over());
+        } else if state.is_drafting() {
+            deltas.append(&mut state.draft_pick(0)?);
+        } else {
+            while state.mats[player].must_discard > 0 {
+                if state.mats[player].hand.is_empty() {
+                    let amount = state.mats[player].must_discard;
+                    state.mats[player].must_discard = 0;
+                    deltas.push(BoardDelta::DecreaseDiscardAmount(player, amount));
+                } else {
+                    deltas.append(&mut state.act(player, PlayerAction::Discard(0))?);
+                }
+            }
+            deltas.append(&mut state.act(player, PlayerAction::EndTurn)?);
+        }
+
+        *self = state;
+        Ok(deltas)
+    }
+
     pub fn do_action(&mut self, action: PlayerAction) -> Result<Vec<BoardDelta>, &'static str> {
+        self.do_action_as(self.current_player, action)
+    }
+
+    // Only the current player can act, except for conceding
+    pub fn do_action_as(
+        &mut self,
+        player: Player,
+        action: PlayerAction,
+    ) -> Result<Vec<BoardDelta>, &'static str> {
+        Ok(self.trace_action(player, action)?.deltas())
+    }
+
+    // The same as `do_action_as`, telling why each delta happened
+    pub fn trace_action(
+        &mut self,
+        player: Player,
+        action: PlayerAction,
+    ) -> Result<ActionOutcome, &'static str> {
+        let trace = self.act(player, action.clone())?;
+        let deltas: Vec<BoardDelta> = trace.entries.iter().map(|e| e.delta.clone()).collect();
+        self.track_knowledge(&deltas);
+        self.action_count += 1;
+        Ok(ActionOutcome {
+            action_id: self.action_count - 1,
+            player,
+            action,
+            entries: trace.entries,
+        })
+    }
+
+    // Keeps track of what every player can deduce from the deltas, the same
+    // way `Board::apply` does
+    fn track_knowledge(&mut self, deltas: &[BoardDelta]) {
+        for delta in deltas {
+            match delta {
+                BoardDelta::Move(from, _, to, card) => {
+                    let mut public = !matches!(
+                        from,
+                        Location::Hand(_) | Location::ShopDeck | Location::Deck(_)
+                    );
+                    match from {
+                        Location::Deck(p) if self.mats[*p].known_deck > 0 => {
+                            self.mats[*p].known_deck -= 1;
+                            public = true;
+                        }
+                        Location::Hand(p) => {
+                            let known_hand = &mut self.mats[*p].known_hand;
+                            if let Some(i) =
+                                known_hand.iter().position(|c| Some(c) == card.as_ref())
+                            {
+                                known_hand.remove(i);
+                            }
+                        }
+                        _ => {}
+                    }
+                    match to {
+                        Location::Deck(p) if public => self.mats[*p].known_deck += 1,
+                        Location::Deck(p) => self.mats[*p].known_deck = 0,
+                        Location::Hand(p) if public => {
+                            self.mats[*p].known_hand.extend(card.clone());
+                        }
+                        _ => {}
+                    }
+                }
+                BoardDelta::ShuffleDeck(p) => self.mats[*p].known_deck = 0,
+                _ => {}
+            }
+        }
+    }
+
+    // Replays a delta made elsewhere, like one from `diff`. Shuffles only forget
+    // what was known of the deck, the order stays as it is until cards are taken
+    pub fn apply(&mut self, delta: BoardDelta) -> Result<(), &'static str> {
+        match delta.clone() {
+            BoardDelta::Move(from, index, to, card) => {
+                let taken = match from {
+                    Location::Hand(p) => take(&mut self.mats[p].hand, index),
+                    Location::Discard(p) => take(&mut self.mats[p].discard, index),
+                    Location::Field(p) => take(&mut self.mats[p].field, index).map(|c| c.card),
+                    Location::Shop => take(&mut self.shop, index),
+                    Location::DraftPool => match self.draft.as_mut() {
+                        Some(draft) => take(&mut draft.pool, index),
+                        None => None,
+                    },
+                    // Piles are taken from the top, but as shuffles don't say
+                    // the new order a named card is looked for further down
+                    Location::Deck(p) => take_from_pile(&mut self.mats[p].deck, &card),
+                    Location::ShopDeck => take_from_pile(&mut self.shop_deck, &card),
+                    Location::FireGems => take_from_pile(&mut self.gems, &card),
+                    Location::Sacrifice => return Err("Sacrificed cards can't be moved"),
+                };
+                let taken = taken.ok_or("No such card to move")?;
+                if card.is_some() && card != Some(taken.clone()) {
+                    return Err("Moved card doesn't match the board");
+                }
+                match to {
+                    Location::Hand(p) => self.mats[p].hand.push(taken),
+                    Location::Discard(p) => self.mats[p].discard.push(taken),
+                    Location::Field(p) => self.mats[p].field.push(CardInField::new(taken)),
+                    Location::Deck(p) => self.mats[p].deck.push(taken),
+                    Location::Shop => self.shop.push(taken),
+                    Location::ShopDeck => self.shop_deck.push(taken),
+                    Location::FireGems => self.gems.push(taken),
+                    Location::Sacrifice => self.sacrificed.push(taken),
+                    Location::DraftPool => {
+                        self.draft.as_mut().ok_or("Not drafting")?.pool.push(taken)
+                    }
+                }
+            }
+            BoardDelta::IncreaseHealth(p, x) => self.mats[p].lives += x,
+            BoardDelta::DecreaseHealth(p, x) => {
+                self.mats[p].lives = self.mats[p].lives.saturating_sub(x)
+            }
+            BoardDelta::IncreaseCombat(p, x) => self.mats[p].combat += x,
+            BoardDelta::DecreaseCombat(p, x) => {
+                self.mats[p].combat = self.mats[p].combat.saturating_sub(x)
+            }
+            BoardDelta::IncreaseGold(p, x) => self.mats[p].gold += x,
+            BoardDelta::DecreaseGold(p, x) => {
+                self.mats[p].gold = self.mats[p].gold.saturating_sub(x)
+            }
+            BoardDelta::IncreaseDiscardAmount(p, x) => self.mats[p].must_discard += x,
+            BoardDelta::DecreaseDiscardAmount(p, x) => {
+                self.mats[p].must_discard = self.mats[p].must_discard.saturating_sub(x)
+            }
+            BoardDelta::ChangeCurrentPlayer(p) => self.current_player = p,
+            BoardDelta::SetExpendAbilityUsed(p, i, value) => {
+                self.mats[p]
+                    .field
+                    .get_mut(i)
+                    .ok_or("No such champion")?
+                    .expend_ability_used = value
+            }
+            BoardDelta::SetAllyAbilityUsed(p, i, value) => {
+                self.mats[p]
+                    .field
+                    .get_mut(i)
+                    .ok_or("No such champion")?
+                    .ally_ability_used = value
+            }
+            BoardDelta::PlayerEliminated(p) => self.mats[p].eliminated = true,
+            BoardDelta::GameOver => self.game_over = true,
+            BoardDelta::PlayerDeclaredAction(_)
+            | BoardDelta::TriggeredAbility(_, _, _)
+            | BoardDelta::ShuffleDeck(_) => {}
+        }
+        self.track_knowledge(&[delta]);
+        Ok(())
+    }
+
+    fn act(&mut self, player: Player, action: PlayerAction) -> Result<Trace, &'static str> {
         if self.game_over {
             return Err("The game is already over");
         }
+        match action {
+            PlayerAction::Concede => return self.concede(player),
+            _ if player != self.current_player => return Err("Not the current player"),
+            PlayerAction::TimedOut => return self.time_out(),
+            PlayerAction::PlayAll
+            | PlayerAction::ActivateAllAutomaticAbilities
+            | PlayerAction::AttackWithEverything(_) => return self.act_batch(player, action),
+            _ => {}
+        }
 
         let mut state = self.clone();
-        let mut deltas = vec![];
+        let mut deltas = Trace::new(Cause::Action(action.clone()));
+
+        if state.is_drafting() {
+            if let PlayerAction::DraftPick(position) = action {
+                deltas.append(&mut state.draft_pick(position)?);
+                *self = state;
+                return Ok(deltas);
+            } else {
+                return Err("Must finish drafting first");
+            }
+        }
 
         if state.mats[state.current_player].must_discard > 0 {
             if let PlayerAction::Discard(card_in_hand) = action {
-                let ref mut mat = state.mats[state.current_player];
+                let mat = &mut state.mats[state.current_player];
                 if card_in_hand >= mat.hand.len() {
                     return Err("No such card in hand");
                 }
@@ -354,7 +956,7 @@ impl MasterBoard {
 
         match action {
             PlayerAction::Play(position, effect_args) => {
-                let ref mut mat = state.mats[state.current_player];
+                let mat = &mut state.mats[state.current_player];
                 if position >= mat.hand.len() {
                     return Err("No such card in hand");
                 }
@@ -370,12 +972,13 @@ impl MasterBoard {
                 ));
 
                 if let Some(effects) = card.primary_ability() {
-                    deltas.append(&mut state.apply_effects(effects, effect_args)?);
+                    let cause = ability(&card, AbilityKind::Primary);
+                    deltas.append(&mut state.apply_effects(effects, effect_args, cause)?);
                 }
             }
 
             PlayerAction::ActivateSacrificeAbility(card_in_field, effect_args) => {
-                let ref mut mat = state.mats[state.current_player];
+                let mat = &mut state.mats[state.current_player];
                 if card_in_field >= mat.field.len() {
                     return Err("No such card in field");
                 }
@@ -390,14 +993,15 @@ impl MasterBoard {
                 ));
 
                 if let Some(effects) = card.sacrifice_ability() {
-                    deltas.append(&mut state.apply_effects(effects, effect_args)?);
+                    let cause = ability(&card, AbilityKind::Sacrifice);
+                    deltas.append(&mut state.apply_effects(effects, effect_args, cause)?);
                 } else {
                     return Err("No such sacrifice ability");
                 }
             }
 
             PlayerAction::ActivateExpendAbility(card_in_field, effect_args) => {
-                let ref mut mat = state.mats[state.current_player];
+                let mat = &mut state.mats[state.current_player];
                 if card_in_field >= mat.field.len() {
                     return Err("No such card in field");
                 }
@@ -405,6 +1009,7 @@ impl MasterBoard {
                     return Err("Expend ability already used");
                 }
                 let card = mat.field[card_in_field].card.clone();
+                deltas.cause = ability(&card, AbilityKind::Expend);
                 deltas.push(BoardDelta::SetExpendAbilityUsed(
                     state.current_player,
                     card_in_field,
@@ -413,14 +1018,15 @@ impl MasterBoard {
 
                 if let Some(effects) = card.expend_ability() {
                     mat.field[card_in_field].expend_ability_used = true;
-                    deltas.append(&mut state.apply_effects(effects, effect_args)?);
+                    let cause = ability(&card, AbilityKind::Expend);
+                    deltas.append(&mut state.apply_effects(effects, effect_args, cause)?);
                 } else {
                     return Err("Card does not have expend ability");
                 }
             }
 
             PlayerAction::ActivateAllyAbility(card_in_field, effect_args) => {
-                let ref mut mat = state.mats[state.current_player];
+                let mat = &mut state.mats[state.current_player];
                 if card_in_field >= mat.field.len() {
                     return Err("No such card in field");
                 }
@@ -440,20 +1046,23 @@ impl MasterBoard {
                     {
                         return Err("No ally in field");
                     }
+                    deltas.cause = ability(&card, AbilityKind::Ally);
                     deltas.push(BoardDelta::SetAllyAbilityUsed(
                         state.current_player,
                         card_in_field,
                         true,
                     ));
                     mat.field[card_in_field].ally_ability_used = true;
-                    deltas.append(&mut state.apply_effects(effects, effect_args)?);
+                    let cause = ability(&card, AbilityKind::Ally);
+                    deltas.append(&mut state.apply_effects(effects, effect_args, cause)?);
                 } else {
                     return Err("Card does not have ally ability");
                 }
             }
 
             PlayerAction::EndTurn => {
-                let ref mut mat = state.mats[state.current_player];
+                let mat = &mut state.mats[state.current_player];
+                deltas.cause = Cause::Rule(Rule::EndOfTurn);
 
                 if mat.gold > 0 {
                     deltas.push(BoardDelta::DecreaseGold(state.current_player, mat.gold));
@@ -502,7 +1111,7 @@ impl MasterBoard {
                     ));
                 }
 
-                while mat.hand.len() > 0 {
+                while !mat.hand.is_empty() {
                     deltas.push(BoardDelta::Move(
                         Location::Hand(state.current_player),
                         0,
@@ -512,21 +1121,29 @@ impl MasterBoard {
                     mat.discard.push(mat.hand.remove(0));
                 }
 
-                deltas.append(&mut state.apply_effects(vec![Effect::Draw(5)], vec![])?);
+                deltas.append(&mut state.apply_effects(
+                    vec![Effect::Draw(5)],
+                    vec![],
+                    Cause::Rule(Rule::NewHand),
+                )?);
 
-                state.current_player = (state.current_player + 1) % state.players;
+                state.current_player = state.next_player(state.current_player);
                 deltas.push(BoardDelta::ChangeCurrentPlayer(state.current_player));
+                deltas.append(&mut state.resolve_triggers(
+                    GameEvent::TurnStarted(state.current_player),
+                    Cause::Rule(Rule::EndOfTurn),
+                )?);
             }
 
             PlayerAction::PurchaseFromShop(position) => {
-                let ref mut mat = state.mats[state.current_player];
+                let mat = &mut state.mats[state.current_player];
 
                 if position >= state.shop.len() {
                     return Err("No such card in shop");
                 }
                 let card = state.shop[position].clone();
 
-                let cost = card.cost();
+                let cost = card.effective_cost(&mat.field);
 
                 if mat.gold < cost {
                     return Err("Not enough gold");
@@ -574,6 +1191,7 @@ impl MasterBoard {
 
                 state.shop.remove(position);
                 if let Some(card) = state.shop_deck.pop() {
+                    deltas.cause = Cause::Rule(Rule::RefillShop);
                     deltas.push(BoardDelta::Move(
                         Location::ShopDeck,
                         0,
@@ -581,31 +1199,51 @@ impl MasterBoard {
                         Some(card.clone()),
                     ));
                     state.shop.push(card);
-                } else {
                 }
+
+                deltas.append(&mut state.resolve_triggers(
+                    GameEvent::Acquired(state.current_player, card),
+                    Cause::Action(PlayerAction::PurchaseFromShop(position)),
+                )?);
             }
 
             PlayerAction::PurchaseFireGem => {
-                let ref mut mat = state.mats[state.current_player];
+                let mat = &mut state.mats[state.current_player];
 
                 if state.gems.is_empty() {
                     return Err("No more fire gems");
                 }
-                let cost = Card::FireGem.cost();
+                let cost = Card::FireGem.effective_cost(&mat.field);
 
                 if mat.gold < cost {
                     return Err("Not enough gold");
                 }
                 mat.gold -= cost;
-                mat.discard.push(state.gems.pop().unwrap());
-
                 deltas.push(BoardDelta::DecreaseGold(state.current_player, cost));
-                deltas.push(BoardDelta::Move(
-                    Location::FireGems,
-                    0,
-                    Location::Discard(state.current_player),
-                    Some(Card::FireGem),
-                ));
+
+                let gem = state.gems.pop().unwrap();
+                if state.rules.purchase_to_hand_applies_to_gems && mat.next_purchase_to_hand > 0 {
+                    mat.next_purchase_to_hand -= 1;
+                    mat.hand.push(gem);
+                    deltas.push(BoardDelta::Move(
+                        Location::FireGems,
+                        0,
+                        Location::Hand(state.current_player),
+                        Some(Card::FireGem),
+                    ));
+                } else {
+                    mat.discard.push(gem);
+                    deltas.push(BoardDelta::Move(
+                        Location::FireGems,
+                        0,
+                        Location::Discard(state.current_player),
+                        Some(Card::FireGem),
+                    ));
+                }
+                deltas.append(&mut state.resolve_triggers(
+                    GameEvent::Acquired(state.current_player, Card::FireGem),
+                    Cause::Action(PlayerAction::PurchaseFireGem),
+                )?);
             }
 
             PlayerAction::AttackPlayer(player, amount) => {
@@ -615,6 +1253,9 @@ impl MasterBoard {
                 if state.current_player == player {
                     return Err("Player can't attack himself");
                 }
+                if state.mats[player].eliminated {
+                    return Err("Player already eliminated");
+                }
                 if state.mats[player]
                     .field
                     .iter()
@@ -654,7 +1295,9 @@ impl MasterBoard {
                     return Err("Can't attack champion player with guards");
                 }
 
-                let def = state.mats[player].field[champion].card.defense();
+                let def = state.mats[player].field[champion]
+                    .card
+                    .effective_defense(&state.mats[player].field);
                 if def > state.mats[state.current_player].combat {
                     return Err("Not enough combat");
                 }
@@ -666,28 +1309,183 @@ impl MasterBoard {
                     Location::Field(player),
                     champion,
                     Location::Discard(player),
-                    Some(card),
+                    Some(card.clone()),
                 ));
+                let cause = deltas.cause.clone();
+                deltas
+                    .append(&mut state.resolve_triggers(GameEvent::Stunned(player, card), cause)?);
             }
 
             PlayerAction::Discard(_) => {
                 return Err("Cant discard now");
             }
+
+            PlayerAction::DraftPick(_) => {
+                return Err("Not drafting");
+            }
+
+            PlayerAction::Concede
+            | PlayerAction::TimedOut
+            | PlayerAction::PlayAll
+            | PlayerAction::ActivateAllAutomaticAbilities
+            | PlayerAction::AttackWithEverything(_) => unreachable!(),
         }
 
-        if state.mats.iter().filter(|m| m.is_alive()).count() == 1 {
-            deltas.push(BoardDelta::GameOver);
-            state.game_over = true;
+        for player in 0..state.players {
+            if !state.mats[player].is_alive() && !state.mats[player].eliminated {
+                deltas.append(&mut state.eliminate(player)?);
+            }
         }
+        deltas.append(&mut state.check_game_over());
 
         *self = state;
         Ok(deltas)
     }
 
+    // Repeats single actions until none is left, all of them succeed or none does
+    fn act_batch(&mut self, player: Player, action: PlayerAction) -> Result<Trace, &'static str> {
+        let mut state = self.clone();
+        let mut deltas = Trace::new(Cause::Action(action.clone()));
+
+        while let Some(next) = state.next_in_batch(&action) {
+            let done = matches!(next, PlayerAction::AttackPlayer(_, _));
+            deltas.append(&mut state.act(player, next)?);
+            if done {
+                break;
+            }
+        }
+        if deltas.is_empty() {
+            return Err("Nothing to do");
+        }
+
+        *self = state;
+        Ok(deltas)
+    }
+
+    fn next_in_batch(&self, action: &PlayerAction) -> Option<PlayerAction> {
+        let mat = &self.mats[self.current_player];
+        if self.game_over || mat.must_discard > 0 {
+            return None;
+        }
+        let automatic = |effects: Option<Vec<Effect>>| match effects {
+            Some(effects) => !effects.iter().any(|e| e.needs_arguments()),
+            None => false,
+        };
+
+        match action {
+            PlayerAction::PlayAll => mat
+                .hand
+                .iter()
+                .position(|card| {
+                    card.primary_ability().is_none() || automatic(card.primary_ability())
+                })
+                .map(|i| PlayerAction::Play(i, vec![])),
+
+            PlayerAction::ActivateAllAutomaticAbilities => {
+                let expend = mat.field.iter().position(|cif| {
+                    !cif.expend_ability_used && automatic(cif.card.expend_ability())
+                });
+                if let Some(i) = expend {
+                    return Some(PlayerAction::ActivateExpendAbility(i, vec![]));
+                }
+                mat.field
+                    .iter()
+                    .position(|cif| {
+                        !cif.ally_ability_used
+                            && automatic(cif.card.ally_ability())
+                            && mat
+                                .field
+                                .iter()
+                                .filter(|other| other.card.faction() == cif.card.faction())
+                                .count()
+                                >= 2
+                    })
+                    .map(|i| PlayerAction::ActivateAllyAbility(i, vec![]))
+            }
+
+            PlayerAction::AttackWithEverything(target) => {
+                let target = *target;
+                if target >= self.players || target == self.current_player || mat.combat == 0 {
+                    return None;
+                }
+                // Guards have to be stunned before the player can be attacked
+                let field = &self.mats[target].field;
+                if field.iter().any(|cif| cif.card.is_guard()) {
+                    field
+                        .iter()
+                        .position(|cif| {
+                            cif.card.is_guard() && cif.card.effective_defense(field) <= mat.combat
+                        })
+                        .map(|i| PlayerAction::AttackPlayerChampion(target, i))
+                } else {
+                    Some(PlayerAction::AttackPlayer(target, mat.combat))
+                }
+            }
+
+            _ => None,
+        }
+    }
+
+    // Covers the hidden information too, unlike the hash of any Board view
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher
+            .cards(&self.shop)
+            .cards(&self.shop_deck)
+            .cards(&self.gems)
+            .cards(&self.sacrificed)
+            .usize(self.current_player)
+            .usize(self.players)
+            .bool(self.game_over)
+            .bool(self.draft.is_some());
+        if let Some(draft) = &self.draft {
+            hasher
+                .cards(&draft.pool)
+                .usize(draft.picks_left)
+                .usize(draft.first_player);
+        }
+        hash_rules(&mut hasher, &self.rules);
+        hasher
+            .u64(self.rng.seed())
+            .bool(self.rng.algorithm() == Algorithm::Legacy);
+        for mat in self.mats.iter() {
+            hasher.usize(mat.field.len());
+            for cif in mat.field.iter() {
+                hasher
+                    .card(&cif.card)
+                    .bool(cif.expend_ability_used)
+                    .bool(cif.ally_ability_used);
+            }
+            hasher
+                .str(&mat.name)
+                .str(mat.ancestry.id())
+                .cards(&mat.hand)
+                .cards(&mat.discard)
+                .cards(&mat.deck)
+                .usize(mat.lives)
+                .usize(mat.combat)
+                .usize(mat.gold)
+                .usize(mat.must_discard)
+                .usize(mat.next_action_purchase_to_top_of_deck)
+                .usize(mat.next_purchase_to_top_of_deck)
+                .usize(mat.next_purchase_to_hand)
+                .usize(mat.timeouts)
+                .bool(mat.eliminated)
+                .usize(mat.known_deck)
+                .cards(&mat.known_hand);
+        }
+        hasher.finish()
+    }
+
     pub fn scoped_to(&self, player: usize) -> Board {
         Board {
             shop: self.shop.clone(),
             shop_deck: self.shop_deck.len(),
+            draft_pool: self
+                .draft
+                .as_ref()
+                .map(|d| d.pool.clone())
+                .unwrap_or_default(),
             gems: self.gems.len(),
             sacrificed: self.sacrificed.clone(),
             current_player: self.current_player,
@@ -698,10 +1496,13 @@ impl MasterBoard {
                 .iter()
                 .map(|mat| Mat {
                     name: mat.name.clone(),
+                    ancestry: mat.ancestry.clone(),
                     field: mat.field.clone(),
                     hand: mat.hand.len(),
                     discard: mat.discard.clone(),
                     deck: mat.deck.len(),
+                    known_deck: mat.deck[mat.deck.len().saturating_sub(mat.known_deck)..].to_vec(),
+                    known_hand: mat.known_hand.clone(),
                     lives: mat.lives,
                     combat: mat.combat,
                     gold: mat.gold,
@@ -713,3 +1514,39 @@ impl MasterBoard {
         }
     }
 }
+
+// Field by field, options and variants are told apart by a leading tag
+fn hash_rules(hasher: &mut StateHasher, rules: &Rules) {
+    let optional_duration = |hasher: &mut StateHasher, duration: Option<Duration>| {
+        hasher
+            .bool(duration.is_some())
+            .u64(duration.map(|d| d.as_nanos() as u64).unwrap_or_default());
+    };
+    hasher.usize(rules.starting_lives.len());
+    for lives in rules.starting_lives.iter() {
+        hasher.usize(*lives);
+    }
+    hasher.usize(rules.starting_hands.len());
+    for amount in rules.starting_hands.iter() {
+        hasher.usize(*amount);
+    }
+    hasher
+        .usize(rules.market_size)
+        .bool(rules.fire_gems.is_some())
+        .usize(rules.fire_gems.unwrap_or_default())
+        .bool(rules.purchase_to_hand_applies_to_gems);
+    match rules.first_player {
+        FirstPlayer::Random => hasher.str("Random").usize(0),
+        FirstPlayer::Fixed(p) => hasher.str("Fixed").usize(p),
+        FirstPlayer::Alternating(games) => hasher.str("Alternating").usize(games),
+    };
+    hasher.bool(rules.clock.is_some());
+    if let Some(clock) = &rules.clock {
+        optional_duration(hasher, clock.per_turn);
+        optional_duration(hasher, clock.total);
+        match clock.on_timeout {
+            TimeoutPolicy::AutoEndTurn => hasher.str("AutoEndTurn").usize(0),
+            TimeoutPolicy::ForfeitAfter(n) => hasher.str("ForfeitAfter").usize(n),
+        };
+    }
+}
@@ -1,9 +1,10 @@
-use super::{api::*, cards::*, master::*};
+use super::{api::*, cards::*, journal::*, local::hide_card_info, master::*, version};
 use crate::smallrng::*;
+use std::time::Duration;
 
 #[test]
 fn test_initial_state() {
-    let state = MasterBoard::new(2, &Setup::test(), SRng::new(0));
+    let state = MasterBoard::new(2, &Setup::test(), SRng::new(0)).unwrap();
 
     let ref p1 = state.mats[state.current_player];
     let ref p2 = state.mats[(state.current_player + 1) % 2];
@@ -30,7 +31,7 @@ fn test_initial_state() {
 
 #[test]
 fn simple_test_run() -> Result<(), &'static str> {
-    let mut state = MasterBoard::new(2, &Setup::test(), SRng::new(0));
+    let mut state = MasterBoard::new(2, &Setup::test(), SRng::legacy(0))?;
     let p1 = state.current_player;
     let p2 = (state.current_player + 1) % 2;
 
@@ -305,7 +306,7 @@ fn attack_all(state: &mut MasterBoard) -> Result<Vec<BoardDelta>, &'static str>
 
 #[test]
 fn second_test_run() -> Result<(), &'static str> {
-    let mut state = MasterBoard::new(2, &Setup::base(), SRng::new(14279));
+    let mut state = MasterBoard::new(2, &Setup::base(), SRng::legacy(14279))?;
     let p1 = state.current_player;
     let p2 = (state.current_player + 1) % 2;
     assert_vec_eq(
@@ -590,7 +591,7 @@ fn lets_see(state: &MasterBoard) {
 
 #[test]
 fn test_master_board_2_board() -> Result<(), &'static str> {
-    let mut master = MasterBoard::new(2, &Setup::base(), SRng::new(14279));
+    let mut master = MasterBoard::new(2, &Setup::base(), SRng::new(14279))?;
 
     let mut board = master.scoped_to(master.current_player);
 
@@ -639,3 +640,636 @@ fn missing_cards() {
     }
 }
 
+#[test]
+fn test_static_modifiers() -> Result<(), &'static str> {
+    let mut state = MasterBoard::new(2, &Setup::base(), SRng::new(14279))?;
+    let p1 = state.current_player;
+    let p2 = (state.current_player + 1) % 2;
+
+    state.mats[p1]
+        .field
+        .push(CardInField::new(Card::Quartermaster));
+    state.mats[p1].gold = 2;
+    state.shop[0] = Card::Influence;
+    state.do_action(PlayerAction::PurchaseFromShop(0))?;
+    {
+        // Influence costs 2, but actions are 1 cheaper
+        assert_eq!(state.mats[p1].gold, 1);
+        assert_eq!(state.mats[p1].discard.last(), Some(&Card::Influence));
+    }
+    state
+        .do_action(PlayerAction::PurchaseFireGem)
+        .expect_err("Fire gems are not actions");
+
+    state.mats[p2]
+        .field
+        .push(CardInField::new(Card::ShieldBearer));
+    state.mats[p2]
+        .field
+        .push(CardInField::new(Card::WolfShaman));
+    state.mats[p1].combat = 5;
+    state
+        .do_action(PlayerAction::AttackPlayerChampion(p2, 1))
+        .expect_err("Must attack the guard first");
+    state
+        .do_action(PlayerAction::AttackPlayerChampion(p2, 0))
+        .expect_err("Shield bearer has 6 defense with its own bonus");
+    state.mats[p1].combat = 6;
+    state.do_action(PlayerAction::AttackPlayerChampion(p2, 0))?;
+    {
+        assert_eq!(state.mats[p1].combat, 0);
+        assert_eq!(state.mats[p2].field.len(), 1);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_triggered_abilities() -> Result<(), &'static str> {
+    let mut state = MasterBoard::new(2, &Setup::base(), SRng::new(14279))?;
+    let p1 = state.current_player;
+    let p2 = (state.current_player + 1) % 2;
+
+    state.mats[p1]
+        .field
+        .push(CardInField::new(Card::MerchantPrince));
+    state.mats[p1].gold = 10;
+    state.shop[0] = Card::Influence;
+    let deltas = state.do_action(PlayerAction::PurchaseFromShop(0))?;
+    {
+        assert!(deltas.iter().any(|d| matches!(
+            d,
+            BoardDelta::TriggeredAbility(p, Card::MerchantPrince, Trigger::Acquire(_)) if *p == p1
+        )));
+        assert_eq!(state.mats[p1].combat, 1);
+    }
+    state.do_action(PlayerAction::PurchaseFireGem)?;
+    {
+        // Fire gems are not actions, so nothing is triggered
+        assert_eq!(state.mats[p1].combat, 1);
+    }
+
+    state.mats[p2]
+        .field
+        .push(CardInField::new(Card::BloodZealot));
+    state.mats[p2]
+        .field
+        .push(CardInField::new(Card::DawnSinger));
+    state.mats[p1].combat = 4;
+    state.do_action(PlayerAction::AttackPlayerChampion(p2, 0))?;
+    {
+        // The stunned champion heals its owner, not the attacker
+        assert_eq!(state.mats[p2].lives, 53);
+        assert_eq!(state.mats[p1].lives, 50);
+        assert_eq!(state.mats[p2].discard.last(), Some(&Card::BloodZealot));
+    }
+
+    let deltas = state.do_action(PlayerAction::EndTurn)?;
+    {
+        assert_eq!(state.current_player, p2);
+        assert_eq!(state.mats[p2].lives, 55);
+        match deltas.last() {
+            Some(BoardDelta::IncreaseHealth(p, 2)) => assert_eq!(*p, p2),
+            d => panic!("Unexpected last delta {:?}", d),
+        }
+    }
+
+    // With nothing listening the trace keeps what raised the event
+    let quiet = state.resolve_triggers(
+        GameEvent::Acquired(p1, Card::FireGem),
+        Cause::Action(PlayerAction::PurchaseFireGem),
+    )?;
+    assert!(quiet.is_empty());
+    assert!(matches!(
+        quiet.cause,
+        Cause::Action(PlayerAction::PurchaseFireGem)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_ancestry_loadouts() {
+    let setup = Setup::base()
+        .with_ancestry(0, Ancestry::Ogre)
+        .with_ancestry(2, Ancestry::Smallfolk);
+    let state = MasterBoard::new(3, &setup, SRng::new(0)).unwrap();
+
+    let cards = |p: usize| {
+        let mat = &state.mats[p];
+        let mut cards = mat.hand.clone();
+        cards.extend(mat.deck.iter().cloned());
+        cards
+    };
+
+    assert_eq!(state.mats[0].ancestry, Ancestry::Ogre);
+    assert_eq!(state.mats[0].lives, 55);
+    assert!(cards(0).contains(&Card::OgreClub));
+    assert!(!cards(0).contains(&Card::ShortSword));
+
+    assert_eq!(state.mats[1].ancestry, Ancestry::Human);
+    assert_eq!(state.mats[1].lives, 50);
+    assert_eq!(cards(1).len(), 10);
+    assert!(cards(1).contains(&Card::ShortSword));
+
+    assert_eq!(state.mats[2].lives, 45);
+    assert_eq!(cards(2).iter().filter(|c| **c == Card::Gold).count(), 6);
+    assert!(cards(2).contains(&Card::LuckyCharm));
+
+    let board = state.scoped_to(1);
+    assert_eq!(board.mats[0].ancestry, Ancestry::Ogre);
+    assert_eq!(board.mats[2].ancestry, Ancestry::Smallfolk);
+}
+
+#[test]
+fn test_draft() -> Result<(), &'static str> {
+    let setup = Setup::base().with_draft(6, 2);
+    let mut state = MasterBoard::new(2, &setup, SRng::new(14279))?;
+    let p1 = state.current_player;
+    let p2 = (state.current_player + 1) % 2;
+    let mut board = state.scoped_to(p1);
+
+    assert!(state.is_drafting());
+    assert!(board.is_drafting());
+    assert_eq!(board.draft_pool.len(), 6);
+    assert_eq!(state.shop.len(), 0);
+    assert_eq!(state.mats[p1].hand.len(), 0);
+    assert_eq!(state.mats[p2].deck.len(), 10);
+    state
+        .do_action(PlayerAction::EndTurn)
+        .expect_err("Must draft first");
+    state
+        .do_action(PlayerAction::DraftPick(6))
+        .expect_err("No such card in pool");
+
+    let first_pick = state.draft.as_ref().unwrap().pool[2].clone();
+    board += state.do_action(PlayerAction::DraftPick(2))?;
+    {
+        assert_eq!(state.current_player, p2);
+        assert_eq!(state.mats[p1].deck.last(), Some(&first_pick));
+        assert_eq!(board.draft_pool.len(), 5);
+    }
+    board += state.do_action(PlayerAction::DraftPick(0))?;
+    board += state.do_action(PlayerAction::DraftPick(0))?;
+    board += state.do_action(PlayerAction::DraftPick(0))?;
+    {
+        assert!(!state.is_drafting());
+        assert!(!board.is_drafting());
+        assert_eq!(state.current_player, p1);
+        assert_eq!(state.shop.len(), 6);
+        assert_eq!(state.shop.len() + state.shop_deck.len(), 76);
+        assert_eq!(state.mats[p1].hand.len(), 3);
+        assert_eq!(state.mats[p1].deck.len(), 9);
+        assert_eq!(state.mats[p2].hand.len(), 5);
+        assert_eq!(state.mats[p2].deck.len(), 7);
+        assert_eq!(board, state.scoped_to(p1));
+    }
+    state
+        .do_action(PlayerAction::DraftPick(0))
+        .expect_err("Draft is over");
+    play_all_hand(&mut state)?;
+    state.do_action(PlayerAction::EndTurn)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_house_rules() -> Result<(), &'static str> {
+    let mut setup = Setup::base();
+    setup.rules = Rules {
+        starting_lives: vec![40],
+        market_size: 4,
+        fire_gems: Some(3),
+        starting_hands: vec![2, 6],
+        purchase_to_hand_applies_to_gems: true,
+        first_player: FirstPlayer::Fixed(1),
+        clock: None,
+    };
+    let mut state = MasterBoard::new(2, &setup, SRng::new(0))?;
+    {
+        assert_eq!(state.current_player, 1);
+        assert_eq!(state.shop.len(), 4);
+        assert_eq!(state.gems.len(), 3);
+        assert_eq!(state.mats[0].lives, 40);
+        assert_eq!(state.mats[1].lives, 50);
+        assert_eq!(state.mats[1].hand.len(), 2);
+        assert_eq!(state.mats[0].hand.len(), 6);
+    }
+
+    state.mats[1].gold = 2;
+    state.mats[1].next_purchase_to_hand = 1;
+    state.do_action(PlayerAction::PurchaseFireGem)?;
+    {
+        assert_eq!(state.mats[1].hand.len(), 3);
+        assert_eq!(state.mats[1].hand.last(), Some(&Card::FireGem));
+        assert_eq!(state.mats[1].next_purchase_to_hand, 0);
+        assert_eq!(state.gems.len(), 2);
+    }
+
+    setup.rules.first_player = FirstPlayer::Alternating(3);
+    let state = MasterBoard::new(2, &setup, SRng::new(0))?;
+    assert_eq!(state.current_player, 1);
+    Ok(())
+}
+
+#[test]
+fn test_house_rules_that_cant_be_dealt() {
+    let mut setup = Setup::base();
+    setup.rules.starting_hands = vec![3, 11];
+    assert_eq!(
+        MasterBoard::new(2, &setup, SRng::new(0)).err(),
+        Some("Starting hand larger than the deck")
+    );
+    let loadout = Loadout::new(Ancestry::Human, &[Card::Gold, Card::Gold]);
+    setup.rules.starting_hands = vec![2, 2];
+    assert!(MasterBoard::with_loadouts(&setup, vec![loadout.clone(); 2], SRng::new(0)).is_ok());
+    setup.rules.starting_hands = vec![];
+    assert_eq!(
+        MasterBoard::with_loadouts(&setup, vec![loadout; 2], SRng::new(0)).err(),
+        Some("Starting hand larger than the deck")
+    );
+
+    let mut setup = Setup::base();
+    setup.rules.first_player = FirstPlayer::Fixed(2);
+    assert_eq!(
+        MasterBoard::new(2, &setup, SRng::new(0)).err(),
+        Some("No such first player")
+    );
+    assert!(MasterBoard::new(3, &setup, SRng::new(0)).is_ok());
+}
+
+#[test]
+fn test_journal_replay() -> Result<(), &'static str> {
+    let mut setup = Setup::test();
+    setup.rules.market_size = 3;
+    let mut journal = Journal::new(2, setup, SRng::new(14279));
+    let mut state = journal.start()?;
+    journal.do_action(&mut state, PlayerAction::Play(0, vec![]))?;
+    journal
+        .do_action(&mut state, PlayerAction::Play(99, vec![]))
+        .expect_err("Invalid card");
+    journal.do_action(&mut state, PlayerAction::EndTurn)?;
+    assert_eq!(journal.actions.len(), 2);
+
+    let data = serde_json::to_string(&journal).unwrap();
+    let journal: Journal = serde_json::from_str(&data).unwrap();
+    assert_eq!(journal.setup.rules.market_size, 3);
+
+    let replayed = journal.replay()?;
+    assert_eq!(replayed.current_player, state.current_player);
+    for p in 0..2 {
+        assert_eq!(replayed.scoped_to(p), state.scoped_to(p));
+    }
+    assert_eq!(replayed.state_hash(), state.state_hash());
+    assert_ne!(journal.start()?.state_hash(), state.state_hash());
+    Ok(())
+}
+
+#[test]
+fn test_journal_snapshots() -> Result<(), &'static str> {
+    let mut journal = Journal::new(2, Setup::base(), SRng::new(777));
+    journal.snapshot_interval = 5;
+    let mut state = journal.start()?;
+    for _ in 0..12 {
+        journal.do_action(&mut state, PlayerAction::PlayAll)?;
+        journal.do_action(&mut state, PlayerAction::EndTurn)?;
+    }
+    assert_eq!(journal.snapshots.len(), 4);
+    assert_eq!(journal.snapshots[1].at, 10);
+
+    let mut scratch = journal.clone();
+    scratch.snapshots.clear();
+    for at in [0, 9, 10, 11, 24].iter() {
+        assert_eq!(
+            journal.board_at(*at)?.state_hash(),
+            scratch.board_at(*at)?.state_hash()
+        );
+    }
+    assert_eq!(journal.verify()?.state_hash(), state.state_hash());
+    assert_eq!(journal.truncated(12)?.snapshots.len(), 2);
+
+    // Journals from before snapshots still load
+    let mut data = serde_json::to_value(&scratch).unwrap();
+    data.as_object_mut().unwrap().remove("snapshots");
+    data.as_object_mut().unwrap().remove("snapshot_interval");
+    let old: Journal = version::from_str(&data.to_string())?;
+    let compacted = old.compact(8)?;
+    assert_eq!(compacted.snapshots.len(), 3);
+    assert_eq!(compacted.actions.len(), 24);
+    let loaded: Journal = version::from_str(&version::to_string(&compacted)?)?;
+    assert_eq!(loaded.replay()?.state_hash(), state.state_hash());
+
+    journal.snapshots[2].board.mats[0].lives += 1;
+    journal.verify().expect_err("Tampered snapshot");
+    journal.compact(8).expect_err("Tampered snapshot");
+    Ok(())
+}
+
+#[test]
+fn test_delta_causes() -> Result<(), &'static str> {
+    let mut state = MasterBoard::from_scenario(
+        "
+        shop Profit StreetThug
+        shop_deck Influence
+        player Human Alice
+        hand Gold
+        deck Gold*5
+        field DeathCultist
+        player Dwarf Bob
+        deck Gold*5
+        ",
+    )?;
+
+    let played = state.trace_action(0, PlayerAction::Play(0, vec![]))?;
+    assert_eq!(played.action_id, 0);
+    assert!(matches!(
+        played.entries[0].cause,
+        Cause::Action(PlayerAction::Play(0, _))
+    ));
+    assert!(matches!(
+        played.entries[1].cause,
+        Cause::Ability {
+            card: Card::Gold,
+            kind: AbilityKind::Primary,
+            effect: Some((0, Effect::Gold(1))),
+        }
+    ));
+
+    let expended = state.trace_action(0, PlayerAction::ActivateExpendAbility(0, vec![]))?;
+    assert_eq!(expended.action_id, 1);
+    let causes: Vec<_> = expended.entries.iter().map(|e| &e.cause).collect();
+    assert!(matches!(
+        causes.as_slice(),
+        [
+            Cause::Ability { effect: None, .. },
+            Cause::Ability {
+                card: Card::DeathCultist,
+                kind: AbilityKind::Expend,
+                effect: Some((0, Effect::Combat(2))),
+            },
+        ]
+    ));
+
+    let mut other = state.clone();
+    state.mats[0].gold = 4;
+    let bought = state.trace_action(0, PlayerAction::PurchaseFromShop(0))?;
+    let refill = bought.entries.last().unwrap();
+    assert!(matches!(refill.cause, Cause::Rule(Rule::RefillShop)));
+    assert!(matches!(
+        refill.delta,
+        BoardDelta::Move(Location::ShopDeck, _, Location::Shop, _)
+    ));
+
+    // Tracing changes nothing about the deltas themselves
+    let ended = state.clone().trace_action(0, PlayerAction::EndTurn)?;
+    let deltas = state.do_action(PlayerAction::EndTurn)?;
+    assert_eq!(
+        format!("{:?}", ended.clone().deltas()),
+        format!("{:?}", deltas)
+    );
+    for entry in ended.entries.iter() {
+        let rule = match entry.delta {
+            BoardDelta::Move(Location::Deck(_), _, Location::Hand(_), _) => Rule::NewHand,
+            _ => Rule::EndOfTurn,
+        };
+        assert!(matches!(&entry.cause, Cause::Rule(r) if *r == rule));
+    }
+
+    other.action_count = 7;
+    let conceded = other.trace_action(1, PlayerAction::Concede)?;
+    assert_eq!(conceded.action_id, 7);
+    assert!(matches!(
+        conceded.entries.last().unwrap().cause,
+        Cause::Rule(Rule::GameOver)
+    ));
+    assert!(conceded
+        .entries
+        .iter()
+        .any(|e| matches!(e.cause, Cause::Rule(Rule::Elimination(1)))));
+    Ok(())
+}
+
+#[test]
+fn test_concede_and_elimination() -> Result<(), &'static str> {
+    let mut setup = Setup::base();
+    setup.rules.first_player = FirstPlayer::Fixed(0);
+    let mut state = MasterBoard::new(3, &setup, SRng::new(0))?;
+    state.mats[1]
+        .field
+        .push(CardInField::new(Card::KrakaHighPriest));
+    state.mats[0].hand.push(Card::Spark);
+    let spark = state.mats[0].hand.len() - 1;
+    let mut board = state.scoped_to(0);
+
+    state
+        .do_action_as(2, PlayerAction::EndTurn)
+        .expect_err("Not the current player");
+    board += state.do_action_as(1, PlayerAction::Concede)?;
+    {
+        let mat = &state.mats[1];
+        assert!(mat.eliminated);
+        assert_eq!(mat.lives, 0);
+        assert!(mat.field.is_empty());
+        assert!(mat.hand.is_empty());
+        assert!(mat.discard.contains(&Card::KrakaHighPriest));
+        assert_eq!(state.current_player, 0);
+        assert!(!state.game_over);
+    }
+    state
+        .do_action_as(1, PlayerAction::Concede)
+        .expect_err("Player already eliminated");
+    state
+        .do_action(PlayerAction::AttackPlayer(1, 0))
+        .expect_err("Player already eliminated");
+    assert_eq!(
+        state
+            .do_action(PlayerAction::Play(spark, vec![EffectArgument::Opponent(1)]))
+            .unwrap_err(),
+        "Opponent already eliminated"
+    );
+    board += state.do_action(PlayerAction::Play(spark, vec![EffectArgument::Opponent(2)]))?;
+    assert_eq!(state.mats[2].must_discard, 1);
+
+    board += state.do_action(PlayerAction::EndTurn)?;
+    assert_eq!(state.current_player, 2);
+    board += state.do_action(PlayerAction::Concede)?;
+    {
+        assert!(state.game_over);
+        assert!(state.mats[0].is_alive());
+        assert_eq!(state.current_player, 2);
+    }
+    assert_eq!(board, state.scoped_to(0));
+    Ok(())
+}
+
+#[test]
+fn test_timeouts() -> Result<(), &'static str> {
+    let mut setup = Setup::base();
+    setup.rules.first_player = FirstPlayer::Fixed(0);
+    setup.rules.clock = Some(Clock {
+        per_turn: Some(Duration::from_secs(30)),
+        total: None,
+        on_timeout: TimeoutPolicy::ForfeitAfter(2),
+    });
+    let mut journal = Journal::new(2, setup, SRng::new(14279));
+    let mut state = journal.start()?;
+    state.mats[0].must_discard = 1;
+
+    journal.do_action(&mut state, PlayerAction::TimedOut)?;
+    {
+        assert_eq!(state.current_player, 1);
+        assert_eq!(state.mats[0].timeouts, 1);
+        assert_eq!(state.mats[0].must_discard, 0);
+        assert_eq!(state.mats[0].hand.len(), 5);
+        assert_eq!(state.mats[0].discard.len(), 3);
+    }
+    state
+        .do_action_as(0, PlayerAction::TimedOut)
+        .expect_err("Not the current player");
+
+    journal.do_action(&mut state, PlayerAction::EndTurn)?;
+    journal.do_action(&mut state, PlayerAction::TimedOut)?;
+    {
+        assert!(state.mats[0].eliminated);
+        assert!(state.mats[1].is_alive());
+        assert!(state.game_over);
+    }
+
+    let replayed = journal.replay()?;
+    assert!(replayed.game_over);
+    assert_eq!(replayed.mats[0].timeouts, 2);
+    Ok(())
+}
+
+// Applies the deltas as the board player would receive them
+fn apply_scoped(board: &mut Board, deltas: &[BoardDelta]) {
+    for delta in deltas {
+        *board += hide_card_info(board.you, delta.clone());
+    }
+}
+
+#[test]
+fn test_known_cards() -> Result<(), &'static str> {
+    let mut setup = Setup::base();
+    setup.rules.first_player = FirstPlayer::Fixed(0);
+    let mut state = MasterBoard::new(2, &setup, SRng::new(0))?;
+    state.mats[0].hand = vec![Card::SmashAndGrab];
+    state.mats[0].discard = vec![Card::HitJob];
+    let mut mine = state.scoped_to(0);
+    let mut theirs = state.scoped_to(1);
+
+    let deltas = state.do_action(PlayerAction::Play(
+        0,
+        vec![
+            EffectArgument::ChooseSecond,
+            EffectArgument::CardInDiscard(0),
+        ],
+    ))?;
+    apply_scoped(&mut mine, &deltas);
+    apply_scoped(&mut theirs, &deltas);
+    {
+        assert_eq!(state.mats[0].known_deck, 1);
+        assert_eq!(theirs.mats[0].known_deck, vec![Card::HitJob]);
+    }
+
+    // The card on top is drawn, but everybody knows it is in the hand now
+    let deltas = state.do_action(PlayerAction::EndTurn)?;
+    apply_scoped(&mut mine, &deltas);
+    apply_scoped(&mut theirs, &deltas);
+    {
+        assert_eq!(state.mats[0].known_deck, 0);
+        assert_eq!(state.mats[0].known_hand, vec![Card::HitJob]);
+        assert!(theirs.mats[0].known_deck.is_empty());
+        assert_eq!(theirs.mats[0].known_hand, vec![Card::HitJob]);
+    }
+    assert_eq!(mine, state.scoped_to(0));
+    assert_eq!(theirs, state.scoped_to(1));
+
+    let deltas = state.do_action(PlayerAction::EndTurn)?;
+    apply_scoped(&mut mine, &deltas);
+    apply_scoped(&mut theirs, &deltas);
+    let position = state.mats[0]
+        .hand
+        .iter()
+        .position(|c| *c == Card::HitJob)
+        .unwrap();
+    let deltas = state.do_action(PlayerAction::Play(position, vec![]))?;
+    apply_scoped(&mut mine, &deltas);
+    apply_scoped(&mut theirs, &deltas);
+    {
+        assert!(state.mats[0].known_hand.is_empty());
+        assert!(theirs.mats[0].known_hand.is_empty());
+    }
+    assert_eq!(mine, state.scoped_to(0));
+    assert_eq!(theirs, state.scoped_to(1));
+
+    theirs.mats[1].known_deck = vec![Card::Gold];
+    theirs += BoardDelta::ShuffleDeck(1);
+    assert!(theirs.mats[1].known_deck.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_batch_actions() -> Result<(), &'static str> {
+    let mut setup = Setup::base();
+    setup.rules.first_player = FirstPlayer::Fixed(0);
+    let mut state = MasterBoard::new(2, &setup, SRng::new(14279))?;
+    state.mats[0].hand = vec![
+        Card::Gold,
+        Card::SmashAndGrab,
+        Card::HitJob,
+        Card::DeathCultist,
+        Card::TithePriest,
+    ];
+    state.mats[1].field = vec![CardInField::new(Card::ManAtArms)];
+    let mut theirs = state.scoped_to(1);
+
+    assert_eq!(
+        state.do_action(PlayerAction::AttackWithEverything(1)).err(),
+        Some("Nothing to do")
+    );
+
+    // Cards with choices are left in hand
+    let deltas = state.do_action(PlayerAction::PlayAll)?;
+    apply_scoped(&mut theirs, &deltas);
+    assert_eq!(state.mats[0].hand, vec![Card::SmashAndGrab]);
+    assert_eq!(state.mats[0].field.len(), 4);
+    assert_eq!(state.mats[0].gold, 1);
+    assert_eq!(state.mats[0].combat, 7);
+
+    let deltas = state.do_action(PlayerAction::ActivateAllAutomaticAbilities)?;
+    apply_scoped(&mut theirs, &deltas);
+    assert_eq!(state.mats[0].combat, 9);
+    assert!(state.mats[0].field[2].expend_ability_used);
+    assert!(!state.mats[0].field[3].expend_ability_used);
+    assert!(state
+        .do_action(PlayerAction::ActivateAllAutomaticAbilities)
+        .is_err());
+
+    // The guard is stunned first, then the player takes the rest
+    let defense = Card::ManAtArms.effective_defense(&state.mats[1].field);
+    let deltas = state.do_action(PlayerAction::AttackWithEverything(1))?;
+    apply_scoped(&mut theirs, &deltas);
+    assert!(state.mats[1].field.is_empty());
+    assert_eq!(state.mats[1].discard, vec![Card::ManAtArms]);
+    assert_eq!(state.mats[1].lives, 50 - (9 - defense));
+    assert_eq!(state.mats[0].combat, 0);
+    assert_eq!(theirs, state.scoped_to(1));
+    Ok(())
+}
+
+#[test]
+fn test_state_hash_is_pinned() {
+    // Changing these means every hash sent or saved before stops matching
+    let mut state = MasterBoard::new(2, &Setup::test(), SRng::new(0)).unwrap();
+    assert_eq!(state.state_hash(), 18124115122044419049);
+    assert_eq!(state.scoped_to(0).state_hash(), 13210957477312689942);
+
+    let before = state.state_hash();
+    state.rules.clock = Some(Clock {
+        per_turn: None,
+        total: Some(Duration::from_secs(60)),
+        on_timeout: TimeoutPolicy::ForfeitAfter(2),
+    });
+    assert_ne!(state.state_hash(), before);
+}
@@ -1,9 +1,55 @@
+pub mod analysis;
 pub mod api;
+pub mod branches;
+pub mod campaign;
 pub mod cards;
+pub mod diff;
+pub mod fair;
+pub mod hash;
+pub mod inference;
+pub mod journal;
 pub mod local;
 pub mod master;
+pub mod notation;
+pub mod puzzle;
+pub mod replay;
+pub mod report;
+pub mod scenario;
+pub mod sha256;
+pub mod starrealms;
+pub mod version;
 
+#[cfg(test)]
+mod analysis_test;
 #[cfg(test)]
 mod api_test;
 #[cfg(test)]
+mod branches_test;
+#[cfg(test)]
+mod campaign_test;
+#[cfg(test)]
+mod diff_test;
+#[cfg(test)]
+mod fair_test;
+#[cfg(test)]
+mod fuzz_test;
+#[cfg(test)]
+mod inference_test;
+#[cfg(test)]
 mod master_test;
+#[cfg(test)]
+mod notation_test;
+#[cfg(test)]
+mod puzzle_test;
+#[cfg(test)]
+mod recorded_test;
+#[cfg(test)]
+mod replay_test;
+#[cfg(test)]
+mod report_test;
+#[cfg(test)]
+mod scenario_test;
+#[cfg(test)]
+mod starrealms_test;
+#[cfg(test)]
+mod version_test;
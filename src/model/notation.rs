@@ -0,0 +1,421 @@
+use super::{
+    api::{EffectArgument, Player, PlayerAction},
+    cards::{Card, Setup},
+    journal::Journal,
+    master::MasterBoard,
+};
+use crate::smallrng::{Algorithm, SRng};
+use std::fmt::Debug;
+
+// A plain text record of a game, header tags first and then a line per turn:
+//
+//     [Seed "14279"]
+//     [Players "2"]
+//     [Setup "{...}"]
+//     [P1 "Player 1"]
+//     [P2 "Player 2"]
+//     [Result "P1"]
+//
+//     T1 P1: play Gold Gold Ruby; buy Rasmus
+//     T2 P2: play all; use all; atk P1 5; atk P1 Rasmus
+//     T3 P1: play Influence[opp:P2]; P2 concede
+//
+// The setup is the json one. The result is the winning seat, `*` while the game
+// is going on and `-` when it ended without a winner. Games seeded for the random
+// generator older versions used also have an `[Rng "legacy"]` tag.
+//
+// Actions are separated by `;`, cards are named by their variant and the same
+// verb can go on with more cards. `Gold/2` stands for the second Gold of the
+// zone when there are several. Effect arguments go in brackets right after the
+// card: `first`, `second`, `hand:2`, `discard:0`, `champion:P2:1` and `opp:P2`.
+// Other verbs are `expend`, `ally`, `sac`, `discard`, `gem`, `pick`, `timeout`,
+// `concede` and `atk P2 all`. Ending the turn isn't written, the next turn line
+// stands for it. Draft picks go on their own `D1 P1: pick Card` lines. Actions
+// are by the seat of the line unless it's written before them, and everything
+// after a `#` is ignored.
+impl Journal {
+    pub fn to_notation(&self) -> Result<String, &'static str> {
+        let mut board = self.start()?;
+        let mut lines = vec![
+            tag("Seed", &self.rng.seed().to_string()),
+            tag("Players", &self.players.to_string()),
+        ];
+        if self.rng.algorithm() == Algorithm::Legacy {
+            lines.push(tag("Rng", "legacy"));
+        }
+        lines.push(tag(
+            "Setup",
+            &serde_json::to_string(&self.setup).map_err(|_| "Could not serialize setup")?,
+        ));
+        for (p, mat) in board.mats.iter().enumerate() {
+            lines.push(tag(&seat(p), &mat.name));
+        }
+        lines.push(tag("Result", &result(&self.replay()?)));
+        lines.push("".into());
+
+        let mut turns = Turns::default();
+        let mut line = turns.header(&board) + ":";
+        let mut last_verb = "";
+        for (player, action) in self.actions.iter() {
+            let was_drafting = board.is_drafting();
+            let line_player = board.current_player;
+            if let Some((verb, text)) = format_action(&board, *player, action)? {
+                if !verb.is_empty() && verb == last_verb {
+                    line += &format!(" {}", text);
+                } else {
+                    let item = match verb {
+                        "" => text,
+                        _ => format!("{} {}", verb, text),
+                    };
+                    let separator = if line.ends_with(':') { " " } else { "; " };
+                    line += &format!("{}{}", separator, item);
+                }
+                last_verb = verb;
+            }
+            board.do_action_as(*player, action.clone())?;
+            if board.game_over {
+                continue;
+            }
+            let ended = matches!(action, PlayerAction::EndTurn);
+            if was_drafting || ended || board.current_player != line_player {
+                lines.push(line);
+                line = turns.header(&board) + ":";
+                last_verb = "";
+            }
+        }
+        lines.push(line);
+        Ok(lines.join("\n") + "\n")
+    }
+
+    // Replays the notation, checking every action against the rules
+    pub fn from_notation(text: &str) -> Result<Journal, &'static str> {
+        let mut tags: Vec<(String, String)> = vec![];
+        let mut moves = vec![];
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.starts_with('[') {
+                tags.push(parse_tag(line)?);
+            } else if !line.is_empty() {
+                moves.push(line);
+            }
+        }
+        let find_tag = |name: &str| {
+            tags.iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| value.as_str())
+                .ok_or("Missing tag in notation")
+        };
+
+        let seed = find_tag("Seed")?.parse().map_err(|_| "Invalid seed tag")?;
+        let players = find_tag("Players")?
+            .parse()
+            .map_err(|_| "Invalid players tag")?;
+        let setup: Setup =
+            serde_json::from_str(find_tag("Setup")?).map_err(|_| "Invalid setup tag")?;
+        let rng = match find_tag("Rng") {
+            Ok("legacy") => SRng::legacy(seed),
+            Ok(_) => return Err("Unknown rng tag"),
+            Err(_) => SRng::new(seed),
+        };
+        let mut journal = Journal::new(players, setup, rng);
+        let mut board = journal.start()?;
+        for (p, mat) in board.mats.iter().enumerate() {
+            if find_tag(&seat(p))? != mat.name {
+                return Err("Players don't match the setup");
+            }
+        }
+
+        let mut turns = Turns::default();
+        let mut previous: Option<(bool, Player)> = None;
+        for line in moves {
+            let mut parts = line.splitn(2, ':');
+            let header = parts.next().unwrap_or_default();
+            let rest = parts.next().ok_or("Missing turn header")?;
+            // The turn only ends here when nothing before passed it on
+            if let Some((false, player)) = previous {
+                if !board.game_over && board.current_player == player {
+                    journal.do_action_as(&mut board, player, PlayerAction::EndTurn)?;
+                }
+            }
+            if turns.header(&board) != header.trim() {
+                return Err("Turn out of order in notation");
+            }
+            previous = Some((board.is_drafting(), board.current_player));
+
+            let line_player = board.current_player;
+            for text in rest.split(';').map(str::trim).filter(|t| !t.is_empty()) {
+                let mut words: Vec<&str> = text.split_whitespace().collect();
+                let mut player = line_player;
+                if let Ok(seat) = parse_seat(words[0]) {
+                    player = seat;
+                    words.remove(0);
+                }
+                match words.as_slice() {
+                    [verb, cards @ ..]
+                        if CARD_VERBS.contains(verb) && !cards.is_empty() && cards != ["all"] =>
+                    {
+                        // Each card is found in the zone as it is by then
+                        for card in cards {
+                            let action = parse_card_action(&board, verb, card)?;
+                            journal.do_action_as(&mut board, player, action)?;
+                        }
+                    }
+                    words => {
+                        let action = parse_action(&board, words)?;
+                        journal.do_action_as(&mut board, player, action)?;
+                    }
+                }
+            }
+        }
+
+        if let Ok(expected) = find_tag("Result") {
+            if expected != result(&board) {
+                return Err("Result doesn't match the game");
+            }
+        }
+        Ok(journal)
+    }
+}
+
+// Counts the draft picks and the turns so far
+#[derive(Default)]
+struct Turns {
+    picks: usize,
+    turns: usize,
+}
+
+impl Turns {
+    fn header(&mut self, board: &MasterBoard) -> String {
+        let seat = seat(board.current_player);
+        match board.is_drafting() {
+            true => {
+                self.picks += 1;
+                format!("D{} {}", self.picks, seat)
+            }
+            false => {
+                self.turns += 1;
+                format!("T{} {}", self.turns, seat)
+            }
+        }
+    }
+}
+
+fn tag(name: &str, value: &str) -> String {
+    format!(
+        "[{} \"{}\"]",
+        name,
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+fn parse_tag(line: &str) -> Result<(String, String), &'static str> {
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or("Invalid tag in notation")?;
+    let mut parts = inner.splitn(2, ' ');
+    let name = parts.next().unwrap_or_default();
+    let quoted = parts.next().unwrap_or_default().trim();
+    let value = quoted
+        .strip_prefix('"')
+        .and_then(|q| q.strip_suffix('"'))
+        .ok_or("Invalid tag in notation")?;
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(chars.next().ok_or("Invalid tag in notation")?),
+            c => unescaped.push(c),
+        }
+    }
+    Ok((name.into(), unescaped))
+}
+
+fn seat(player: Player) -> String {
+    format!("P{}", player + 1)
+}
+
+fn field(board: &MasterBoard, player: Player) -> Result<Vec<Card>, &'static str> {
+    let mat = board.mats.get(player).ok_or("No such player")?;
+    Ok(mat.field.iter().map(|cif| cif.card.clone()).collect())
+}
+
+fn parse_seat(word: &str) -> Result<Player, &'static str> {
+    match word.strip_prefix('P').and_then(|n| n.parse::<usize>().ok()) {
+        Some(n) if n > 0 => Ok(n - 1),
+        _ => Err("Invalid seat in notation"),
+    }
+}
+
+fn result(board: &MasterBoard) -> String {
+    if !board.game_over {
+        return "*".into();
+    }
+    match board
+        .mats
+        .iter()
+        .position(|m| m.is_alive() && !m.eliminated)
+    {
+        Some(winner) => seat(winner),
+        None => "-".into(),
+    }
+}
+
+// The card at the index, numbered among the ones with the same name if needed
+fn name_in<T: Debug>(cards: &[T], index: usize) -> Result<String, &'static str> {
+    let name = format!("{:?}", cards.get(index).ok_or("No such card in notation")?);
+    let same: Vec<usize> = (0..cards.len())
+        .filter(|i| format!("{:?}", cards[*i]) == name)
+        .collect();
+    match same.iter().position(|i| *i == index) {
+        Some(0) => Ok(name),
+        Some(n) => Ok(format!("{}/{}", name, n + 1)),
+        None => Err("No such card in notation"),
+    }
+}
+
+fn find_in<T: Debug>(cards: &[T], word: &str) -> Result<usize, &'static str> {
+    let mut parts = word.splitn(2, '/');
+    let name = parts.next().unwrap_or_default();
+    let nth = match parts.next() {
+        Some(n) => n.parse::<usize>().map_err(|_| "Invalid card in notation")?,
+        None => 1,
+    };
+    (0..cards.len())
+        .filter(|i| format!("{:?}", cards[*i]) == name)
+        .nth(nth.max(1) - 1)
+        .ok_or("No such card in notation")
+}
+
+fn format_args(args: &[EffectArgument]) -> String {
+    if args.is_empty() {
+        return "".into();
+    }
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| match arg {
+            EffectArgument::ChooseFirst => "first".into(),
+            EffectArgument::ChooseSecond => "second".into(),
+            EffectArgument::CardInHand(i) => format!("hand:{}", i),
+            EffectArgument::CardInDiscard(i) => format!("discard:{}", i),
+            EffectArgument::Champion(p, i) => format!("champion:{}:{}", seat(*p), i),
+            EffectArgument::Opponent(p) => format!("opp:{}", seat(*p)),
+        })
+        .collect();
+    format!("[{}]", args.join(","))
+}
+
+fn parse_args(text: &str) -> Result<Vec<EffectArgument>, &'static str> {
+    let mut args = vec![];
+    for arg in text.split(',').filter(|a| !a.is_empty()) {
+        let parts: Vec<&str> = arg.split(':').collect();
+        let index = |word: &str| word.parse().map_err(|_| "Invalid argument in notation");
+        args.push(match parts.as_slice() {
+            ["first"] => EffectArgument::ChooseFirst,
+            ["second"] => EffectArgument::ChooseSecond,
+            ["hand", i] => EffectArgument::CardInHand(index(i)?),
+            ["discard", i] => EffectArgument::CardInDiscard(index(i)?),
+            ["champion", p, i] => EffectArgument::Champion(parse_seat(p)?, index(i)?),
+            ["opp", p] => EffectArgument::Opponent(parse_seat(p)?),
+            _ => return Err("Invalid argument in notation"),
+        });
+    }
+    Ok(args)
+}
+
+// The verb and what follows it, nothing for the end of a turn
+fn format_action(
+    board: &MasterBoard,
+    player: Player,
+    action: &PlayerAction,
+) -> Result<Option<(&'static str, String)>, &'static str> {
+    let mat = &board.mats[board.current_player];
+    let own = field(board, board.current_player)?;
+    let (verb, text) = match action {
+        PlayerAction::Play(i, args) => ("play", name_in(&mat.hand, *i)? + &format_args(args)),
+        PlayerAction::ActivateExpendAbility(i, args) => {
+            ("expend", name_in(&own, *i)? + &format_args(args))
+        }
+        PlayerAction::ActivateAllyAbility(i, args) => {
+            ("ally", name_in(&own, *i)? + &format_args(args))
+        }
+        PlayerAction::ActivateSacrificeAbility(i, args) => {
+            ("sac", name_in(&own, *i)? + &format_args(args))
+        }
+        PlayerAction::AttackPlayer(p, amount) => ("", format!("atk {} {}", seat(*p), amount)),
+        PlayerAction::AttackPlayerChampion(p, i) => (
+            "",
+            format!("atk {} {}", seat(*p), name_in(&field(board, *p)?, *i)?),
+        ),
+        PlayerAction::AttackWithEverything(p) => ("", format!("atk {} all", seat(*p))),
+        PlayerAction::PurchaseFromShop(i) => ("buy", name_in(&board.shop, *i)?),
+        PlayerAction::Discard(i) => ("discard", name_in(&mat.hand, *i)?),
+        PlayerAction::DraftPick(i) => {
+            let draft = board.draft.as_ref().ok_or("Not drafting")?;
+            ("pick", name_in(&draft.pool, *i)?)
+        }
+        PlayerAction::PurchaseFireGem => ("", "gem".into()),
+        PlayerAction::PlayAll => ("", "play all".into()),
+        PlayerAction::ActivateAllAutomaticAbilities => ("", "use all".into()),
+        PlayerAction::TimedOut => ("", "timeout".into()),
+        PlayerAction::Concede if player != board.current_player => {
+            ("", format!("{} concede", seat(player)))
+        }
+        PlayerAction::Concede => ("", "concede".into()),
+        PlayerAction::EndTurn => return Ok(None),
+    };
+    Ok(Some((verb, text)))
+}
+
+const CARD_VERBS: [&str; 7] = ["play", "expend", "ally", "sac", "buy", "discard", "pick"];
+
+fn parse_card_action(
+    board: &MasterBoard,
+    verb: &str,
+    word: &str,
+) -> Result<PlayerAction, &'static str> {
+    let mut parts = word.splitn(2, '[');
+    let card = parts.next().unwrap_or_default();
+    let args = match parts.next() {
+        Some(args) => parse_args(
+            args.strip_suffix(']')
+                .ok_or("Invalid argument in notation")?,
+        )?,
+        None => vec![],
+    };
+    let mat = &board.mats[board.current_player];
+    let in_field = || find_in(&field(board, board.current_player)?, card);
+    Ok(match verb {
+        "play" => PlayerAction::Play(find_in(&mat.hand, card)?, args),
+        "expend" => PlayerAction::ActivateExpendAbility(in_field()?, args),
+        "ally" => PlayerAction::ActivateAllyAbility(in_field()?, args),
+        "sac" => PlayerAction::ActivateSacrificeAbility(in_field()?, args),
+        "buy" => PlayerAction::PurchaseFromShop(find_in(&board.shop, card)?),
+        "discard" => PlayerAction::Discard(find_in(&mat.hand, card)?),
+        _ => {
+            let draft = board.draft.as_ref().ok_or("Not drafting")?;
+            PlayerAction::DraftPick(find_in(&draft.pool, card)?)
+        }
+    })
+}
+
+fn parse_action(board: &MasterBoard, words: &[&str]) -> Result<PlayerAction, &'static str> {
+    Ok(match words {
+        ["play", "all"] => PlayerAction::PlayAll,
+        ["use", "all"] => PlayerAction::ActivateAllAutomaticAbilities,
+        ["atk", p, "all"] => PlayerAction::AttackWithEverything(parse_seat(p)?),
+        ["atk", p, target] => {
+            let p = parse_seat(p)?;
+            match target.parse() {
+                Ok(amount) => PlayerAction::AttackPlayer(p, amount),
+                Err(_) => {
+                    PlayerAction::AttackPlayerChampion(p, find_in(&field(board, p)?, target)?)
+                }
+            }
+        }
+        ["gem"] => PlayerAction::PurchaseFireGem,
+        ["timeout"] => PlayerAction::TimedOut,
+        ["concede"] => PlayerAction::Concede,
+        _ => return Err("Unknown action in notation"),
+    })
+}
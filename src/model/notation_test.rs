@@ -0,0 +1,110 @@
+use super::{api::*, cards::*, journal::*, recorded_test::*};
+use crate::smallrng::*;
+
+fn card_by_card_game(seed: u64, setup: Setup) -> Result<Journal, &'static str> {
+    recorded_game(setup, SRng::new(seed), Strategy::CardByCard, Some(12))
+}
+
+fn assert_same_game(journal: &Journal, parsed: &Journal) -> Result<(), &'static str> {
+    assert_eq!(
+        format!("{:?}", parsed.actions),
+        format!("{:?}", journal.actions)
+    );
+    assert_eq!(
+        parsed.replay()?.state_hash(),
+        journal.replay()?.state_hash()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_notation_round_trip() -> Result<(), &'static str> {
+    let journal = card_by_card_game(14279, setup())?;
+    let text = journal.to_notation()?;
+    assert!(text.starts_with("[Seed \"14279\"]\n[Players \"2\"]\n[Setup \"{"));
+    assert!(text.contains("\n[Result \"*\"]\n\nT1 P1: play "));
+    assert!(text.contains("Gold/2"));
+    assert!(text.contains("; buy "));
+    assert!(text.ends_with("\nT13 P1:\n"));
+    assert!(!text.contains("T14"));
+    let parsed = Journal::from_notation(&text)?;
+    assert_same_game(&journal, &parsed)?;
+    assert_eq!(parsed.to_notation()?, text);
+    Ok(())
+}
+
+#[test]
+fn test_concession_notation() -> Result<(), &'static str> {
+    // Conceding out of turn ends the game
+    let journal = card_by_card_game(14279, setup())?;
+    let mut ended = journal.clone();
+    let mut state = ended.replay()?;
+    ended.do_action_as(&mut state, 1, PlayerAction::Concede)?;
+    let text = ended.to_notation()?;
+    assert!(text.contains("[Result \"P1\"]"));
+    assert!(text.ends_with("\nT13 P1: P2 concede\n"));
+    assert_same_game(&ended, &Journal::from_notation(&text)?)?;
+    Ok(())
+}
+
+#[test]
+fn test_draft_notation() -> Result<(), &'static str> {
+    let mut setup = setup();
+    setup.draft = Some(DraftRules { pool: 6, picks: 4 });
+    let drafted = card_by_card_game(777, setup)?;
+    let text = drafted.to_notation()?;
+    assert!(text.contains("\nD1 P1: pick "));
+    assert!(text.contains("\nD4 P2: pick "));
+    assert_same_game(&drafted, &Journal::from_notation(&text)?)?;
+    Ok(())
+}
+
+#[test]
+fn test_short_notation() -> Result<(), &'static str> {
+    let text = card_by_card_game(14279, setup())?.to_notation()?;
+    let lines: Vec<&str> = text.lines().collect();
+    let first_turn = lines.iter().position(|l| l.starts_with("T1 ")).unwrap();
+
+    // Handwritten notation can leave out the result and comment lines
+    let mut short: Vec<String> = lines[..first_turn + 2]
+        .iter()
+        .filter(|l| !l.starts_with("[Result"))
+        .map(|l| l.to_string())
+        .collect();
+    short.push("# Nothing else happened".into());
+    let parsed = Journal::from_notation(&short.join("\n"))?;
+    assert_eq!(parsed.replay()?.current_player, 1);
+    assert!(parsed
+        .actions
+        .iter()
+        .any(|(_, a)| matches!(a, PlayerAction::EndTurn)));
+    Ok(())
+}
+
+#[test]
+fn test_broken_notation() -> Result<(), &'static str> {
+    let text = card_by_card_game(14279, setup())?.to_notation()?;
+    let broken = |from: &str, to: &str| Journal::from_notation(&text.replacen(from, to, 1));
+    assert_eq!(
+        broken("[Result \"*\"]", "[Result \"P1\"]").unwrap_err(),
+        "Result doesn't match the game"
+    );
+    assert_eq!(
+        broken("[P1 \"", "[P1 \"Not ").unwrap_err(),
+        "Players don't match the setup"
+    );
+    assert_eq!(
+        broken("T2 P2:", "T2 P1:").unwrap_err(),
+        "Turn out of order in notation"
+    );
+    assert_eq!(
+        broken("T1 P1: play ", "T1 P1: play Rasmus ").unwrap_err(),
+        "No such card in notation"
+    );
+    assert_eq!(
+        broken("T1 P1: play ", "T1 P1: dance ").unwrap_err(),
+        "Unknown action in notation"
+    );
+    assert!(broken("T2 P2:", "T2 P2: atk P1 1000;").is_err());
+    Ok(())
+}
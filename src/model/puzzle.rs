@@ -0,0 +1,367 @@
+use super::{
+    api::{Api, Board, BoardDelta, EffectArgument, Player, PlayerAction},
+    cards::{Card, Effect},
+    master::MasterBoard,
+};
+use std::collections::HashSet;
+
+// Past this many positions the puzzle is considered too open to check
+const MAX_STATES: usize = 200_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Goal {
+    // Every opponent is out of the game
+    Lethal,
+    // No opponent has a guard left in play
+    KillAllGuards,
+    // The current player has at least this much gold
+    Gold(usize),
+}
+
+impl Goal {
+    pub fn is_met(&self, state: &MasterBoard, player: Player) -> bool {
+        let mut opponents = state.mats.iter().enumerate().filter(|(p, _)| *p != player);
+        match self {
+            Goal::Lethal => opponents.all(|(_, mat)| !mat.is_alive() || mat.eliminated),
+            Goal::KillAllGuards => {
+                opponents.all(|(_, mat)| !mat.field.iter().any(|cif| cif.card.is_guard()))
+            }
+            Goal::Gold(amount) => state.mats[player].gold >= *amount,
+        }
+    }
+}
+
+// A position to be won by the current player without ending the turn
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub board: MasterBoard,
+    pub goal: Goal,
+}
+
+// The actions reaching the goal, and the moves they stand for in no particular order
+#[derive(Clone, Debug)]
+pub struct Solution {
+    pub actions: Vec<PlayerAction>,
+    pub moves: Vec<String>,
+}
+
+impl Puzzle {
+    // A scenario with an extra `goal lethal`, `goal guards` or `goal gold <n>` line
+    pub fn from_text(text: &str) -> Result<Puzzle, &'static str> {
+        let mut goal = None;
+        let mut scenario = String::new();
+        for line in text.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["goal", "lethal"] => goal = Some(Goal::Lethal),
+                ["goal", "guards"] => goal = Some(Goal::KillAllGuards),
+                ["goal", "gold", amount] => {
+                    goal = Some(Goal::Gold(
+                        amount.parse().map_err(|_| "Expected an amount of gold")?,
+                    ))
+                }
+                ["goal", ..] => return Err("Unknown puzzle goal"),
+                _ => {
+                    scenario += line;
+                    scenario += "\n";
+                }
+            }
+        }
+        Ok(Puzzle {
+            board: MasterBoard::from_scenario(&scenario)?,
+            goal: goal.ok_or("Missing puzzle goal")?,
+        })
+    }
+
+    pub fn solve(&self) -> Result<Vec<Solution>, &'static str> {
+        self.solve_from(&self.board)
+    }
+
+    // Only the minimal solutions are kept, those that don't make moves on top
+    // of what another solution does
+    pub fn solve_from(&self, state: &MasterBoard) -> Result<Vec<Solution>, &'static str> {
+        let mut search = Search {
+            goal: &self.goal,
+            player: state.current_player,
+            visited: HashSet::new(),
+            solutions: vec![],
+        };
+        search.explore(state, &mut vec![], &mut vec![])?;
+
+        let mut solutions: Vec<Solution> = vec![];
+        search.solutions.sort_by_key(|s| s.moves.len());
+        for solution in search.solutions {
+            if !solutions
+                .iter()
+                .any(|s| is_sub_multiset(&s.moves, &solution.moves))
+            {
+                solutions.push(solution);
+            }
+        }
+        Ok(solutions)
+    }
+
+    // Lines of play are cut when they reach a position already searched, so
+    // solutions only differing in how they get to the same position count once
+    pub fn is_unique(&self) -> Result<bool, &'static str> {
+        Ok(self.solve()?.len() == 1)
+    }
+
+    // The next action of some solution from the given position
+    pub fn hint(&self, state: &MasterBoard) -> Result<Option<PlayerAction>, &'static str> {
+        let solutions = self.solve_from(state)?;
+        Ok(solutions
+            .into_iter()
+            .next()
+            .and_then(|s| s.actions.into_iter().next()))
+    }
+}
+
+struct Search<'a> {
+    goal: &'a Goal,
+    player: Player,
+    visited: HashSet<u64>,
+    solutions: Vec<Solution>,
+}
+
+impl<'a> Search<'a> {
+    fn explore(
+        &mut self,
+        state: &MasterBoard,
+        actions: &mut Vec<PlayerAction>,
+        moves: &mut Vec<String>,
+    ) -> Result<(), &'static str> {
+        if self.goal.is_met(state, self.player) {
+            let mut sorted = moves.clone();
+            sorted.sort();
+            self.solutions.push(Solution {
+                actions: actions.clone(),
+                moves: sorted,
+            });
+            return Ok(());
+        }
+        if state.game_over || !self.visited.insert(state.state_hash()) {
+            return Ok(());
+        }
+        if self.visited.len() > MAX_STATES {
+            return Err("Too many positions to search");
+        }
+
+        for action in candidate_actions(state) {
+            let mut next = state.clone();
+            if next.do_action(action.clone()).is_ok() {
+                moves.push(describe(state, &action));
+                actions.push(action);
+                self.explore(&next, actions, moves)?;
+                actions.pop();
+                moves.pop();
+            }
+        }
+        Ok(())
+    }
+}
+
+// Every action of the current turn that could be legal, EndTurn aside
+fn candidate_actions(state: &MasterBoard) -> Vec<PlayerAction> {
+    let me = state.current_player;
+    let mat = &state.mats[me];
+    let mut actions = vec![];
+    if mat.must_discard > 0 {
+        return (0..mat.hand.len()).map(PlayerAction::Discard).collect();
+    }
+
+    for (i, card) in mat.hand.iter().enumerate() {
+        for args in arguments(state, &card.primary_ability().unwrap_or_default()) {
+            actions.push(PlayerAction::Play(i, args));
+        }
+    }
+    for (i, cif) in mat.field.iter().enumerate() {
+        if let (false, Some(effects)) = (cif.expend_ability_used, cif.card.expend_ability()) {
+            for args in arguments(state, &effects) {
+                actions.push(PlayerAction::ActivateExpendAbility(i, args));
+            }
+        }
+        if let (false, Some(effects)) = (cif.ally_ability_used, cif.card.ally_ability()) {
+            for args in arguments(state, &effects) {
+                actions.push(PlayerAction::ActivateAllyAbility(i, args));
+            }
+        }
+        if let Some(effects) = cif.card.sacrifice_ability() {
+            for args in arguments(state, &effects) {
+                actions.push(PlayerAction::ActivateSacrificeAbility(i, args));
+            }
+        }
+    }
+    for (o, opponent) in state.mats.iter().enumerate().filter(|(o, _)| *o != me) {
+        for amount in 1..=mat.combat {
+            actions.push(PlayerAction::AttackPlayer(o, amount));
+        }
+        for i in 0..opponent.field.len() {
+            actions.push(PlayerAction::AttackPlayerChampion(o, i));
+        }
+    }
+    for (i, card) in state.shop.iter().enumerate() {
+        if card.effective_cost(&mat.field) <= mat.gold {
+            actions.push(PlayerAction::PurchaseFromShop(i));
+        }
+    }
+    if Card::FireGem.effective_cost(&mat.field) <= mat.gold {
+        actions.push(PlayerAction::PurchaseFireGem);
+    }
+    actions
+}
+
+// Every way of filling the arguments of the effects, some may be rejected
+fn arguments(state: &MasterBoard, effects: &[Effect]) -> Vec<Vec<EffectArgument>> {
+    let (effect, rest) = match effects.split_first() {
+        Some(split) => split,
+        None => return vec![vec![]],
+    };
+    let me = state.current_player;
+    let mat = &state.mats[me];
+    let then = |prefix: EffectArgument| (vec![prefix], rest.to_vec());
+    let in_discard = || (0..mat.discard.len()).map(EffectArgument::CardInDiscard);
+    let others = (0..state.players).filter(|o| *o != me);
+
+    let options: Vec<(Vec<EffectArgument>, Vec<Effect>)> = match effect {
+        Effect::Choice(first, second) => vec![
+            (
+                vec![EffectArgument::ChooseFirst],
+                first.iter().chain(rest).cloned().collect(),
+            ),
+            (
+                vec![EffectArgument::ChooseSecond],
+                second.iter().chain(rest).cloned().collect(),
+            ),
+        ],
+        Effect::OpponentDiscards(_) => others.map(EffectArgument::Opponent).map(then).collect(),
+        Effect::Sacrifice(amount) => {
+            let cards: Vec<_> = (0..mat.hand.len())
+                .map(EffectArgument::CardInHand)
+                .chain(in_discard())
+                .collect();
+            let mut picks = vec![vec![]];
+            for _ in 0..*amount {
+                picks = picks
+                    .into_iter()
+                    .flat_map(|pick: Vec<EffectArgument>| {
+                        cards.iter().map(move |card| {
+                            let mut pick = pick.clone();
+                            pick.push(card.clone());
+                            pick
+                        })
+                    })
+                    .collect();
+            }
+            picks.into_iter().map(|p| (p, rest.to_vec())).collect()
+        }
+        Effect::PrepareChampion => (0..mat.field.len())
+            .map(|i| then(EffectArgument::Champion(me, i)))
+            .collect(),
+        Effect::StunChampion => others
+            .flat_map(|o| (0..state.mats[o].field.len()).map(move |i| (o, i)))
+            .map(|(o, i)| then(EffectArgument::Champion(o, i)))
+            .collect(),
+        Effect::PutCardFromDiscardOverDeck | Effect::PutChampionFromDiscardOverDeck => {
+            in_discard().map(then).collect()
+        }
+        _ => vec![(vec![], rest.to_vec())],
+    };
+
+    let mut all = vec![];
+    for (prefix, remaining) in options {
+        for tail in arguments(state, &remaining) {
+            all.push(prefix.iter().cloned().chain(tail).collect());
+        }
+    }
+    all
+}
+
+// Names the action by the cards involved, so the order it is made in doesn't matter
+fn describe(state: &MasterBoard, action: &PlayerAction) -> String {
+    let mat = &state.mats[state.current_player];
+    match action {
+        PlayerAction::Play(i, args) => format!("Play {:?} {:?}", mat.hand[*i], args),
+        PlayerAction::ActivateExpendAbility(i, args) => {
+            format!("Expend {:?} {:?}", mat.field[*i].card, args)
+        }
+        PlayerAction::ActivateAllyAbility(i, args) => {
+            format!("Ally {:?} {:?}", mat.field[*i].card, args)
+        }
+        PlayerAction::ActivateSacrificeAbility(i, args) => {
+            format!("Sacrifice {:?} {:?}", mat.field[*i].card, args)
+        }
+        PlayerAction::AttackPlayer(o, _) => format!("Attack {}", o),
+        PlayerAction::AttackPlayerChampion(o, i) => {
+            format!("Stun {:?} of {}", state.mats[*o].field[*i].card, o)
+        }
+        PlayerAction::PurchaseFromShop(i) => format!("Buy {:?}", state.shop[*i]),
+        PlayerAction::Discard(i) => format!("Discard {:?}", mat.hand[*i]),
+        action => format!("{:?}", action),
+    }
+}
+
+fn is_sub_multiset(small: &[String], big: &[String]) -> bool {
+    let mut big = big.to_vec();
+    small.iter().all(|m| match big.iter().position(|b| b == m) {
+        Some(i) => {
+            big.remove(i);
+            true
+        }
+        None => false,
+    })
+}
+
+// Plays a puzzle from the point of view of its solver
+pub struct PuzzleApi {
+    puzzle: Puzzle,
+    board: MasterBoard,
+    view: Board,
+    deltas: Vec<BoardDelta>,
+}
+
+impl PuzzleApi {
+    pub fn new(puzzle: Puzzle) -> PuzzleApi {
+        let board = puzzle.board.clone();
+        let view = board.scoped_to(board.current_player);
+        PuzzleApi {
+            puzzle,
+            board,
+            view,
+            deltas: vec![],
+        }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.puzzle
+            .goal
+            .is_met(&self.board, self.puzzle.board.current_player)
+    }
+
+    pub fn hint(&self) -> Result<Option<PlayerAction>, &'static str> {
+        self.puzzle.hint(&self.board)
+    }
+
+    pub fn restart(&mut self) {
+        *self = PuzzleApi::new(self.puzzle.clone());
+    }
+}
+
+impl Api for PuzzleApi {
+    type Error = &'static str;
+    fn get_board(&self) -> &Board {
+        &self.view
+    }
+    fn do_action(&mut self, action: PlayerAction) -> Result<(), Self::Error> {
+        if let PlayerAction::EndTurn = action {
+            return Err("Puzzles must be solved within the turn");
+        }
+        let mut deltas = self.board.do_action(action)?;
+        self.view = self.board.scoped_to(self.puzzle.board.current_player);
+        self.deltas.append(&mut deltas);
+        Ok(())
+    }
+    fn poll_deltas(&mut self) -> Vec<BoardDelta> {
+        self.deltas.split_off(0)
+    }
+}
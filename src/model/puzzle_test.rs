@@ -0,0 +1,94 @@
+use super::{api::*, puzzle::*};
+
+const PUZZLE: &str = "
+goal lethal
+current 0
+shop Profit
+player Human Alice
+hand HitJob Gold
+field DeathCultist
+player Human Bob
+lives 5
+field ManAtArms
+";
+
+fn puzzle(text: &str, lives: usize) -> Puzzle {
+    Puzzle::from_text(&text.replace("lives 5", &format!("lives {}", lives))).unwrap()
+}
+
+#[test]
+fn test_solve_puzzle() -> Result<(), &'static str> {
+    // The guard has to be stunned with part of the combat
+    let solutions = puzzle(PUZZLE, 5).solve()?;
+    assert_eq!(solutions.len(), 1);
+    assert_eq!(
+        solutions[0].moves,
+        vec![
+            "Attack 1",
+            "Expend DeathCultist []",
+            "Play HitJob []",
+            "Stun ManAtArms of 1",
+        ]
+    );
+    assert!(puzzle(PUZZLE, 6).solve()?.is_empty());
+
+    let guards = Puzzle::from_text(&PUZZLE.replace("goal lethal", "goal guards"))?;
+    assert_eq!(guards.goal, Goal::KillAllGuards);
+    assert_eq!(
+        guards.solve()?[0].moves,
+        vec!["Play HitJob []", "Stun ManAtArms of 1"]
+    );
+
+    let gold = Puzzle::from_text(&PUZZLE.replace("goal lethal", "goal gold 1"))?;
+    assert!(gold.is_unique()?);
+
+    assert!(Puzzle::from_text(&PUZZLE.replace("goal lethal", "")).is_err());
+    assert!(Puzzle::from_text(&PUZZLE.replace("goal lethal", "goal fun")).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_solve_with_cost_reduction() -> Result<(), &'static str> {
+    // The hit job is only affordable with the quartermaster discount
+    let text = "
+goal lethal
+current 0
+shop HitJob
+player Human Alice
+gold 3
+next_purchase_to_hand 1
+field Quartermaster:expend
+player Human Bob
+lives 7
+";
+    let puzzle = Puzzle::from_text(text)?;
+    assert!(puzzle.is_unique()?);
+    assert!(matches!(
+        puzzle.hint(&puzzle.board)?,
+        Some(PlayerAction::PurchaseFromShop(0))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_play_puzzle_with_hints() -> Result<(), &'static str> {
+    let mut api = PuzzleApi::new(puzzle(PUZZLE, 5));
+    assert!(api.do_action(PlayerAction::EndTurn).is_err());
+
+    // A wasted action still leaves a way to win
+    api.do_action(PlayerAction::Play(1, vec![]))?;
+    let mut steps = 0;
+    while !api.is_solved() {
+        let hint = api.hint()?.expect("The puzzle has a solution");
+        api.do_action(hint)?;
+        steps += 1;
+    }
+    assert_eq!(steps, 4);
+    assert!(api.get_board().game_over);
+    assert!(api.hint()?.is_none());
+
+    api.restart();
+    assert!(!api.is_solved());
+    assert_eq!(api.get_board().your_hand.len(), 2);
+    Ok(())
+}
@@ -0,0 +1,162 @@
+// Recorded games shared by the tests of everything built on journals
+use super::{api::*, cards::*, journal::*, master::*};
+use crate::smallrng::*;
+
+// How both players spend their turns
+#[derive(Clone, Copy, Debug)]
+pub enum Strategy {
+    // Plays the whole hand and ends the turn
+    PlayAll,
+    // Plays the whole hand and buys a fire gem when it can
+    FireGems,
+    // Sacrifices its fire gems, buys the first card of the shop and then fire
+    // gems while it can and attacks with everything
+    Rush,
+    // Buys the priciest card it can afford and attacks with everything
+    Priciest,
+    // Plays the cards one at a time from the end of the hand, so repeated cards
+    // need numbering, and buys whatever it can afford
+    CardByCard,
+    // Plays the hand from the front, buys one card or fire gem and attacks
+    // with all the combat, ignoring whatever fails
+    Careless,
+}
+
+// The base game, starting with the first player so the rng only shuffles
+pub fn setup() -> Setup {
+    let mut setup = Setup::base();
+    setup.rules.first_player = FirstPlayer::Fixed(0);
+    setup
+}
+
+// A two player game after some turns, or until it's over when there is no limit
+pub fn recorded_game(
+    setup: Setup,
+    rng: SRng,
+    strategy: Strategy,
+    turns: Option<usize>,
+) -> Result<Journal, &'static str> {
+    let mut journal = Journal::new(2, setup, rng);
+    let mut state = journal.start()?;
+    while state.is_drafting() {
+        journal.do_action(&mut state, PlayerAction::DraftPick(0))?;
+    }
+    let mut turn = 0;
+    while !state.game_over && turns.is_none_or(|t| turn < t) {
+        play_turn(&mut journal, &mut state, strategy)?;
+        turn += 1;
+    }
+    Ok(journal)
+}
+
+fn play_turn(
+    journal: &mut Journal,
+    state: &mut MasterBoard,
+    strategy: Strategy,
+) -> Result<(), &'static str> {
+    let player = state.current_player;
+    let other = (player + 1) % 2;
+    match strategy {
+        Strategy::PlayAll => {
+            journal.do_action(state, PlayerAction::PlayAll)?;
+        }
+        Strategy::FireGems => {
+            journal.do_action(state, PlayerAction::PlayAll)?;
+            if state.mats[player].gold >= Card::FireGem.cost() {
+                journal.do_action(state, PlayerAction::PurchaseFireGem)?;
+            }
+        }
+        Strategy::Rush => {
+            journal.do_action(state, PlayerAction::PlayAll)?;
+            let _ = journal.do_action(state, PlayerAction::ActivateAllAutomaticAbilities);
+            while let Some(i) = (0..state.mats[player].field.len())
+                .find(|i| state.mats[player].field[*i].card == Card::FireGem)
+            {
+                journal.do_action(state, PlayerAction::ActivateSacrificeAbility(i, vec![]))?;
+            }
+            while journal
+                .do_action(state, PlayerAction::PurchaseFromShop(0))
+                .is_ok()
+            {}
+            while journal
+                .do_action(state, PlayerAction::PurchaseFireGem)
+                .is_ok()
+            {}
+            let _ = journal.do_action(state, PlayerAction::AttackWithEverything(other));
+        }
+        Strategy::Priciest => {
+            let _ = journal.do_action(state, PlayerAction::PlayAll);
+            let _ = journal.do_action(state, PlayerAction::ActivateAllAutomaticAbilities);
+            loop {
+                let gold = state.mats[player].gold;
+                let pick = (0..state.shop.len())
+                    .filter(|i| state.shop[*i].cost() <= gold)
+                    .max_by_key(|i| state.shop[*i].cost());
+                match pick {
+                    Some(i) => journal.do_action(state, PlayerAction::PurchaseFromShop(i))?,
+                    None => break,
+                };
+            }
+            let _ = journal.do_action(state, PlayerAction::AttackWithEverything(other));
+            if !state.game_over {
+                for _ in 0..state.mats[player].must_discard {
+                    journal.do_action(state, PlayerAction::Discard(0))?;
+                }
+            }
+        }
+        Strategy::CardByCard => {
+            for _ in 0..state.mats[player].must_discard {
+                journal.do_action(state, PlayerAction::Discard(0))?;
+            }
+            while !state.mats[player].hand.is_empty() {
+                let last = state.mats[player].hand.len() - 1;
+                let tries = vec![
+                    vec![],
+                    vec![EffectArgument::Opponent(other)],
+                    vec![EffectArgument::ChooseSecond],
+                    vec![EffectArgument::ChooseFirst, EffectArgument::Opponent(other)],
+                ];
+                let played = tries.into_iter().any(|args| {
+                    journal
+                        .do_action(state, PlayerAction::Play(last, args))
+                        .is_ok()
+                });
+                assert!(played);
+            }
+            for i in 0..state.mats[player].field.len() {
+                let _ = journal.do_action(state, PlayerAction::ActivateExpendAbility(i, vec![]));
+            }
+            while let Some(i) =
+                (0..state.shop.len()).find(|i| state.shop[*i].cost() <= state.mats[player].gold)
+            {
+                journal.do_action(state, PlayerAction::PurchaseFromShop(i))?;
+            }
+            if state.mats[player].gold >= Card::FireGem.cost() {
+                journal.do_action(state, PlayerAction::PurchaseFireGem)?;
+            }
+            let _ = journal.do_action(state, PlayerAction::AttackWithEverything(other));
+        }
+        Strategy::Careless => {
+            for _ in 0..state.mats[player].must_discard {
+                let _ = journal.do_action(state, PlayerAction::Discard(0));
+            }
+            for _ in 0..state.mats[player].hand.len() {
+                let _ = journal.do_action(state, PlayerAction::Play(0, vec![]));
+            }
+            let gold = state.mats[player].gold;
+            if !state.shop.is_empty() && state.shop[0].cost() <= gold {
+                let _ = journal.do_action(state, PlayerAction::PurchaseFromShop(0));
+            } else if gold >= 2 {
+                let _ = journal.do_action(state, PlayerAction::PurchaseFireGem);
+            }
+            let combat = state.mats[player].combat;
+            if combat > 0 {
+                let _ = journal.do_action(state, PlayerAction::AttackPlayer(other, combat));
+            }
+        }
+    }
+    if !state.game_over {
+        journal.do_action(state, PlayerAction::EndTurn)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,199 @@
+use super::{
+    api::{Board, BoardDelta, Player, PlayerAction},
+    journal::Journal,
+    local::hide_card_info,
+    master::MasterBoard,
+};
+
+// One delta of the game, along with the action that caused it
+#[derive(Clone, Debug)]
+pub struct Step {
+    // Index of the action in the journal
+    pub action: usize,
+    pub player: Player,
+    pub declared: PlayerAction,
+    pub delta: BoardDelta,
+    // Turns are counted from 1, every change of current player starts a new one
+    pub turn: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Perspective {
+    Player(Player),
+    // The whole game, decks and hands included
+    Omniscient,
+}
+
+// Walks through a recorded game one delta at a time, in both directions
+pub struct Replay {
+    starts: Vec<Board>,
+    steps: Vec<Step>,
+    // The whole game before every action, and after the last one
+    masters: Vec<MasterBoard>,
+    // How many steps are applied to the boards
+    position: usize,
+    boards: Vec<Board>,
+    master: MasterBoard,
+    pub perspective: Perspective,
+}
+
+impl Replay {
+    pub fn new(journal: &Journal) -> Result<Replay, &'static str> {
+        let mut board = journal.start()?;
+        let starts: Vec<Board> = (0..board.players).map(|p| board.scoped_to(p)).collect();
+        let mut steps = vec![];
+        let mut masters = vec![board.clone()];
+        let mut turn = 1;
+        for (i, (player, action)) in journal.actions.iter().enumerate() {
+            let deltas = board.do_action_as(*player, action.clone())?;
+            masters.push(board.clone());
+            for delta in deltas {
+                steps.push(Step {
+                    action: i,
+                    player: *player,
+                    declared: action.clone(),
+                    delta: delta.clone(),
+                    turn,
+                });
+                if let BoardDelta::ChangeCurrentPlayer(_) = delta {
+                    turn += 1;
+                }
+            }
+        }
+        Ok(Replay {
+            boards: starts.clone(),
+            starts,
+            steps,
+            master: masters[0].clone(),
+            masters,
+            position: 0,
+            perspective: Perspective::Omniscient,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn turns(&self) -> usize {
+        self.steps.last().map(|s| s.turn).unwrap_or(1)
+    }
+
+    // The step that was applied last, none at the start of the game
+    pub fn current(&self) -> Option<&Step> {
+        self.position.checked_sub(1).map(|i| &self.steps[i])
+    }
+
+    // The turn being played once the applied steps are on the board
+    pub fn turn(&self) -> usize {
+        1 + self.steps[..self.position]
+            .iter()
+            .filter(|s| matches!(s.delta, BoardDelta::ChangeCurrentPlayer(_)))
+            .count()
+    }
+
+    // What the player sees once the applied steps are on the board
+    pub fn board(&self, player: Player) -> &Board {
+        &self.boards[player]
+    }
+
+    // The whole game once the applied steps are on the board. Within an action
+    // that shuffles a deck the order of the deck is only right by its end
+    pub fn master(&self) -> &MasterBoard {
+        &self.master
+    }
+
+    pub fn seek(&mut self, position: usize) -> Result<(), &'static str> {
+        let position = position.min(self.steps.len());
+        if position < self.position {
+            self.boards = self.starts.clone();
+            self.position = 0;
+        }
+        for step in self.steps[self.position..position].iter() {
+            for (p, board) in self.boards.iter_mut().enumerate() {
+                board
+                    .apply(hide_card_info(p, step.delta.clone()))
+                    .map_err(|_| "The journal does not replay cleanly")?;
+            }
+        }
+        self.position = position;
+        self.master = self.master_at(position)?;
+        Ok(())
+    }
+
+    // Starts from the game before the action being played and applies its steps
+    fn master_at(&self, position: usize) -> Result<MasterBoard, &'static str> {
+        let action = match self.steps.get(position) {
+            Some(next) => next.action,
+            None => return Ok(self.masters[self.masters.len() - 1].clone()),
+        };
+        let mut master = self.masters[action].clone();
+        for step in self.steps[..position].iter().filter(|s| s.action == action) {
+            master
+                .apply(step.delta.clone())
+                .map_err(|_| "The journal does not replay cleanly")?;
+        }
+        Ok(master)
+    }
+
+    pub fn step_delta(&mut self, forward: bool) -> Result<(), &'static str> {
+        let position = match forward {
+            true => self.position + 1,
+            false => self.position.saturating_sub(1),
+        };
+        self.seek(position)
+    }
+
+    // Moves to the end of the action being played, or of the previous one
+    pub fn step_action(&mut self, forward: bool) -> Result<(), &'static str> {
+        let position = match (forward, self.steps.get(self.position), self.current()) {
+            (true, Some(next), _) => {
+                self.position
+                    + self.steps[self.position..]
+                        .iter()
+                        .take_while(|s| s.action == next.action)
+                        .count()
+            }
+            (true, None, _) => self.position,
+            (false, _, Some(current)) => self
+                .steps
+                .iter()
+                .position(|s| s.action == current.action)
+                .unwrap_or(0),
+            (false, _, None) => 0,
+        };
+        self.seek(position)
+    }
+
+    // Moves to the start of the next turn, or of the current one unless already there
+    pub fn step_turn(&mut self, forward: bool) -> Result<(), &'static str> {
+        let turn = self.turn();
+        if forward {
+            self.jump_to_turn(turn + 1)
+        } else if self.position == self.start_of(turn) {
+            self.jump_to_turn(turn.saturating_sub(1))
+        } else {
+            self.jump_to_turn(turn)
+        }
+    }
+
+    pub fn jump_to_turn(&mut self, turn: usize) -> Result<(), &'static str> {
+        self.seek(self.start_of(turn))
+    }
+
+    // Right before the first delta of the turn
+    fn start_of(&self, turn: usize) -> usize {
+        self.steps
+            .iter()
+            .position(|s| s.turn >= turn)
+            .unwrap_or(self.steps.len())
+    }
+}
@@ -0,0 +1,73 @@
+use super::{api::*, journal::*, recorded_test::*, replay::*};
+use crate::smallrng::*;
+
+fn recorded() -> Result<Journal, &'static str> {
+    recorded_game(setup(), SRng::new(14279), Strategy::PlayAll, Some(3))
+}
+
+#[test]
+fn test_step_actions_and_deltas() -> Result<(), &'static str> {
+    let journal = recorded()?;
+    let mut replay = Replay::new(&journal)?;
+    assert_eq!(replay.turns(), 3);
+    assert_eq!(replay.turn(), 1);
+    assert!(replay.current().is_none());
+
+    // The first action plays the whole hand
+    replay.step_action(true)?;
+    let step = replay.current().unwrap().clone();
+    assert_eq!(step.action, 0);
+    assert!(matches!(step.declared, PlayerAction::PlayAll));
+    assert_eq!(replay.board(0).mats[0].field.len(), 3);
+    let played = replay.board(0).clone();
+    let position = replay.position();
+    replay.step_delta(false)?;
+    assert_eq!(replay.position(), position - 1);
+    assert_ne!(*replay.board(0), played);
+    replay.step_action(false)?;
+    assert_eq!(replay.position(), 0);
+    Ok(())
+}
+
+#[test]
+fn test_step_turns() -> Result<(), &'static str> {
+    let journal = recorded()?;
+    let mut replay = Replay::new(&journal)?;
+    replay.step_turn(true)?;
+    assert_eq!(replay.turn(), 2);
+    assert_eq!(replay.board(0).current_player, 1);
+    replay.step_delta(true)?;
+    replay.step_turn(false)?;
+    assert_eq!(replay.turn(), 2);
+    replay.step_turn(false)?;
+    assert_eq!(replay.turn(), 1);
+
+    replay.jump_to_turn(2)?;
+    assert_eq!(replay.turn(), 2);
+    assert!(matches!(
+        replay.current().unwrap().delta,
+        BoardDelta::ChangeCurrentPlayer(1)
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_perspectives() -> Result<(), &'static str> {
+    // Each player only sees their own hand, the whole game is seen at once
+    let journal = recorded()?;
+    let mut replay = Replay::new(&journal)?;
+    replay.seek(replay.len())?;
+    let end = journal.replay()?;
+    assert_eq!(*replay.board(1), end.scoped_to(1));
+    assert_eq!(replay.master().state_hash(), end.state_hash());
+    replay.seek(0)?;
+    assert_eq!(replay.master().state_hash(), journal.start()?.state_hash());
+
+    // Hands are shown even in the middle of actions that reshuffle a deck
+    for position in 0..=replay.len() {
+        replay.seek(position)?;
+        let master = replay.master();
+        assert_eq!(master.mats[1].hand.len(), replay.board(0).mats[1].hand);
+    }
+    Ok(())
+}
@@ -0,0 +1,261 @@
+use super::{
+    api::{BoardDelta, Location, Player, PlayerAction},
+    cards::{Card, Faction},
+    journal::Journal,
+    master::{MasterBoard, MasterMat},
+};
+use serde::Serialize;
+
+// What happened during one turn, as seen from the player playing it
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct TurnReport {
+    pub turn: usize,
+    pub player: Player,
+    pub gold: usize,
+    pub combat: usize,
+    pub damage_to_players: usize,
+    pub damage_to_champions: usize,
+    // For every player, once the turn is over
+    pub lives: Vec<usize>,
+    pub cards_owned: Vec<usize>,
+}
+
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct CardEvent {
+    pub turn: usize,
+    pub player: Player,
+    pub card: Card,
+}
+
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct Report {
+    pub players: Vec<String>,
+    pub winner: Option<Player>,
+    pub turns: Vec<TurnReport>,
+    pub bought: Vec<CardEvent>,
+    pub sacrificed: Vec<CardEvent>,
+    // The player is the one who stunned the champion
+    pub stunned: Vec<CardEvent>,
+    // Cards owned at the end of the game, by faction
+    pub factions: Vec<Vec<(Faction, usize)>>,
+    // From this turn on the winner had more lives than anybody else
+    pub lead_taken: Option<usize>,
+}
+
+impl Report {
+    pub fn new(journal: &Journal) -> Result<Report, &'static str> {
+        let mut state = journal.start()?;
+        let mut report = Report {
+            players: state.mats.iter().map(|m| m.name.clone()).collect(),
+            winner: None,
+            turns: vec![],
+            bought: vec![],
+            sacrificed: vec![],
+            stunned: vec![],
+            factions: vec![],
+            lead_taken: None,
+        };
+        let mut turn = new_turn(1, &state);
+
+        for (player, action) in journal.actions.iter() {
+            let before = state.clone();
+            let deltas = state.do_action_as(*player, action.clone())?;
+            // Turns start counting once the draft is over
+            if before.is_drafting() {
+                turn = new_turn(1, &state);
+                continue;
+            }
+            let attacking = matches!(
+                action,
+                PlayerAction::AttackPlayer(_, _)
+                    | PlayerAction::AttackPlayerChampion(_, _)
+                    | PlayerAction::AttackWithEverything(_)
+            );
+            let mut lives: Vec<usize> = before.mats.iter().map(|m| m.lives).collect();
+            let mut spent = 0;
+
+            for delta in deltas {
+                match delta {
+                    BoardDelta::IncreaseGold(p, x) if p == turn.player => turn.gold += x,
+                    BoardDelta::IncreaseCombat(p, x) if p == turn.player => turn.combat += x,
+                    BoardDelta::DecreaseCombat(p, x) if attacking && p == turn.player => spent += x,
+                    BoardDelta::IncreaseHealth(p, x) => lives[p] += x,
+                    BoardDelta::DecreaseHealth(p, x) => {
+                        if attacking && p != turn.player {
+                            turn.damage_to_players += x;
+                            spent = spent.saturating_sub(x);
+                        }
+                        lives[p] = lives[p].saturating_sub(x);
+                    }
+                    BoardDelta::Move(from, _, to, Some(card)) => {
+                        let event = |player| CardEvent {
+                            turn: turn.turn,
+                            player,
+                            card: card.clone(),
+                        };
+                        match (from, to) {
+                            (Location::Shop, to) | (Location::FireGems, to) => {
+                                if let Some(p) = owner(&to) {
+                                    report.bought.push(event(p));
+                                }
+                            }
+                            (from, Location::Sacrifice) => {
+                                if let Some(p) = owner(&from) {
+                                    report.sacrificed.push(event(p));
+                                }
+                            }
+                            // Once out of lives the field is cleaned up, not stunned
+                            (Location::Field(o), Location::Discard(_))
+                                if o != turn.player && card.is_champion() && lives[o] > 0 =>
+                            {
+                                report.stunned.push(event(turn.player))
+                            }
+                            _ => {}
+                        }
+                    }
+                    BoardDelta::ChangeCurrentPlayer(next) => {
+                        turn.damage_to_champions += spent;
+                        spent = 0;
+                        report.turns.push(end_turn(turn, &state));
+                        turn = new_turn(report.turns.len() + 1, &state);
+                        turn.player = next;
+                    }
+                    _ => {}
+                }
+            }
+            turn.damage_to_champions += spent;
+        }
+        if turn.gold + turn.combat + turn.damage_to_players + turn.damage_to_champions > 0 {
+            report.turns.push(end_turn(turn, &state));
+        }
+
+        if state.game_over {
+            report.winner = state
+                .mats
+                .iter()
+                .position(|m| m.is_alive() && !m.eliminated);
+        }
+        report.factions = state.mats.iter().map(|m| factions(&owned(m))).collect();
+        if let Some(winner) = report.winner {
+            let leading = |t: &TurnReport| {
+                (0..t.lives.len()).all(|p| p == winner || t.lives[p] < t.lives[winner])
+            };
+            let since = report
+                .turns
+                .iter()
+                .rposition(|t| !leading(t))
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            report.lead_taken = report.turns.get(since).map(|t| t.turn);
+        }
+        Ok(report)
+    }
+
+    pub fn to_json(&self) -> Result<String, &'static str> {
+        serde_json::to_string_pretty(self).map_err(|_| "Could not serialize report")
+    }
+
+    pub fn to_text(&self) -> String {
+        let name = |p: Player| self.players[p].clone();
+        let mut lines = vec![];
+        match self.winner {
+            Some(winner) => lines.push(format!(
+                "Winner: {} after {} turns, leading since turn {}",
+                name(winner),
+                self.turns.len(),
+                self.lead_taken.unwrap_or_default()
+            )),
+            None => lines.push(format!("No winner after {} turns", self.turns.len())),
+        }
+
+        lines.push("".into());
+        lines.push("Turn  Player      Gold  Combat  To players  To champions".into());
+        for t in self.turns.iter() {
+            lines.push(format!(
+                "{:>4}  {:<10} {:>5} {:>7} {:>11} {:>13}",
+                t.turn,
+                name(t.player),
+                t.gold,
+                t.combat,
+                t.damage_to_players,
+                t.damage_to_champions
+            ));
+        }
+
+        for (p, player) in self.players.iter().enumerate() {
+            let of = |events: &[CardEvent]| -> Vec<String> {
+                events
+                    .iter()
+                    .filter(|e| e.player == p)
+                    .map(|e| format!("{:?} ({})", e.card, e.turn))
+                    .collect()
+            };
+            let sizes: Vec<String> = self
+                .turns
+                .iter()
+                .map(|t| t.cards_owned[p].to_string())
+                .collect();
+            let factions: Vec<String> = self.factions[p]
+                .iter()
+                .map(|(f, n)| format!("{:?} {}", f, n))
+                .collect();
+            lines.push("".into());
+            lines.push(player.clone());
+            lines.push(format!("  Bought: {}", of(&self.bought).join(", ")));
+            lines.push(format!("  Sacrificed: {}", of(&self.sacrificed).join(", ")));
+            lines.push(format!("  Stunned: {}", of(&self.stunned).join(", ")));
+            lines.push(format!("  Cards owned: {}", sizes.join(" ")));
+            lines.push(format!("  Factions: {}", factions.join(", ")));
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+fn new_turn(turn: usize, state: &MasterBoard) -> TurnReport {
+    TurnReport {
+        turn,
+        player: state.current_player,
+        gold: 0,
+        combat: 0,
+        damage_to_players: 0,
+        damage_to_champions: 0,
+        lives: vec![],
+        cards_owned: vec![],
+    }
+}
+
+fn end_turn(mut turn: TurnReport, state: &MasterBoard) -> TurnReport {
+    turn.lives = state.mats.iter().map(|m| m.lives).collect();
+    turn.cards_owned = state.mats.iter().map(|m| owned(m).len()).collect();
+    turn
+}
+
+fn owner(location: &Location) -> Option<Player> {
+    match location {
+        Location::Hand(p) | Location::Discard(p) | Location::Deck(p) | Location::Field(p) => {
+            Some(*p)
+        }
+        _ => None,
+    }
+}
+
+fn owned(mat: &MasterMat) -> Vec<&Card> {
+    mat.deck
+        .iter()
+        .chain(mat.hand.iter())
+        .chain(mat.discard.iter())
+        .chain(mat.field.iter().map(|cif| &cif.card))
+        .collect()
+}
+
+fn factions(cards: &[&Card]) -> Vec<(Faction, usize)> {
+    let mut factions: Vec<(Faction, usize)> = vec![];
+    for card in cards {
+        let faction = card.faction();
+        match factions.iter_mut().find(|(f, _)| *f == faction) {
+            Some((_, n)) => *n += 1,
+            None => factions.push((faction, 1)),
+        }
+    }
+    factions
+}
@@ -0,0 +1,62 @@
+use super::{journal::*, recorded_test::*, report::*};
+use crate::smallrng::*;
+
+fn finished_game() -> Result<Journal, &'static str> {
+    let mut setup = setup();
+    setup.rules.starting_lives = vec![30, 20];
+    recorded_game(setup, SRng::new(777), Strategy::Rush, None)
+}
+
+#[test]
+fn test_winner_and_turns() -> Result<(), &'static str> {
+    let report = Report::new(&finished_game()?)?;
+    assert_eq!(report.winner, Some(0));
+    assert_eq!(report.players, vec!["Player 1", "Player 2"]);
+    assert_eq!(report.turns[0].player, 0);
+    assert_eq!(report.turns[1].player, 1);
+    let dealt: usize = report
+        .turns
+        .iter()
+        .filter(|t| t.player == 0)
+        .map(|t| t.damage_to_players)
+        .sum();
+    assert!(dealt >= 20);
+    for t in report.turns.iter() {
+        assert!(t.damage_to_players + t.damage_to_champions <= t.combat);
+    }
+    let lead = report.lead_taken.unwrap();
+    assert!(lead <= report.turns.len());
+    assert!(report.turns[lead - 1..]
+        .iter()
+        .all(|t| t.lives[0] > t.lives[1]));
+    Ok(())
+}
+
+#[test]
+fn test_cards_owned() -> Result<(), &'static str> {
+    let journal = finished_game()?;
+    let report = Report::new(&journal)?;
+    let end = journal.replay()?;
+    let owned = |p: usize| {
+        let mat = &end.mats[p];
+        mat.deck.len() + mat.hand.len() + mat.discard.len() + mat.field.len()
+    };
+    for p in 0..2 {
+        let bought = report.bought.iter().filter(|e| e.player == p).count();
+        let factions: usize = report.factions[p].iter().map(|(_, n)| n).sum();
+        assert_eq!(factions, owned(p));
+        let sacrificed = report.sacrificed.iter().filter(|e| e.player == p).count();
+        assert!(sacrificed > 0);
+        assert_eq!(10 + bought, owned(p) + sacrificed);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_report_formats() -> Result<(), &'static str> {
+    let report = Report::new(&finished_game()?)?;
+    assert!(report.to_text().starts_with("Winner: Player 1"));
+    let json: serde_json::Value = serde_json::from_str(&report.to_json()?).unwrap();
+    assert_eq!(json["winner"], 0);
+    Ok(())
+}
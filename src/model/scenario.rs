@@ -0,0 +1,248 @@
+use super::{
+    cards::{Ancestry, Card, CardInField, Loadout, Rules},
+    master::{MasterBoard, MasterMat},
+};
+use crate::smallrng::{Algorithm, SRng};
+use serde::de::DeserializeOwned;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+// A plain text description of a position, one line per zone or stat:
+//
+//     seed 14279
+//     current 0
+//     shop KrakaHighPriest Profit StreetThug
+//     shop_deck Influence*2 Bribe
+//     gems FireGem*16
+//
+//     player Human Player 1
+//     lives 50
+//     hand Gold*2 Ruby
+//     deck Gold*5 Dagger ShortSword
+//     field DeathCultist:expend WolfShaman:expend:ally
+//
+// Decks list their cards from the bottom, the last one is drawn first. `Card*n`
+// repeats a card, missing zones are empty and missing stats are zero, except for
+// lives that start as the ancestry says. Everything after a `#` is ignored.
+// Rules are the default ones, and `seed 14279 legacy` picks the random generator
+// older games were played with.
+impl MasterBoard {
+    pub fn from_scenario(text: &str) -> Result<MasterBoard, &'static str> {
+        let mut board = MasterBoard {
+            shop: vec![],
+            shop_deck: vec![],
+            gems: vec![],
+            sacrificed: vec![],
+            current_player: 0,
+            players: 0,
+            game_over: false,
+            mats: vec![],
+            draft: None,
+            rules: Rules::default(),
+            rng: SRng::new(0),
+            action_count: 0,
+        };
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+            let key = match words.next() {
+                Some(key) => key,
+                None => continue,
+            };
+            let rest: Vec<&str> = words.collect();
+
+            if key == "player" {
+                let ancestry: Ancestry =
+                    parse_name(rest.first().ok_or("Missing player ancestry")?)?;
+                let loadout = Loadout::new(ancestry, &[]);
+                let name = rest[1..].join(" ");
+                board
+                    .mats
+                    .push(MasterMat::new(name, 0, &loadout, SRng::new(0)));
+                continue;
+            }
+
+            match board.mats.last_mut() {
+                None => match key {
+                    "seed" => {
+                        board.rng = match rest.as_slice() {
+                            [seed, "legacy"] => SRng::legacy(parse_number(&[seed])?),
+                            _ => SRng::new(parse_number(&rest)?),
+                        }
+                    }
+                    "current" => board.current_player = parse_number(&rest)?,
+                    "game_over" => board.game_over = true,
+                    "shop" => board.shop = parse_cards(&rest)?,
+                    "shop_deck" => board.shop_deck = parse_cards(&rest)?,
+                    "gems" => board.gems = parse_cards(&rest)?,
+                    "sacrificed" => board.sacrificed = parse_cards(&rest)?,
+                    _ => return Err("Unknown board line in scenario"),
+                },
+                Some(mat) => match key {
+                    "hand" => mat.hand = parse_cards(&rest)?,
+                    "deck" => mat.deck = parse_cards(&rest)?,
+                    "discard" => mat.discard = parse_cards(&rest)?,
+                    "known_hand" => mat.known_hand = parse_cards(&rest)?,
+                    "field" => mat.field = parse_field(&rest)?,
+                    "eliminated" => mat.eliminated = true,
+                    "lives" => mat.lives = parse_number(&rest)?,
+                    "combat" => mat.combat = parse_number(&rest)?,
+                    "gold" => mat.gold = parse_number(&rest)?,
+                    "must_discard" => mat.must_discard = parse_number(&rest)?,
+                    "next_action_purchase_to_top_of_deck" => {
+                        mat.next_action_purchase_to_top_of_deck = parse_number(&rest)?
+                    }
+                    "next_purchase_to_top_of_deck" => {
+                        mat.next_purchase_to_top_of_deck = parse_number(&rest)?
+                    }
+                    "next_purchase_to_hand" => mat.next_purchase_to_hand = parse_number(&rest)?,
+                    "timeouts" => mat.timeouts = parse_number(&rest)?,
+                    "known_deck" => mat.known_deck = parse_number(&rest)?,
+                    _ => return Err("Unknown player line in scenario"),
+                },
+            }
+        }
+
+        board.players = board.mats.len();
+        if board.players < 2 {
+            return Err("A scenario needs at least two players");
+        }
+        if board.current_player >= board.players {
+            return Err("No such current player");
+        }
+        if board.mats.iter().any(|mat| mat.known_deck > mat.deck.len()) {
+            return Err("More known cards than cards in deck");
+        }
+        Ok(board)
+    }
+
+    // Draft state and house rules are not part of the scenario
+    pub fn to_scenario(&self) -> String {
+        let mut lines = vec![
+            match self.rng.algorithm() {
+                Algorithm::Legacy => format!("seed {} legacy", self.rng.seed()),
+                Algorithm::Pcg32 => format!("seed {}", self.rng.seed()),
+            },
+            format!("current {}", self.current_player),
+        ];
+        if self.game_over {
+            lines.push("game_over".into());
+        }
+        lines.push(format_cards("shop", &self.shop));
+        lines.push(format_cards("shop_deck", &self.shop_deck));
+        lines.push(format_cards("gems", &self.gems));
+        lines.push(format_cards("sacrificed", &self.sacrificed));
+
+        for mat in self.mats.iter() {
+            lines.push("".into());
+            lines.push(format!("player {:?} {}", mat.ancestry, mat.name));
+            if mat.eliminated {
+                lines.push("eliminated".into());
+            }
+            let stats = [
+                ("lives", mat.lives),
+                ("combat", mat.combat),
+                ("gold", mat.gold),
+                ("must_discard", mat.must_discard),
+                (
+                    "next_action_purchase_to_top_of_deck",
+                    mat.next_action_purchase_to_top_of_deck,
+                ),
+                (
+                    "next_purchase_to_top_of_deck",
+                    mat.next_purchase_to_top_of_deck,
+                ),
+                ("next_purchase_to_hand", mat.next_purchase_to_hand),
+                ("timeouts", mat.timeouts),
+                ("known_deck", mat.known_deck),
+            ];
+            for (name, value) in stats.iter() {
+                if *value > 0 || *name == "lives" {
+                    lines.push(format!("{} {}", name, value));
+                }
+            }
+            lines.push(format_cards("hand", &mat.hand));
+            lines.push(format_cards("deck", &mat.deck));
+            lines.push(format_cards("discard", &mat.discard));
+            let field: Vec<String> = mat
+                .field
+                .iter()
+                .map(|cif| {
+                    let mut card = format!("{:?}", cif.card);
+                    if cif.expend_ability_used {
+                        card += ":expend";
+                    }
+                    if cif.ally_ability_used {
+                        card += ":ally";
+                    }
+                    card
+                })
+                .collect();
+            lines.push(format!("field {}", field.join(" ")).trim_end().into());
+            if !mat.known_hand.is_empty() {
+                lines.push(format_cards("known_hand", &mat.known_hand));
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+// Cards and ancestries are written as their variant name
+fn parse_name<T: DeserializeOwned>(name: &str) -> Result<T, &'static str> {
+    serde_json::from_value(serde_json::Value::String(name.into()))
+        .map_err(|_| "Unknown name in scenario")
+}
+
+fn parse_number<T: FromStr>(words: &[&str]) -> Result<T, &'static str> {
+    match words {
+        [number] => number.parse().map_err(|_| "Expected a number in scenario"),
+        _ => Err("Expected a number in scenario"),
+    }
+}
+
+fn parse_cards(words: &[&str]) -> Result<Vec<Card>, &'static str> {
+    let mut cards = vec![];
+    for word in words {
+        let mut parts = word.splitn(2, '*');
+        let card: Card = parse_name(parts.next().unwrap_or_default())?;
+        let times = match parts.next() {
+            Some(times) => times.parse().map_err(|_| "Expected a number in scenario")?,
+            None => 1,
+        };
+        cards.extend(vec![card; times]);
+    }
+    Ok(cards)
+}
+
+fn parse_field(words: &[&str]) -> Result<Vec<CardInField>, &'static str> {
+    let mut field = vec![];
+    for word in words {
+        let mut parts = word.split(':');
+        let mut cif = CardInField::new(parse_name(parts.next().unwrap_or_default())?);
+        for flag in parts {
+            match flag {
+                "expend" => cif.expend_ability_used = true,
+                "ally" => cif.ally_ability_used = true,
+                _ => return Err("Unknown field flag in scenario"),
+            }
+        }
+        field.push(cif);
+    }
+    Ok(field)
+}
+
+// Runs of the same card are written once with their count
+fn format_cards<T: Debug + PartialEq>(key: &str, cards: &[T]) -> String {
+    let mut line = key.to_string();
+    let mut i = 0;
+    while i < cards.len() {
+        let run = cards[i..].iter().take_while(|c| **c == cards[i]).count();
+        line += &format!(" {:?}", cards[i]);
+        if run > 1 {
+            line += &format!("*{}", run);
+        }
+        i += run;
+    }
+    line
+}
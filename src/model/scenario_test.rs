@@ -0,0 +1,86 @@
+use super::{api::*, cards::*, master::*};
+use crate::smallrng::*;
+
+const SCENARIO: &str = "
+seed 42
+current 0
+shop KrakaHighPriest Profit StreetThug
+shop_deck Influence*2 Bribe
+gems FireGem*16
+
+player Human Alice
+hand Gold*2 HitJob # about to win
+deck Gold*5 Dagger ShortSword
+field DeathCultist:expend WolfShaman
+
+player Dwarf Bob
+lives 5
+field ManAtArms
+";
+
+#[test]
+fn test_parse_scenario() -> Result<(), &'static str> {
+    let mut state = MasterBoard::from_scenario(SCENARIO)?;
+    assert_eq!(state.players, 2);
+    assert_eq!(
+        state.shop_deck,
+        vec![Card::Influence, Card::Influence, Card::Bribe]
+    );
+    assert_eq!(state.gems.len(), 16);
+
+    let alice = &state.mats[0];
+    assert_eq!(alice.name, "Alice");
+    assert_eq!(alice.hand, vec![Card::Gold, Card::Gold, Card::HitJob]);
+    assert_eq!(alice.deck.last(), Some(&Card::ShortSword));
+    assert!(alice.field[0].expend_ability_used);
+    assert!(!alice.field[1].expend_ability_used);
+    assert_eq!(state.mats[1].ancestry, Ancestry::Dwarf);
+    assert_eq!(state.mats[1].lives, 5);
+
+    state.do_action(PlayerAction::PlayAll)?;
+    state.do_action(PlayerAction::ActivateAllAutomaticAbilities)?;
+    state.do_action(PlayerAction::AttackWithEverything(1))?;
+    assert!(state.game_over);
+
+    assert!(MasterBoard::from_scenario("player Human Alone").is_err());
+    assert!(MasterBoard::from_scenario("shop NotACard").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_known_deck_past_the_deck() {
+    let scenario = format!("{}known_deck 3\ndeck Gold*2\n", SCENARIO);
+    assert_eq!(
+        MasterBoard::from_scenario(&scenario).err(),
+        Some("More known cards than cards in deck")
+    );
+
+    let mut state = MasterBoard::from_scenario(SCENARIO).unwrap();
+    state.mats[1].known_deck = 3;
+    assert!(state.scoped_to(0).mats[1].known_deck.is_empty());
+}
+
+#[test]
+fn test_scenario_round_trip() -> Result<(), &'static str> {
+    let mut state = MasterBoard::new(3, &Setup::base(), SRng::new(14279))?;
+    for _ in 0..4 {
+        state.do_action(PlayerAction::PlayAll)?;
+        state.do_action(PlayerAction::PurchaseFireGem).ok();
+        state.do_action(PlayerAction::EndTurn)?;
+    }
+    let mut champion = CardInField::new(Card::WolfShaman);
+    champion.ally_ability_used = true;
+    state.mats[1].field.push(champion);
+
+    let text = state.to_scenario();
+    let parsed = MasterBoard::from_scenario(&text)?;
+    assert_eq!(parsed.to_scenario(), text);
+    assert_eq!(parsed.state_hash(), state.state_hash());
+
+    state.rng = SRng::legacy(state.rng.seed());
+    let text = state.to_scenario();
+    assert!(text.lines().next().unwrap().ends_with(" legacy"));
+    let parsed = MasterBoard::from_scenario(&text)?;
+    assert_eq!(parsed.state_hash(), state.state_hash());
+    Ok(())
+}
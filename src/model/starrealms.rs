@@ -0,0 +1,990 @@
+// Star Realms played on the same board as Hero Realms. Authority is kept
+// in the mat lives, trade in the mat gold, the trade row is the shop,
+// explorers are the supply pile and scrapped cards go to the sacrifice pile.
+// Bases and outposts stay in the field like champions do.
+use super::{
+    api::{Api, Board, BoardDelta, EffectArgument, GameCard, Location, Mat, Player, PlayerAction},
+    cards::CardInField,
+    master::draw,
+};
+use crate::lobby::{Game, GameApi, GameSetup};
+use crate::smallrng::SRng;
+use serde::{Deserialize, Serialize};
+
+pub struct StarRealms;
+
+impl Game for StarRealms {
+    type Setup = Setup;
+    type Api = HotSeat;
+    type SeatOption = ();
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Setup {
+    pub trade_deck: Vec<Card>,
+    pub explorers: usize,
+    pub player_deck: Vec<Card>,
+    pub authority: usize,
+    pub trade_row: usize,
+}
+
+impl Setup {
+    pub fn base() -> Setup {
+        use Card::*;
+        let mut trade_deck = vec![];
+        for (card, copies) in [
+            (FederationShuttle, 3),
+            (Cutter, 3),
+            (TradeEscort, 1),
+            (Flagship, 1),
+            (Freighter, 2),
+            (CommandShip, 1),
+            (TradingPost, 2),
+            (DefenseCenter, 1),
+            (BlobFighter, 3),
+            (TradePod, 3),
+            (Ram, 2),
+            (BlobDestroyer, 2),
+            (BattleBlob, 1),
+            (BlobWheel, 3),
+            (TheHive, 1),
+            (ImperialFighter, 3),
+            (Corvette, 2),
+            (ImperialFrigate, 3),
+            (SurveyShip, 3),
+            (SpaceStation, 2),
+            (RoyalRedoubt, 1),
+            (Battlecruiser, 1),
+            (TradeBot, 3),
+            (MissileBot, 3),
+            (SupplyBot, 3),
+            (BattleStation, 2),
+            (PatrolMech, 2),
+            (BattleMech, 1),
+        ]
+        .iter()
+        {
+            trade_deck.extend(vec![card.clone(); *copies]);
+        }
+
+        let mut player_deck = vec![Scout; 8];
+        player_deck.extend(vec![Viper; 2]);
+
+        Setup {
+            trade_deck,
+            explorers: 10,
+            player_deck,
+            authority: 50,
+            trade_row: 5,
+        }
+    }
+}
+
+impl GameSetup for Setup {
+    fn min_players(&self) -> usize {
+        2
+    }
+    fn max_players(&self) -> usize {
+        4
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Faction {
+    Unaligned,
+    TradeFederation,
+    Blob,
+    StarEmpire,
+    MachineCult,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Card {
+    Scout,
+    Viper,
+    Explorer,
+
+    FederationShuttle,
+    Cutter,
+    TradeEscort,
+    Flagship,
+    Freighter,
+    CommandShip,
+    TradingPost,
+    DefenseCenter,
+
+    BlobFighter,
+    TradePod,
+    Ram,
+    BlobDestroyer,
+    BattleBlob,
+    BlobWheel,
+    TheHive,
+
+    ImperialFighter,
+    Corvette,
+    ImperialFrigate,
+    SurveyShip,
+    SpaceStation,
+    RoyalRedoubt,
+    Battlecruiser,
+
+    TradeBot,
+    MissileBot,
+    SupplyBot,
+    BattleStation,
+    PatrolMech,
+    BattleMech,
+}
+
+impl GameCard for Card {
+    type SeatOption = ();
+    type Trigger = ();
+
+    fn supply_card() -> Card {
+        Card::Explorer
+    }
+
+    fn id(&self) -> &'static str {
+        Card::id(self)
+    }
+
+    // There is nothing to choose for a seat
+    fn seat_option_id(_: &()) -> &'static str {
+        ""
+    }
+}
+
+impl Card {
+    pub fn id(&self) -> &'static str {
+        use Card::*;
+        match self {
+            Scout => "Scout",
+            Viper => "Viper",
+            Explorer => "Explorer",
+
+            FederationShuttle => "FederationShuttle",
+            Cutter => "Cutter",
+            TradeEscort => "TradeEscort",
+            Flagship => "Flagship",
+            Freighter => "Freighter",
+            CommandShip => "CommandShip",
+            TradingPost => "TradingPost",
+            DefenseCenter => "DefenseCenter",
+
+            BlobFighter => "BlobFighter",
+            TradePod => "TradePod",
+            Ram => "Ram",
+            BlobDestroyer => "BlobDestroyer",
+            BattleBlob => "BattleBlob",
+            BlobWheel => "BlobWheel",
+            TheHive => "TheHive",
+
+            ImperialFighter => "ImperialFighter",
+            Corvette => "Corvette",
+            ImperialFrigate => "ImperialFrigate",
+            SurveyShip => "SurveyShip",
+            SpaceStation => "SpaceStation",
+            RoyalRedoubt => "RoyalRedoubt",
+            Battlecruiser => "Battlecruiser",
+
+            TradeBot => "TradeBot",
+            MissileBot => "MissileBot",
+            SupplyBot => "SupplyBot",
+            BattleStation => "BattleStation",
+            PatrolMech => "PatrolMech",
+            BattleMech => "BattleMech",
+        }
+    }
+
+    pub fn faction(&self) -> Faction {
+        use Card::*;
+        match self {
+            Scout | Viper | Explorer => Faction::Unaligned,
+            FederationShuttle | Cutter | TradeEscort | Flagship | Freighter | CommandShip
+            | TradingPost | DefenseCenter => Faction::TradeFederation,
+            BlobFighter | TradePod | Ram | BlobDestroyer | BattleBlob | BlobWheel | TheHive => {
+                Faction::Blob
+            }
+            ImperialFighter | Corvette | ImperialFrigate | SurveyShip | SpaceStation
+            | RoyalRedoubt | Battlecruiser => Faction::StarEmpire,
+            TradeBot | MissileBot | SupplyBot | BattleStation | PatrolMech | BattleMech => {
+                Faction::MachineCult
+            }
+        }
+    }
+
+    pub fn cost(&self) -> usize {
+        use Card::*;
+        match self {
+            Scout | Viper => 0,
+            FederationShuttle | BlobFighter | ImperialFighter | TradeBot => 1,
+            Explorer | Cutter | TradePod | Corvette | MissileBot => 2,
+            TradingPost | Ram | BlobWheel | ImperialFrigate | SurveyShip | SupplyBot
+            | BattleStation => 3,
+            Freighter | BlobDestroyer | SpaceStation | PatrolMech => 4,
+            TradeEscort | DefenseCenter | TheHive | BattleMech => 5,
+            Flagship | BattleBlob | RoyalRedoubt | Battlecruiser => 6,
+            CommandShip => 8,
+        }
+    }
+
+    // Bases keep their defense while in the field, ships have none
+    pub fn defense(&self) -> Option<usize> {
+        use Card::*;
+        match self {
+            TradingPost | SpaceStation => Some(4),
+            DefenseCenter | BlobWheel | TheHive | BattleStation => Some(5),
+            RoyalRedoubt => Some(6),
+            _ => None,
+        }
+    }
+
+    pub fn is_base(&self) -> bool {
+        self.defense().is_some()
+    }
+
+    // Outposts have to be destroyed before attacking their owner or other bases
+    pub fn is_outpost(&self) -> bool {
+        use Card::*;
+        matches!(
+            self,
+            TradingPost | DefenseCenter | SpaceStation | RoyalRedoubt | BattleStation
+        )
+    }
+
+    // Applied when a ship is played, and once per turn for bases
+    pub fn primary_ability(&self) -> Option<Vec<Effect>> {
+        use Card::*;
+        use Effect::*;
+        match self {
+            Scout => Some(vec![Trade(1)]),
+            Viper => Some(vec![Combat(1)]),
+            Explorer => Some(vec![Trade(2)]),
+            FederationShuttle => Some(vec![Trade(2)]),
+            Cutter => Some(vec![Authority(4), Trade(2)]),
+            TradeEscort => Some(vec![Authority(4), Combat(4)]),
+            Flagship => Some(vec![Combat(5), Draw(1)]),
+            Freighter => Some(vec![Trade(4)]),
+            CommandShip => Some(vec![Authority(4), Combat(5), Draw(2)]),
+            TradingPost => Some(vec![Choice(vec![Authority(1)], vec![Trade(1)])]),
+            DefenseCenter => Some(vec![Choice(vec![Authority(3)], vec![Combat(2)])]),
+            BlobFighter => Some(vec![Combat(3)]),
+            TradePod => Some(vec![Trade(3)]),
+            Ram => Some(vec![Combat(5)]),
+            BlobDestroyer => Some(vec![Combat(6)]),
+            BattleBlob => Some(vec![Combat(8)]),
+            BlobWheel => Some(vec![Combat(1)]),
+            TheHive => Some(vec![Combat(3)]),
+            ImperialFighter => Some(vec![Combat(2), OpponentDiscards(1)]),
+            Corvette => Some(vec![Combat(1), Draw(1)]),
+            ImperialFrigate => Some(vec![Combat(4), OpponentDiscards(1)]),
+            SurveyShip => Some(vec![Trade(1), Draw(1)]),
+            SpaceStation => Some(vec![Combat(2)]),
+            RoyalRedoubt => Some(vec![Combat(3)]),
+            Battlecruiser => Some(vec![Combat(5), Draw(1)]),
+            TradeBot => Some(vec![Trade(1), Scrap]),
+            MissileBot => Some(vec![Combat(2), Scrap]),
+            SupplyBot => Some(vec![Trade(2), Scrap]),
+            BattleStation => None,
+            PatrolMech => Some(vec![Choice(vec![Trade(3)], vec![Combat(5)])]),
+            BattleMech => Some(vec![Combat(4), Scrap]),
+        }
+    }
+
+    pub fn ally_ability(&self) -> Option<Vec<Effect>> {
+        use Card::*;
+        use Effect::*;
+        match self {
+            FederationShuttle => Some(vec![Authority(4)]),
+            Cutter => Some(vec![Combat(4)]),
+            TradeEscort => Some(vec![Draw(1)]),
+            Flagship => Some(vec![Authority(5)]),
+            DefenseCenter => Some(vec![Combat(2)]),
+            BlobFighter => Some(vec![Draw(1)]),
+            TradePod => Some(vec![Combat(2)]),
+            Ram => Some(vec![Combat(2)]),
+            BattleBlob => Some(vec![Draw(1)]),
+            TheHive => Some(vec![Draw(1)]),
+            ImperialFighter => Some(vec![Combat(2)]),
+            Corvette => Some(vec![Combat(2)]),
+            ImperialFrigate => Some(vec![Combat(2)]),
+            SpaceStation => Some(vec![Combat(2)]),
+            RoyalRedoubt => Some(vec![OpponentDiscards(1)]),
+            Battlecruiser => Some(vec![OpponentDiscards(1)]),
+            TradeBot => Some(vec![Combat(2)]),
+            MissileBot => Some(vec![Trade(2)]),
+            SupplyBot => Some(vec![Combat(2)]),
+            PatrolMech => Some(vec![Scrap]),
+            BattleMech => Some(vec![Draw(1)]),
+            _ => None,
+        }
+    }
+
+    pub fn scrap_ability(&self) -> Option<Vec<Effect>> {
+        use Card::*;
+        use Effect::*;
+        match self {
+            Explorer => Some(vec![Combat(2)]),
+            TradingPost => Some(vec![Combat(3)]),
+            Ram => Some(vec![Trade(3)]),
+            BattleBlob => Some(vec![Combat(4)]),
+            BlobWheel => Some(vec![Trade(3)]),
+            ImperialFrigate => Some(vec![Draw(1)]),
+            SurveyShip => Some(vec![OpponentDiscards(1)]),
+            SpaceStation => Some(vec![Trade(4)]),
+            Battlecruiser => Some(vec![Draw(1)]),
+            BattleStation => Some(vec![Combat(5)]),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Effect {
+    Trade(usize),
+    Combat(usize),
+    Authority(usize),
+    Draw(usize),
+    OpponentDiscards(usize),
+    // Optionally scrap a card from hand or discard pile
+    Scrap,
+    Choice(Vec<Effect>, Vec<Effect>),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct MasterBoard {
+    pub trade_row: Vec<Card>,
+    pub trade_deck: Vec<Card>,
+    pub explorers: usize,
+    pub scrapped: Vec<Card>,
+
+    pub current_player: usize,
+    pub players: usize,
+    pub game_over: bool,
+    pub mats: Vec<MasterMat>,
+    pub rng: SRng,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct MasterMat {
+    pub name: String,
+    pub field: Vec<CardInField<Card>>,
+    pub hand: Vec<Card>,
+    pub discard: Vec<Card>,
+    pub deck: Vec<Card>,
+    pub authority: usize,
+    pub combat: usize,
+    pub trade: usize,
+    pub must_discard: usize,
+}
+
+impl MasterMat {
+    pub fn is_alive(&self) -> bool {
+        self.authority > 0
+    }
+}
+
+impl MasterBoard {
+    pub fn new(players: usize, setup: &Setup, mut rng: SRng) -> Result<MasterBoard, &'static str> {
+        if players == 0 {
+            return Err("No players");
+        }
+        let mut trade_deck = setup.trade_deck.clone();
+        rng.shuffle(&mut trade_deck);
+        let trade_row = draw(setup.trade_row.min(trade_deck.len()), &mut trade_deck);
+        let current_player = (rng.next_u64() % players as u64) as usize;
+        let mats = (0..players)
+            .map(|i| {
+                let mut deck = setup.player_deck.clone();
+                rng.shuffle(&mut deck);
+                let turn_position = (i + players - current_player) % players;
+                let starting_cards = match turn_position {
+                    0 => 3,
+                    1 if players > 2 => 4,
+                    _ => 5,
+                };
+                let hand = draw(starting_cards.min(deck.len()), &mut deck);
+                MasterMat {
+                    name: format!("Player {}", i + 1),
+                    field: vec![],
+                    hand,
+                    discard: vec![],
+                    deck,
+                    authority: setup.authority,
+                    combat: 0,
+                    trade: 0,
+                    must_discard: 0,
+                }
+            })
+            .collect();
+
+        Ok(MasterBoard {
+            trade_row,
+            trade_deck,
+            explorers: setup.explorers,
+            scrapped: vec![],
+            current_player,
+            players,
+            game_over: false,
+            mats,
+            rng,
+        })
+    }
+
+    // The next player with authority left
+    pub fn next_player(&self, player: Player) -> Player {
+        (1..=self.players)
+            .map(|i| (player + i) % self.players)
+            .find(|p| self.mats[*p].is_alive())
+            .unwrap_or(player)
+    }
+
+    // Removes the player cards from play and passes the turn if it was theirs
+    fn eliminate(&mut self, player: Player) -> Vec<BoardDelta<Card>> {
+        let mut deltas = vec![];
+        let mat = &mut self.mats[player];
+        if mat.authority > 0 {
+            deltas.push(BoardDelta::DecreaseHealth(player, mat.authority));
+            mat.authority = 0;
+        }
+        if mat.trade > 0 {
+            deltas.push(BoardDelta::DecreaseGold(player, mat.trade));
+            mat.trade = 0;
+        }
+        if mat.combat > 0 {
+            deltas.push(BoardDelta::DecreaseCombat(player, mat.combat));
+            mat.combat = 0;
+        }
+        if mat.must_discard > 0 {
+            deltas.push(BoardDelta::DecreaseDiscardAmount(player, mat.must_discard));
+            mat.must_discard = 0;
+        }
+        while !mat.field.is_empty() {
+            let card = mat.field.remove(0).card;
+            deltas.push(BoardDelta::Move(
+                Location::Field(player),
+                0,
+                Location::Discard(player),
+                Some(card.clone()),
+            ));
+            mat.discard.push(card);
+        }
+        while !mat.hand.is_empty() {
+            let card = mat.hand.remove(0);
+            deltas.push(BoardDelta::Move(
+                Location::Hand(player),
+                0,
+                Location::Discard(player),
+                Some(card.clone()),
+            ));
+            mat.discard.push(card);
+        }
+        deltas.push(BoardDelta::PlayerEliminated(player));
+
+        if self.current_player == player && self.mats.iter().filter(|m| m.is_alive()).count() > 1 {
+            self.current_player = self.next_player(player);
+            deltas.push(BoardDelta::ChangeCurrentPlayer(self.current_player));
+        }
+        deltas
+    }
+
+    pub fn apply_effects(
+        &mut self,
+        mut effects: Vec<Effect>,
+        mut effect_args: Vec<EffectArgument>,
+    ) -> Result<Vec<BoardDelta<Card>>, &'static str> {
+        let player = self.current_player;
+        let mut deltas = vec![];
+        effects.reverse();
+        effect_args.reverse();
+        while let Some(effect) = effects.pop() {
+            match effect {
+                Effect::Trade(x) => {
+                    self.mats[player].trade += x;
+                    deltas.push(BoardDelta::IncreaseGold(player, x));
+                }
+                Effect::Combat(x) => {
+                    self.mats[player].combat += x;
+                    deltas.push(BoardDelta::IncreaseCombat(player, x));
+                }
+                Effect::Authority(x) => {
+                    self.mats[player].authority += x;
+                    deltas.push(BoardDelta::IncreaseHealth(player, x));
+                }
+                Effect::Draw(x) => {
+                    let mat = &mut self.mats[player];
+                    for _ in 0..x {
+                        if mat.deck.is_empty() && !mat.discard.is_empty() {
+                            while !mat.discard.is_empty() {
+                                let card = mat.discard.remove(0);
+                                deltas.push(BoardDelta::Move(
+                                    Location::Discard(player),
+                                    0,
+                                    Location::Deck(player),
+                                    Some(card.clone()),
+                                ));
+                                mat.deck.push(card);
+                            }
+                            self.rng.shuffle(&mut mat.deck);
+                            deltas.push(BoardDelta::ShuffleDeck(player));
+                        }
+
+                        if let Some(card) = mat.deck.pop() {
+                            mat.hand.push(card.clone());
+                            deltas.push(BoardDelta::Move(
+                                Location::Deck(player),
+                                0,
+                                Location::Hand(player),
+                                Some(card),
+                            ));
+                        }
+                    }
+                }
+                Effect::OpponentDiscards(x) => match effect_args.pop() {
+                    Some(EffectArgument::Opponent(o))
+                        if o != player && o < self.players && self.mats[o].is_alive() =>
+                    {
+                        self.mats[o].must_discard += x;
+                        deltas.push(BoardDelta::IncreaseDiscardAmount(o, x));
+                    }
+                    _ => return Err("Wrong arguments, expected opponent"),
+                },
+                Effect::Scrap => match effect_args.pop() {
+                    Some(EffectArgument::CardInHand(i)) => {
+                        if i >= self.mats[player].hand.len() {
+                            return Err("No such card in hand");
+                        }
+                        let card = self.mats[player].hand.remove(i);
+                        self.scrapped.push(card.clone());
+                        deltas.push(BoardDelta::Move(
+                            Location::Hand(player),
+                            i,
+                            Location::Sacrifice,
+                            Some(card),
+                        ));
+                    }
+                    Some(EffectArgument::CardInDiscard(i)) => {
+                        if i >= self.mats[player].discard.len() {
+                            return Err("No such card in discard");
+                        }
+                        let card = self.mats[player].discard.remove(i);
+                        self.scrapped.push(card.clone());
+                        deltas.push(BoardDelta::Move(
+                            Location::Discard(player),
+                            i,
+                            Location::Sacrifice,
+                            Some(card),
+                        ));
+                    }
+                    None => {}
+                    _ => return Err("Wrong arguments, expected card in hand or discard"),
+                },
+                Effect::Choice(first, second) => {
+                    let mut branch = match effect_args.pop() {
+                        Some(EffectArgument::ChooseFirst) => first,
+                        Some(EffectArgument::ChooseSecond) => second,
+                        _ => return Err("Wrong arguments, expected choice"),
+                    };
+                    branch.reverse();
+                    effects.append(&mut branch);
+                }
+            }
+        }
+        Ok(deltas)
+    }
+
+    pub fn do_action(
+        &mut self,
+        action: PlayerAction,
+    ) -> Result<Vec<BoardDelta<Card>>, &'static str> {
+        if self.game_over {
+            return Err("The game is already over");
+        }
+
+        let mut state = self.clone();
+        let mut deltas = vec![];
+        let player = state.current_player;
+
+        if state.mats[player].must_discard > 0 && !matches!(action, PlayerAction::Concede) {
+            if let PlayerAction::Discard(position) = action {
+                let mat = &mut state.mats[player];
+                if position >= mat.hand.len() {
+                    return Err("No such card in hand");
+                }
+                let card = mat.hand.remove(position);
+                mat.discard.push(card.clone());
+                mat.must_discard -= 1;
+                deltas.push(BoardDelta::Move(
+                    Location::Hand(player),
+                    position,
+                    Location::Discard(player),
+                    Some(card),
+                ));
+                deltas.push(BoardDelta::DecreaseDiscardAmount(player, 1));
+
+                *self = state;
+                return Ok(deltas);
+            } else {
+                return Err("Must discard first");
+            }
+        }
+
+        match action {
+            PlayerAction::Play(position, effect_args) => {
+                let mat = &mut state.mats[player];
+                if position >= mat.hand.len() {
+                    return Err("No such card in hand");
+                }
+                let card = mat.hand.remove(position);
+                mat.field.push(CardInField::new(card.clone()));
+                deltas.push(BoardDelta::Move(
+                    Location::Hand(player),
+                    position,
+                    Location::Field(player),
+                    Some(card.clone()),
+                ));
+
+                // Bases use their ability through ActivateExpendAbility
+                if !card.is_base() {
+                    if let Some(effects) = card.primary_ability() {
+                        deltas.append(&mut state.apply_effects(effects, effect_args)?);
+                    }
+                }
+            }
+
+            PlayerAction::ActivateExpendAbility(card_in_field, effect_args) => {
+                let mat = &mut state.mats[player];
+                if card_in_field >= mat.field.len() {
+                    return Err("No such card in field");
+                }
+                let card = mat.field[card_in_field].card.clone();
+                if !card.is_base() {
+                    return Err("Only bases can be activated");
+                }
+                if mat.field[card_in_field].expend_ability_used {
+                    return Err("Base already used this turn");
+                }
+                let effects = card.primary_ability().ok_or("Base has no ability")?;
+                mat.field[card_in_field].expend_ability_used = true;
+                deltas.push(BoardDelta::SetExpendAbilityUsed(
+                    player,
+                    card_in_field,
+                    true,
+                ));
+                deltas.append(&mut state.apply_effects(effects, effect_args)?);
+            }
+
+            PlayerAction::ActivateAllyAbility(card_in_field, effect_args) => {
+                let mat = &mut state.mats[player];
+                if card_in_field >= mat.field.len() {
+                    return Err("No such card in field");
+                }
+                if mat.field[card_in_field].ally_ability_used {
+                    return Err("Ally ability already used");
+                }
+                let card = mat.field[card_in_field].card.clone();
+                let effects = card
+                    .ally_ability()
+                    .ok_or("Card does not have ally ability")?;
+                if mat
+                    .field
+                    .iter()
+                    .filter(|cif| cif.card.faction() == card.faction())
+                    .count()
+                    < 2
+                {
+                    return Err("No ally in field");
+                }
+                mat.field[card_in_field].ally_ability_used = true;
+                deltas.push(BoardDelta::SetAllyAbilityUsed(player, card_in_field, true));
+                deltas.append(&mut state.apply_effects(effects, effect_args)?);
+            }
+
+            PlayerAction::ActivateSacrificeAbility(card_in_field, effect_args) => {
+                let mat = &mut state.mats[player];
+                if card_in_field >= mat.field.len() {
+                    return Err("No such card in field");
+                }
+                let card = mat.field[card_in_field].card.clone();
+                let effects = card
+                    .scrap_ability()
+                    .ok_or("Card does not have scrap ability")?;
+                mat.field.remove(card_in_field);
+
+                // Scrapped explorers go back to their pile
+                if card == Card::Explorer {
+                    state.explorers += 1;
+                    deltas.push(BoardDelta::Move(
+                        Location::Field(player),
+                        card_in_field,
+                        Location::FireGems,
+                        Some(card),
+                    ));
+                } else {
+                    state.scrapped.push(card.clone());
+                    deltas.push(BoardDelta::Move(
+                        Location::Field(player),
+                        card_in_field,
+                        Location::Sacrifice,
+                        Some(card),
+                    ));
+                }
+                deltas.append(&mut state.apply_effects(effects, effect_args)?);
+            }
+
+            PlayerAction::AttackPlayer(target, amount) => {
+                if target >= state.players {
+                    return Err("No such player");
+                }
+                if target == player {
+                    return Err("Player can't attack himself");
+                }
+                if !state.mats[target].is_alive() {
+                    return Err("Player already eliminated");
+                }
+                if state.mats[target]
+                    .field
+                    .iter()
+                    .any(|cif| cif.card.is_outpost())
+                {
+                    return Err("Can't attack player with outposts");
+                }
+                if state.mats[player].combat < amount {
+                    return Err("Not enough combat");
+                }
+                state.mats[player].combat -= amount;
+                state.mats[target].authority = state.mats[target].authority.saturating_sub(amount);
+                deltas.push(BoardDelta::DecreaseCombat(player, amount));
+                deltas.push(BoardDelta::DecreaseHealth(target, amount));
+                if !state.mats[target].is_alive() {
+                    deltas.append(&mut state.eliminate(target));
+                }
+            }
+
+            PlayerAction::AttackPlayerChampion(target, base) => {
+                if target >= state.players {
+                    return Err("No such player");
+                }
+                if target == player {
+                    return Err("Player can't attack his own bases");
+                }
+                if !state.mats[target].is_alive() {
+                    return Err("Player already eliminated");
+                }
+                if base >= state.mats[target].field.len() {
+                    return Err("No such card in field");
+                }
+                let card = state.mats[target].field[base].card.clone();
+                let defense = card.defense().ok_or("Target card is not a base")?;
+                if !card.is_outpost()
+                    && state.mats[target]
+                        .field
+                        .iter()
+                        .any(|cif| cif.card.is_outpost())
+                {
+                    return Err("Can't attack base with outposts");
+                }
+                if state.mats[player].combat < defense {
+                    return Err("Not enough combat");
+                }
+                state.mats[player].combat -= defense;
+                state.mats[target].field.remove(base);
+                state.mats[target].discard.push(card.clone());
+                deltas.push(BoardDelta::DecreaseCombat(player, defense));
+                deltas.push(BoardDelta::Move(
+                    Location::Field(target),
+                    base,
+                    Location::Discard(target),
+                    Some(card),
+                ));
+            }
+
+            PlayerAction::PurchaseFromShop(position) => {
+                if position >= state.trade_row.len() {
+                    return Err("No such card in trade row");
+                }
+                let card = state.trade_row[position].clone();
+                let mat = &mut state.mats[player];
+                if mat.trade < card.cost() {
+                    return Err("Not enough trade");
+                }
+                mat.trade -= card.cost();
+                mat.discard.push(card.clone());
+                state.trade_row.remove(position);
+                deltas.push(BoardDelta::DecreaseGold(player, card.cost()));
+                deltas.push(BoardDelta::Move(
+                    Location::Shop,
+                    position,
+                    Location::Discard(player),
+                    Some(card),
+                ));
+                if let Some(card) = state.trade_deck.pop() {
+                    state.trade_row.push(card.clone());
+                    deltas.push(BoardDelta::Move(
+                        Location::ShopDeck,
+                        0,
+                        Location::Shop,
+                        Some(card),
+                    ));
+                }
+            }
+
+            PlayerAction::PurchaseFireGem => {
+                if state.explorers == 0 {
+                    return Err("No more explorers");
+                }
+                let cost = Card::Explorer.cost();
+                let mat = &mut state.mats[player];
+                if mat.trade < cost {
+                    return Err("Not enough trade");
+                }
+                mat.trade -= cost;
+                mat.discard.push(Card::Explorer);
+                state.explorers -= 1;
+                deltas.push(BoardDelta::DecreaseGold(player, cost));
+                deltas.push(BoardDelta::Move(
+                    Location::FireGems,
+                    0,
+                    Location::Discard(player),
+                    Some(Card::Explorer),
+                ));
+            }
+
+            PlayerAction::EndTurn => {
+                let mat = &mut state.mats[player];
+                if mat.trade > 0 {
+                    deltas.push(BoardDelta::DecreaseGold(player, mat.trade));
+                    mat.trade = 0;
+                }
+                if mat.combat > 0 {
+                    deltas.push(BoardDelta::DecreaseCombat(player, mat.combat));
+                    mat.combat = 0;
+                }
+
+                while let Some(i) = mat.field.iter().position(|cif| !cif.card.is_base()) {
+                    let card = mat.field.remove(i).card;
+                    deltas.push(BoardDelta::Move(
+                        Location::Field(player),
+                        i,
+                        Location::Discard(player),
+                        Some(card.clone()),
+                    ));
+                    mat.discard.push(card);
+                }
+
+                for (i, cif) in mat.field.iter_mut().enumerate() {
+                    cif.expend_ability_used = false;
+                    cif.ally_ability_used = false;
+                    deltas.push(BoardDelta::SetExpendAbilityUsed(player, i, false));
+                    deltas.push(BoardDelta::SetAllyAbilityUsed(player, i, false));
+                }
+
+                while !mat.hand.is_empty() {
+                    let card = mat.hand.remove(0);
+                    deltas.push(BoardDelta::Move(
+                        Location::Hand(player),
+                        0,
+                        Location::Discard(player),
+                        Some(card.clone()),
+                    ));
+                    mat.discard.push(card);
+                }
+
+                deltas.append(&mut state.apply_effects(vec![Effect::Draw(5)], vec![])?);
+
+                state.current_player = state.next_player(player);
+                deltas.push(BoardDelta::ChangeCurrentPlayer(state.current_player));
+            }
+
+            PlayerAction::Discard(_) => return Err("Cant discard now"),
+            PlayerAction::DraftPick(_) => return Err("Not drafting"),
+            PlayerAction::TimedOut => return Err("Star Realms games have no clock"),
+            PlayerAction::PlayAll
+            | PlayerAction::ActivateAllAutomaticAbilities
+            | PlayerAction::AttackWithEverything(_) => {
+                return Err("Batch actions are not supported in Star Realms")
+            }
+            PlayerAction::Concede => deltas.append(&mut state.eliminate(player)),
+        }
+
+        if state.mats.iter().filter(|m| m.is_alive()).count() == 1 {
+            deltas.push(BoardDelta::GameOver);
+            state.game_over = true;
+        }
+
+        *self = state;
+        Ok(deltas)
+    }
+
+    pub fn scoped_to(&self, player: Player) -> Board<Card> {
+        Board {
+            shop: self.trade_row.clone(),
+            shop_deck: self.trade_deck.len(),
+            draft_pool: vec![],
+            gems: self.explorers,
+            sacrificed: self.scrapped.clone(),
+            current_player: self.current_player,
+            players: self.players,
+            game_over: self.game_over,
+            mats: self
+                .mats
+                .iter()
+                .map(|mat| Mat {
+                    name: mat.name.clone(),
+                    ancestry: (),
+                    field: mat.field.clone(),
+                    hand: mat.hand.len(),
+                    discard: mat.discard.clone(),
+                    deck: mat.deck.len(),
+                    // Nothing is ever put on top of a deck in Star Realms
+                    known_deck: vec![],
+                    known_hand: vec![],
+                    lives: mat.authority,
+                    combat: mat.combat,
+                    gold: mat.trade,
+                    must_discard: mat.must_discard,
+                })
+                .collect(),
+            you: player,
+            your_hand: self.mats[player].hand.clone(),
+        }
+    }
+}
+
+// Every seat plays on the same screen, so the view follows the current player
+pub struct HotSeat {
+    board: MasterBoard,
+    view: Board<Card>,
+    deltas: Vec<BoardDelta<Card>>,
+}
+
+impl HotSeat {
+    pub fn new(players: usize, setup: &Setup, rng: SRng) -> Result<HotSeat, &'static str> {
+        let board = MasterBoard::new(players, setup, rng)?;
+        let view = board.scoped_to(board.current_player);
+        Ok(HotSeat {
+            board,
+            view,
+            deltas: vec![],
+        })
+    }
+}
+
+impl GameApi for HotSeat {}
+
+impl Api<Card> for HotSeat {
+    type Error = &'static str;
+    fn get_board(&self) -> &Board<Card> {
+        &self.view
+    }
+    fn do_action(&mut self, action: PlayerAction) -> Result<(), Self::Error> {
+        let mut deltas = self.board.do_action(action)?;
+        self.view = self.board.scoped_to(self.board.current_player);
+        self.deltas.append(&mut deltas);
+        Ok(())
+    }
+    fn poll_deltas(&mut self) -> Vec<BoardDelta<Card>> {
+        self.deltas.split_off(0)
+    }
+}
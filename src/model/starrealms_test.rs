@@ -0,0 +1,232 @@
+use super::{api::*, cards::CardInField, local::HeroRealms, starrealms::*};
+use crate::lobby::{Game, GameSetup};
+use crate::smallrng::*;
+
+#[test]
+fn test_initial_state() {
+    let state = MasterBoard::new(2, &Setup::base(), SRng::new(0)).unwrap();
+    let p1 = &state.mats[state.current_player];
+    let p2 = &state.mats[(state.current_player + 1) % 2];
+
+    assert_eq!(p1.hand.len(), 3);
+    assert_eq!(p1.deck.len(), 7);
+    assert_eq!(p1.authority, 50);
+    assert_eq!(p2.hand.len(), 5);
+    assert_eq!(p2.deck.len(), 5);
+    assert_eq!(state.trade_row.len(), 5);
+    assert_eq!(state.trade_row.len() + state.trade_deck.len(), 58);
+    assert_eq!(state.explorers, 10);
+
+    let board = state.scoped_to(state.current_player);
+    assert_eq!(board.mats[state.current_player].lives, 50);
+    assert_eq!(board.gems, 10);
+
+    assert!(MasterBoard::new(0, &Setup::base(), SRng::new(0)).is_err());
+    assert!(HotSeat::new(0, &Setup::base(), SRng::new(0)).is_err());
+}
+
+#[test]
+fn test_ally_and_scrap() -> Result<(), &'static str> {
+    let mut state = MasterBoard::new(2, &Setup::base(), SRng::new(0))?;
+    let p = state.current_player;
+    state.mats[p].hand = vec![Card::BlobFighter, Card::Ram, Card::Scout];
+    let mut board = state.scoped_to(p);
+
+    board += state.do_action(PlayerAction::Play(0, vec![]))?;
+    board += state.do_action(PlayerAction::Play(0, vec![]))?;
+    assert_eq!(state.mats[p].combat, 8);
+    state
+        .do_action(PlayerAction::ActivateAllyAbility(2, vec![]))
+        .expect_err("No such card in field");
+
+    board += state.do_action(PlayerAction::ActivateAllyAbility(0, vec![]))?;
+    board += state.do_action(PlayerAction::ActivateAllyAbility(1, vec![]))?;
+    {
+        assert_eq!(state.mats[p].hand.len(), 2);
+        assert_eq!(state.mats[p].combat, 10);
+    }
+    state
+        .do_action(PlayerAction::ActivateAllyAbility(1, vec![]))
+        .expect_err("Ally ability already used");
+
+    board += state.do_action(PlayerAction::ActivateSacrificeAbility(1, vec![]))?;
+    {
+        assert_eq!(state.mats[p].trade, 3);
+        assert_eq!(state.scrapped, vec![Card::Ram]);
+        assert_eq!(state.mats[p].field.len(), 1);
+    }
+    state
+        .do_action(PlayerAction::ActivateAllyAbility(0, vec![]))
+        .expect_err("Ally ability already used");
+    assert_eq!(board, state.scoped_to(p));
+    Ok(())
+}
+
+#[test]
+fn test_bases_and_outposts() -> Result<(), &'static str> {
+    let mut state = MasterBoard::new(2, &Setup::base(), SRng::new(0))?;
+    let p = state.current_player;
+    let o = (p + 1) % 2;
+    state.mats[p].hand = vec![Card::TradingPost, Card::BattleBlob, Card::BlobFighter];
+    state.mats[o].field = vec![
+        CardInField::new(Card::BlobWheel),
+        CardInField::new(Card::BattleStation),
+    ];
+    let mut board = state.scoped_to(p);
+
+    board += state.do_action(PlayerAction::Play(0, vec![]))?;
+    board += state.do_action(PlayerAction::Play(0, vec![]))?;
+    board += state.do_action(PlayerAction::Play(0, vec![]))?;
+    assert_eq!(state.mats[p].trade, 0);
+    state
+        .do_action(PlayerAction::ActivateExpendAbility(0, vec![]))
+        .expect_err("Expected choice");
+    board += state.do_action(PlayerAction::ActivateExpendAbility(
+        0,
+        vec![EffectArgument::ChooseSecond],
+    ))?;
+    assert_eq!(state.mats[p].trade, 1);
+    state
+        .do_action(PlayerAction::ActivateExpendAbility(
+            0,
+            vec![EffectArgument::ChooseSecond],
+        ))
+        .expect_err("Base already used this turn");
+
+    state
+        .do_action(PlayerAction::AttackPlayer(o, 1))
+        .expect_err("Can't attack player with outposts");
+    state
+        .do_action(PlayerAction::AttackPlayerChampion(o, 0))
+        .expect_err("Can't attack base with outposts");
+    board += state.do_action(PlayerAction::AttackPlayerChampion(o, 1))?;
+    board += state.do_action(PlayerAction::AttackPlayerChampion(o, 0))?;
+    board += state.do_action(PlayerAction::AttackPlayer(o, 1))?;
+    {
+        assert_eq!(state.mats[p].combat, 0);
+        assert_eq!(state.mats[o].authority, 49);
+        assert_eq!(state.mats[o].discard.len(), 2);
+    }
+
+    board += state.do_action(PlayerAction::EndTurn)?;
+    {
+        assert_eq!(state.mats[p].field.len(), 1);
+        assert_eq!(state.mats[p].field[0].card, Card::TradingPost);
+        assert!(!state.mats[p].field[0].expend_ability_used);
+    }
+    assert_eq!(board, state.scoped_to(p));
+    Ok(())
+}
+
+#[test]
+fn test_explorers() -> Result<(), &'static str> {
+    let mut state = MasterBoard::new(2, &Setup::base(), SRng::new(14279))?;
+    let p = state.current_player;
+    state.mats[p].hand = vec![Card::Explorer];
+    state.explorers -= 1;
+    let mut board = state.scoped_to(p);
+
+    board += state.do_action(PlayerAction::Play(0, vec![]))?;
+    board += state.do_action(PlayerAction::PurchaseFireGem)?;
+    {
+        assert_eq!(state.explorers, 8);
+        assert_eq!(state.mats[p].discard, vec![Card::Explorer]);
+    }
+    board += state.do_action(PlayerAction::ActivateSacrificeAbility(0, vec![]))?;
+    {
+        assert_eq!(state.explorers, 9);
+        assert_eq!(state.mats[p].combat, 2);
+        assert!(state.scrapped.is_empty());
+    }
+    state
+        .do_action(PlayerAction::PurchaseFireGem)
+        .expect_err("Not enough trade");
+    assert_eq!(board, state.scoped_to(p));
+    Ok(())
+}
+
+#[test]
+fn test_opponent_discards() -> Result<(), &'static str> {
+    let mut state = MasterBoard::new(2, &Setup::base(), SRng::new(777))?;
+    let p = state.current_player;
+    let o = (p + 1) % 2;
+    state.mats[p].hand = vec![Card::ImperialFighter, Card::TradeBot];
+    state.mats[p].discard = vec![Card::Viper];
+
+    state
+        .do_action(PlayerAction::Play(0, vec![EffectArgument::Opponent(p)]))
+        .expect_err("Expected opponent");
+    state.do_action(PlayerAction::Play(0, vec![EffectArgument::Opponent(o)]))?;
+    state.do_action(PlayerAction::Play(
+        0,
+        vec![EffectArgument::CardInDiscard(0)],
+    ))?;
+    {
+        assert_eq!(state.mats[o].must_discard, 1);
+        assert_eq!(state.scrapped, vec![Card::Viper]);
+        assert_eq!(state.mats[p].trade, 1);
+    }
+
+    state.do_action(PlayerAction::EndTurn)?;
+    state
+        .do_action(PlayerAction::Play(0, vec![]))
+        .expect_err("Must discard first");
+    state.do_action(PlayerAction::Discard(0))?;
+    assert_eq!(state.mats[o].hand.len(), 4);
+    assert_eq!(state.mats[o].discard.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_elimination() -> Result<(), &'static str> {
+    let mut state = MasterBoard::new(3, &Setup::base(), SRng::new(777))?;
+    let p = state.current_player;
+    let o = (p + 1) % 3;
+    let last = (p + 2) % 3;
+    state.mats[p].combat = 51;
+    state.mats[p].hand = vec![Card::ImperialFighter];
+    state.mats[o].field = vec![CardInField::new(Card::BlobWheel)];
+    let mut board = state.scoped_to(p);
+
+    // Players out of authority are out of the game
+    board += state.do_action(PlayerAction::AttackPlayer(o, 50))?;
+    assert!(state.mats[o].field.is_empty());
+    assert!(state.mats[o].hand.is_empty());
+    assert!(!state.game_over);
+    assert_eq!(board, state.scoped_to(p));
+    state
+        .do_action(PlayerAction::AttackPlayer(o, 1))
+        .expect_err("Player already eliminated");
+    state
+        .do_action(PlayerAction::Play(0, vec![EffectArgument::Opponent(o)]))
+        .expect_err("Expected opponent");
+
+    board += state.do_action(PlayerAction::EndTurn)?;
+    assert_eq!(state.current_player, last);
+    board += state.do_action(PlayerAction::Concede)?;
+    assert!(state.mats[last].hand.is_empty());
+    assert!(state.game_over);
+    assert_eq!(board, state.scoped_to(p));
+    Ok(())
+}
+
+fn player_range<G: Game>(setup: &G::Setup) -> (usize, usize) {
+    (setup.min_players(), setup.max_players())
+}
+
+#[test]
+fn test_same_lobby_for_both_games() -> Result<(), &'static str> {
+    use crate::model::cards;
+    assert_eq!(player_range::<StarRealms>(&Setup::base()), (2, 4));
+    assert_eq!(player_range::<HeroRealms>(&cards::Setup::base()), (2, 4));
+
+    let mut api = HotSeat::new(2, &Setup::base(), SRng::new(0))?;
+    let first = api.get_board().current_player;
+    assert_eq!(api.get_board().you, first);
+    api.do_action(PlayerAction::EndTurn)?;
+    assert_ne!(api.get_board().current_player, first);
+    assert_eq!(api.get_board().you, api.get_board().current_player);
+    assert!(!api.poll_deltas().is_empty());
+    assert!(api.poll_deltas().is_empty());
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use super::{
+    api::{ClientMessage, GameCard, PlayerAction, ServerMessage},
+    branches::GameTree,
+    campaign::Campaign,
+    cards::Setup,
+    journal::Journal,
+    master::MasterBoard,
+};
+use crate::smallrng::{Algorithm, SRng};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+// Bumped whenever the json of a saved or sent type changes, along with a
+// migration from the version before:
+//
+// 1. Journals list their actions without the seat acting
+// 2. Actions come with their seat
+// 3. Journals keep snapshots, their boards don't count actions
+// 4. Everything is sent and saved in an envelope, boards count their actions
+// 5. Random generators name their algorithm, the ones before were all legacy
+//
+// Versions before 4 had no envelope, so their data is told apart by its shape.
+pub const VERSION: u32 = 5;
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    version: u32,
+    kind: String,
+    data: Value,
+}
+
+pub trait Versioned: Serialize + DeserializeOwned {
+    // Keeps one kind of data from being loaded as another
+    const KIND: &'static str;
+
+    // The version of data saved without an envelope
+    fn unversioned(_data: &Value) -> u32 {
+        3
+    }
+
+    // Brings data saved with the version to the next one
+    fn migrate(version: u32, mut data: Value) -> Result<Value, &'static str> {
+        if version == 4 {
+            mark_legacy_rngs(&mut data);
+        }
+        Ok(data)
+    }
+}
+
+pub fn to_string<T: Versioned>(value: &T) -> Result<String, &'static str> {
+    let envelope = Envelope {
+        version: VERSION,
+        kind: T::KIND.into(),
+        data: serde_json::to_value(value).map_err(|_| "Could not serialize data")?,
+    };
+    serde_json::to_string(&envelope).map_err(|_| "Could not serialize data")
+}
+
+pub fn from_str<T: Versioned>(text: &str) -> Result<T, &'static str> {
+    let value: Value = serde_json::from_str(text).map_err(|_| "Corrupted data")?;
+    let (mut version, mut data) = match serde_json::from_value::<Envelope>(value.clone()) {
+        Ok(envelope) if envelope.kind != T::KIND => return Err("Data of another kind"),
+        Ok(envelope) => (envelope.version, envelope.data),
+        Err(_) => (T::unversioned(&value), value),
+    };
+    if version > VERSION {
+        return Err("Data from a newer version");
+    }
+    while version < VERSION {
+        data = T::migrate(version, data)?;
+        version += 1;
+    }
+    serde_json::from_value(data).map_err(|_| "Corrupted data")
+}
+
+impl Versioned for Journal {
+    const KIND: &'static str = "journal";
+
+    fn unversioned(data: &Value) -> u32 {
+        if data.get("snapshots").is_some() {
+            3
+        } else if matches!(
+            data["actions"].get(0),
+            Some(Value::Object(_)) | Some(Value::String(_))
+        ) {
+            1
+        } else {
+            2
+        }
+    }
+
+    fn migrate(version: u32, mut data: Value) -> Result<Value, &'static str> {
+        match version {
+            // The seat is the one of the current player, conceding out of turn
+            // came later
+            1 => {
+                let players = parse(&data["players"])?;
+                let setup: Setup = parse(&data["setup"])?;
+                let rng = SRng::legacy(parse(&data["rng"]["seed"])?);
+                let actions: Vec<PlayerAction> = parse(&data["actions"])?;
+                let mut board = MasterBoard::new(players, &setup, rng)?;
+                let mut seated = vec![];
+                for action in actions {
+                    seated.push((board.current_player, action.clone()));
+                    board.do_action(action)?;
+                }
+                data["actions"] = to_value(&seated)?;
+            }
+            // What loading them used to default to
+            2 => {
+                data["snapshots"] = Value::Array(vec![]);
+                data["snapshot_interval"] = Value::from(0);
+            }
+            // Snapshots are taken before the action at their index
+            3 => {
+                let snapshots = data["snapshots"].as_array_mut().ok_or("Corrupted data")?;
+                for snapshot in snapshots.iter_mut() {
+                    snapshot["board"]["action_count"] = snapshot["at"].clone();
+                }
+            }
+            4 => mark_legacy_rngs(&mut data),
+            _ => {}
+        }
+        Ok(data)
+    }
+}
+
+// Its journal is the only part that changed so far
+impl Versioned for GameTree {
+    const KIND: &'static str = "game_tree";
+
+    fn unversioned(data: &Value) -> u32 {
+        Journal::unversioned(&data["root"])
+    }
+
+    fn migrate(version: u32, mut data: Value) -> Result<Value, &'static str> {
+        data["root"] = Journal::migrate(version, data["root"].take())?;
+        Ok(data)
+    }
+}
+
+impl Versioned for Campaign {
+    const KIND: &'static str = "campaign";
+}
+
+impl<C: GameCard> Versioned for ServerMessage<C> {
+    const KIND: &'static str = "server_message";
+}
+
+impl Versioned for ClientMessage {
+    const KIND: &'static str = "client_message";
+}
+
+// Generators were saved as nothing but their seed
+fn mark_legacy_rngs(value: &mut Value) {
+    match value {
+        Value::Object(map) if map.len() == 1 && map.contains_key("seed") => {
+            map.insert("algorithm".into(), to_value(&Algorithm::Legacy).unwrap());
+        }
+        Value::Object(map) => map.values_mut().for_each(mark_legacy_rngs),
+        Value::Array(values) => values.iter_mut().for_each(mark_legacy_rngs),
+        _ => {}
+    }
+}
+
+fn parse<T: DeserializeOwned>(value: &Value) -> Result<T, &'static str> {
+    serde_json::from_value(value.clone()).map_err(|_| "Corrupted data")
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, &'static str> {
+    serde_json::to_value(value).map_err(|_| "Could not serialize data")
+}
@@ -0,0 +1,126 @@
+use super::{api::*, branches::*, campaign::*, cards::*, journal::*, recorded_test::*, version::*};
+use crate::smallrng::*;
+
+// The game every journal fixture holds, saved by the version of its name
+fn fixture_game() -> Journal {
+    recorded_game(setup(), SRng::legacy(2024), Strategy::Careless, Some(8)).unwrap()
+}
+
+const JOURNALS: [&str; 5] = [
+    include_str!("fixtures/journal_v1.json"),
+    include_str!("fixtures/journal_v2.json"),
+    include_str!("fixtures/journal_v3.json"),
+    include_str!("fixtures/journal_v4.json"),
+    include_str!("fixtures/journal_v5.json"),
+];
+
+#[test]
+fn test_load_old_journals() -> Result<(), &'static str> {
+    let game = fixture_game();
+    for text in JOURNALS.iter() {
+        let journal: Journal = from_str(text)?;
+        assert_eq!(
+            format!("{:?}", journal.actions),
+            format!("{:?}", game.actions)
+        );
+        assert_eq!(journal.verify()?.state_hash(), game.replay()?.state_hash());
+        for snapshot in journal.snapshots.iter() {
+            assert_eq!(snapshot.board.action_count, snapshot.at);
+        }
+        let saved = to_string(&journal)?;
+        assert!(saved.starts_with(&format!("{{\"version\":{},\"kind\":\"journal\"", VERSION)));
+        assert_eq!(to_string(&from_str::<Journal>(&saved)?)?, saved);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_old_journal_notation() -> Result<(), &'static str> {
+    // Old games keep their generator when written as notation
+    let oldest: Journal = from_str(JOURNALS[0])?;
+    let text = oldest.to_notation()?;
+    assert!(text.contains("\n[Rng \"legacy\"]\n"));
+    let parsed = Journal::from_notation(&text)?;
+    assert_eq!(
+        parsed.replay()?.state_hash(),
+        fixture_game().replay()?.state_hash()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_old_journal_snapshots() -> Result<(), &'static str> {
+    let snapshotted: Journal = from_str(JOURNALS[2])?;
+    assert_eq!(snapshotted.snapshots.len(), 5);
+    assert_eq!(snapshotted.board_at(35)?.action_count, 35);
+    Ok(())
+}
+
+#[test]
+fn test_load_old_trees() -> Result<(), &'static str> {
+    let game = fixture_game();
+    for text in [
+        include_str!("fixtures/game_tree_v2.json"),
+        include_str!("fixtures/game_tree_v4.json"),
+    ]
+    .iter()
+    {
+        let tree: GameTree = from_str(text)?;
+        assert_eq!(
+            format!("{:?}", tree.root.actions),
+            format!("{:?}", game.actions)
+        );
+        assert_eq!(tree.branches.len(), 1);
+        assert_eq!(tree.outcomes()?.len(), 2);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_load_old_campaigns() -> Result<(), &'static str> {
+    for text in [
+        include_str!("fixtures/campaign_v1.json"),
+        include_str!("fixtures/campaign_v4.json"),
+    ]
+    .iter()
+    {
+        let mut campaign: Campaign = from_str(text)?;
+        assert_eq!(campaign.character.name, "Ayla");
+        campaign.start_encounter()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_envelope() -> Result<(), &'static str> {
+    let journal = fixture_game();
+    let saved = to_string(&journal)?;
+    assert_eq!(
+        from_str::<Campaign>(&saved).unwrap_err(),
+        "Data of another kind"
+    );
+    let newer = saved.replacen(&format!("\"version\":{}", VERSION), "\"version\":99", 1);
+    assert_eq!(
+        from_str::<Journal>(&newer).unwrap_err(),
+        "Data from a newer version"
+    );
+    assert_eq!(from_str::<Journal>("{").unwrap_err(), "Corrupted data");
+    Ok(())
+}
+
+#[test]
+fn test_messages() -> Result<(), &'static str> {
+    let message: ServerMessage = ServerMessage::StateHash(42);
+    let sent = to_string(&message)?;
+    assert!(matches!(
+        from_str(&sent)?,
+        ServerMessage::<Card>::StateHash(42)
+    ));
+    let sent = to_string(&ClientMessage::Action(PlayerAction::EndTurn))?;
+    assert!(matches!(
+        from_str(&sent)?,
+        ClientMessage::Action(PlayerAction::EndTurn)
+    ));
+    assert!(from_str::<ClientMessage>(&to_string(&message)?).is_err());
+    Ok(())
+}
@@ -1,47 +1,179 @@
-use rand::rngs::SmallRng;
-use rand::seq::SliceRandom;
-pub use rand::Rng;
-use rand::{Error, RngCore, SeedableRng};
+use rand::{Error, RngCore};
 use serde::{Deserialize, Serialize};
 
+// Everything the rules draw at random goes through here, so recorded seeds
+// replay the same no matter the version of the rand crate.
+//
+// New generators are PCG XSH RR 64/32, see https://www.pcg-random.org, stepping
+// their state on the stream of the reference implementation. The seed is the
+// whole state, so a generator can be written down as a single number.
+//
+// Legacy generators give what the rand 0.7 SmallRng did on 64 bit targets,
+// built again from the seed for every number. Games saved before they were
+// written here were played with them.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Algorithm {
+    Legacy,
+    Pcg32,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct SRng {
     seed: u64,
+    algorithm: Algorithm,
 }
 
 impl SRng {
     pub fn new(seed: u64) -> SRng {
-        SRng { seed }
+        SRng {
+            seed,
+            algorithm: Algorithm::Pcg32,
+        }
+    }
+
+    pub fn legacy(seed: u64) -> SRng {
+        SRng {
+            seed,
+            algorithm: Algorithm::Legacy,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
     }
 
+    // Another generator of the same algorithm, seeded from this one
     pub fn fork(&mut self) -> SRng {
         SRng {
-            seed: self.yield_rng().gen(),
+            seed: self.next_u64(),
+            algorithm: self.algorithm,
         }
     }
 
-    fn yield_rng(&mut self) -> SmallRng {
-        let mut rng = SmallRng::seed_from_u64(self.seed);
-        self.seed = rng.gen();
-        rng
+    pub fn next_u32(&mut self) -> u32 {
+        match self.algorithm {
+            Algorithm::Legacy => self.yield_legacy().next_u64() as u32,
+            Algorithm::Pcg32 => {
+                let mut pcg = Pcg32::new(self.seed, PCG32_STREAM);
+                let x = pcg.next_u32();
+                self.seed = pcg.state;
+                x
+            }
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        match self.algorithm {
+            Algorithm::Legacy => self.yield_legacy().next_u64(),
+            Algorithm::Pcg32 => u64::from(self.next_u32()) | u64::from(self.next_u32()) << 32,
+        }
     }
 
+    // Uniform in `0..bound`, by widening multiplication and rejecting the
+    // values that would favour some results
+    pub fn below(&mut self, bound: u32) -> u32 {
+        assert!(bound > 0, "Empty range");
+        let zone = (bound << bound.leading_zeros()).wrapping_sub(1);
+        loop {
+            let wide = u64::from(self.next_u32()) * u64::from(bound);
+            if wide as u32 <= zone {
+                return (wide >> 32) as u32;
+            }
+        }
+    }
+
+    // Fisher-Yates, from the end of the slice
     pub fn shuffle<T>(&mut self, slice: &mut [T]) {
-        slice.shuffle(self);
+        for i in (1..slice.len()).rev() {
+            slice.swap(i, self.below(i as u32 + 1) as usize);
+        }
+    }
+
+    fn yield_legacy(&mut self) -> Mcg128 {
+        let mut rng = Mcg128::from_u64(self.seed);
+        self.seed = rng.next_u64();
+        rng
     }
 }
 
+// Bots and tests can still use the rand helpers, the rules don't
 impl RngCore for SRng {
     fn next_u32(&mut self) -> u32 {
-        self.yield_rng().next_u32()
+        SRng::next_u32(self)
     }
     fn next_u64(&mut self) -> u64 {
-        self.yield_rng().next_u64()
+        SRng::next_u64(self)
     }
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        self.yield_rng().fill_bytes(dest);
+        for chunk in dest.chunks_mut(8) {
+            let bytes = SRng::next_u64(self).to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
     }
     fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        self.yield_rng().try_fill_bytes(dest)
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+const PCG32_MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+const PCG32_STREAM: u64 = 0xda3e_39cb_94b9_5bdb;
+
+pub(crate) struct Pcg32 {
+    pub state: u64,
+    inc: u64,
+}
+
+impl Pcg32 {
+    pub fn new(state: u64, inc: u64) -> Pcg32 {
+        Pcg32 { state, inc }
+    }
+
+    // Seeded the way pcg32_srandom_r does
+    pub fn seeded(initstate: u64, initseq: u64) -> Pcg32 {
+        let mut pcg = Pcg32::new(0, initseq << 1 | 1);
+        pcg.next_u32();
+        pcg.state = pcg.state.wrapping_add(initstate);
+        pcg.next_u32();
+        pcg
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(PCG32_MULTIPLIER).wrapping_add(self.inc);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+}
+
+// PCG XSL RR 128/64 as a multiplicative generator, seeded from a u64 the way
+// rand_core 0.5 does it
+struct Mcg128 {
+    state: u128,
+}
+
+impl Mcg128 {
+    fn from_u64(seed: u64) -> Mcg128 {
+        let mut expand = Pcg32::new(seed, 11_634_580_027_462_260_723);
+        let mut state = 0;
+        for i in 0..4 {
+            expand.next_u32();
+            let x = expand.state;
+            let word = (((x >> 18) ^ x) >> 27) as u32;
+            state |= u128::from(word.rotate_right((x >> 59) as u32)) << (32 * i);
+        }
+        Mcg128 { state: state | 1 }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self
+            .state
+            .wrapping_mul(0x2360_ED05_1FC6_5DA4_4385_DF64_9FCC_F645);
+        let xsl = ((self.state >> 64) as u64) ^ (self.state as u64);
+        xsl.rotate_right((self.state >> 122) as u32)
     }
 }
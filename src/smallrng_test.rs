@@ -0,0 +1,75 @@
+use crate::smallrng::*;
+
+#[test]
+fn test_pcg32_reference_output() {
+    // First numbers printed by pcg32-demo from the PCG reference implementation
+    let mut pcg = Pcg32::seeded(42, 54);
+    let numbers: Vec<u32> = (0..6).map(|_| pcg.next_u32()).collect();
+    assert_eq!(
+        numbers,
+        vec![0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e]
+    );
+}
+
+#[test]
+fn test_legacy_matches_rand() {
+    // Drawn from rand 0.7 SmallRng through the SRng that reseeded it every time
+    let expected = [
+        (
+            0,
+            15457584781082106573,
+            2159131800,
+            17107146197495673641,
+            [9, 8, 1, 7, 4, 0, 2, 6, 5, 3],
+            8351729803210097311,
+            7931495368426626893,
+        ),
+        (
+            14279,
+            15731356599460561494,
+            2991587252,
+            12672785671687934451,
+            [9, 4, 2, 8, 7, 0, 1, 5, 6, 3],
+            4011534259689371125,
+            8672185428549763056,
+        ),
+    ];
+    for (seed, a, b, c, shuffled, forked, last) in expected.iter() {
+        let mut rng = SRng::legacy(*seed);
+        assert_eq!(rng.next_u64(), *a);
+        assert_eq!(rng.next_u32(), *b);
+        assert_eq!(rng.next_u64(), *c);
+        let mut cards: Vec<usize> = (0..10).collect();
+        rng.shuffle(&mut cards);
+        assert_eq!(cards, shuffled);
+        let fork = rng.fork();
+        assert_eq!(fork.algorithm(), Algorithm::Legacy);
+        assert_eq!((fork.seed(), rng.seed()), (*forked, *last));
+    }
+}
+
+#[test]
+fn test_pcg32_generator() {
+    let mut rng = SRng::new(14279);
+    let mut same = rng.clone();
+    let first = rng.next_u32();
+    assert_eq!(first, Pcg32::new(14279, 0xda3e39cb94b95bdb).next_u32());
+    assert_eq!(same.next_u64() as u32, first);
+
+    // The seed is all there is to the generator
+    let mut resumed = SRng::new(rng.seed());
+    assert_eq!(resumed.next_u64(), rng.next_u64());
+
+    let mut counts = [0; 6];
+    for _ in 0..6000 {
+        counts[rng.below(6) as usize] += 1;
+    }
+    assert!(counts.iter().all(|c| (850..1150).contains(c)));
+
+    let mut cards: Vec<usize> = (0..52).collect();
+    rng.shuffle(&mut cards);
+    assert_ne!(cards, (0..52).collect::<Vec<_>>());
+    cards.sort_unstable();
+    assert_eq!(cards, (0..52).collect::<Vec<_>>());
+    assert_eq!(rng.fork().algorithm(), Algorithm::Pcg32);
+}